@@ -1,4 +1,8 @@
 pub use crate::parser::error::ParseError;
+use crate::value::{Path, PathItem};
+use pest::Position;
+use std::fmt;
+use std::ops::Range;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -14,12 +18,54 @@ pub enum Error {
     Parsing(ParseError),
     #[error("merge conflict at path: {path}")]
     MergeConflict { path: String },
+    #[error("patch operation #{index} failed: {reason}")]
+    PatchFailed { index: usize, reason: String },
+    #[error("{error} at path: {path}")]
+    AtPath { path: String, error: Box<Error> },
+    #[error("{origin}: {error}")]
+    Load { origin: String, error: Box<Error> },
     #[error("invalid field name or enum variant: {0}")]
     InvalidFieldNameOrEnumVariant(String),
     #[error("{0}")]
+    Conversion(Box<ConversionError>),
+    #[error("{0}")]
     Custom(String),
 }
 
+// NOTE: the path is stored as items, as `Path` is not `Send`. The path, the message and the span
+// locate the failure, which can be in a nested value, while the expected type and the actual kind
+// describe the value at the requested path.
+#[derive(Debug, PartialEq)]
+pub struct ConversionError {
+    pub path: Vec<PathItem<'static>>,
+    pub expected: String,
+    // NOTE: `None` if there is no value at the requested path.
+    pub actual: Option<&'static str>,
+    pub message: String,
+    pub span: Option<Range<usize>>,
+    pub source: Option<String>,
+}
+
+impl ConversionError {
+    #[inline]
+    pub fn path(&self) -> Path<'static> {
+        self.path.iter().cloned().collect()
+    }
+
+    pub fn line_col(&self, input: &str) -> Option<(usize, usize)> {
+        let span = self.span.as_ref()?;
+
+        Position::new(input, span.start).map(|pos| pos.line_col())
+    }
+}
+
+impl fmt::Display for ConversionError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at path: {}", self.message, self.path())
+    }
+}
+
 impl Error {
     #[inline]
     pub fn custom<T>(msg: T) -> Self
@@ -32,6 +78,27 @@ impl Error {
 
 #[cfg(feature = "serde")]
 impl Error {
+    // NOTE: errors that happened in nested values are reported with the path of the value.
+    pub(crate) fn without_path(self) -> Self {
+        match self {
+            Error::AtPath { error, .. } => *error,
+            error => error,
+        }
+    }
+
+    pub(crate) fn at_path_item(self, item: &PathItem) -> Self {
+        match self {
+            Error::AtPath { path, error } => Error::AtPath {
+                path: format!("> {item} {path}"),
+                error,
+            },
+            error => Error::AtPath {
+                path: format!("> {item}"),
+                error: Box::new(error),
+            },
+        }
+    }
+
     #[inline]
     pub(crate) fn de_fewer_elements_in_seq(actual_len: usize) -> Self {
         serde::de::Error::invalid_length(actual_len, &"fewer elements in sequence")
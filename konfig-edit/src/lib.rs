@@ -1,6 +1,11 @@
 #![cfg_attr(docs_rs, feature(doc_auto_cfg))]
 
 pub mod error;
+pub mod loader;
 pub mod parser;
+
+#[cfg(feature = "schema")]
+pub mod schema;
+
 pub mod serializer;
 pub mod value;
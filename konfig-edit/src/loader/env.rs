@@ -0,0 +1,364 @@
+use super::shape::{Shape, UNKNOWN};
+use crate::error::{Error, Result};
+use crate::parser::parse_primitive_at;
+use crate::value::{Path, PathItem, Value};
+
+// NOTE: maps environment variables onto paths, e.g. with the `APP__` prefix and the default `__`
+// separator `APP__DB__POOL_SIZE=50` is `> db > pool_size = 50`. Names are lowercased and numeric
+// names are sequence indices. Values are parsed as konfig primitives, falling back to strings.
+//
+// Without a type, all names are structure field names. With a type, the names are matched against
+// the serde names of fields and variants case-insensitively, map keys are told apart from fields
+// and values of string fields are never parsed.
+pub struct Env {
+    prefix: String,
+    separator: String,
+    vars: Option<Vec<(String, String)>>,
+}
+
+impl Env {
+    #[inline]
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            separator: "__".into(),
+            vars: None,
+        }
+    }
+
+    #[inline]
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    // NOTE: uses the given variables instead of the variables of the current process.
+    pub fn vars<K, V>(mut self, vars: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.vars = Some(
+            vars.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    #[inline]
+    pub fn to_value(&self) -> Result<Option<Value>> {
+        self.to_value_with_shape(&Shape::Unknown)
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn to_value_for<T>(&self) -> Result<Option<Value>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.to_value_with_shape(&super::shape::shape_of::<T>())
+    }
+
+    #[inline]
+    pub(crate) fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    // NOTE: values have the names of the variables they came from as the source in their
+    // lexical info.
+    pub(crate) fn to_value_with_shape(&self, shape: &Shape) -> Result<Option<Value>> {
+        let vars = match &self.vars {
+            Some(vars) => vars.clone(),
+            None => process_vars(&self.prefix)?,
+        };
+
+        let mut assignments = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let names = name.strip_prefix(&self.prefix)?;
+
+                Some(self.assignment(shape, names, &value).map(|(path, value)| {
+                    let key = sort_key(&path);
+
+                    (key, path, name, value)
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if assignments.is_empty() {
+            return Ok(None);
+        }
+
+        // NOTE: sequence items need to be defined in order.
+        assignments.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut value = Value::unflatten(
+            assignments
+                .iter()
+                .map(|(_, path, _, value)| (path.clone(), value.clone())),
+        )?;
+
+        for (_, path, name, _) in assignments {
+            if let Some(cell) = value.get_path_mut(path.items()) {
+                cell.lexical_info_mut().source = Some(name.into());
+            }
+        }
+
+        Ok(Some(value))
+    }
+
+    fn assignment(
+        &self,
+        shape: &Shape,
+        names: &str,
+        value: &str,
+    ) -> Result<(Path<'static>, Value)> {
+        let mut shape = shape;
+        let mut path = Path::default();
+
+        for name in names.split(self.separator.as_str()) {
+            if name.is_empty() {
+                return Err(Error::custom(format!(
+                    "invalid environment variable name: {}{names}",
+                    self.prefix
+                )));
+            }
+
+            let (item, item_shape) = path_item(shape, name);
+
+            path.push(item);
+            shape = item_shape;
+        }
+
+        let value = match shape {
+            Shape::String => Value::String(value.into()),
+            _ => parse_primitive(value),
+        };
+
+        Ok((path, value))
+    }
+}
+
+fn path_item<'s>(shape: &'s Shape, name: &str) -> (PathItem<'static>, &'s Shape) {
+    let find = |names: &'s [(&'static str, Shape)]| {
+        names
+            .iter()
+            .find(|(candidate, _)| candidate.eq_ignore_ascii_case(name))
+    };
+
+    let field = || PathItem::StructFieldName(name.to_lowercase().into());
+
+    match shape {
+        Shape::Struct(fields) => match find(fields) {
+            Some((field, shape)) => (PathItem::StructFieldName((*field).into()), shape),
+            None => (field(), &UNKNOWN),
+        },
+        Shape::Enum(variants) => match find(variants) {
+            Some((variant, shape)) => (PathItem::VariantName((*variant).into()), shape),
+            None => (field(), &UNKNOWN),
+        },
+        Shape::Map(value) => (PathItem::MapKey(name.to_lowercase().into()), value),
+        Shape::Sequence(elem) => match name.parse() {
+            Ok(idx) => (PathItem::SequenceIndex(idx), elem),
+            Err(_) => (field(), &UNKNOWN),
+        },
+        Shape::Unknown | Shape::String => match name.parse() {
+            Ok(idx) => (PathItem::SequenceIndex(idx), &UNKNOWN),
+            Err(_) => (field(), &UNKNOWN),
+        },
+    }
+}
+
+// NOTE: variables without the prefix are skipped, even if they are not valid Unicode.
+fn process_vars(prefix: &str) -> Result<Vec<(String, String)>> {
+    std::env::vars_os()
+        .filter(|(name, _)| name.as_encoded_bytes().starts_with(prefix.as_bytes()))
+        .map(|(name, value)| {
+            let name = name.into_string().map_err(|name| {
+                Error::custom(format!(
+                    "environment variable name is not valid Unicode: {}",
+                    name.to_string_lossy()
+                ))
+            })?;
+
+            let value = value.into_string().map_err(|_| {
+                Error::custom(format!(
+                    "value of environment variable {name} is not valid Unicode"
+                ))
+            })?;
+
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn parse_primitive(value: &str) -> Value {
+    match parse_primitive_at(value, 0) {
+        Ok((primitive, end)) if end == value.len() => primitive,
+        _ => Value::String(value.into()),
+    }
+}
+
+fn sort_key(path: &Path) -> Vec<(usize, String)> {
+    path.items()
+        .iter()
+        .map(|item| match item {
+            PathItem::SequenceIndex(idx) => (*idx, String::new()),
+            item => (0, item.to_string()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serializer::serialize;
+    use indoc::indoc;
+
+    fn to_konfig(value: Option<Value>) -> String {
+        serialize(&value.unwrap().into_cell(), Default::default()).unwrap()
+    }
+
+    #[test]
+    fn untyped() {
+        let env = Env::new("APP__").vars([
+            ("APP__DB__POOL_SIZE", "50"),
+            ("APP__SERVERS__1__HOST", "b"),
+            ("APP__SERVERS__0__HOST", "a"),
+            ("APP__SERVERS__0__TLS", "true"),
+            ("APP__NAME", "my app"),
+            ("APP__GREETING", "\"quoted\""),
+            ("OTHER__NAME", "ignored"),
+        ]);
+
+        assert_eq!(
+            to_konfig(env.to_value().unwrap()),
+            indoc! {"
+                > db > pool_size = 50
+
+                > greeting = \"quoted\"
+
+                > name = \"my app\"
+
+                > servers > [0] > host = \"a\"
+
+                > servers > [0] > tls = true
+
+                > servers > [1] > host = \"b\"\
+            "}
+        );
+
+        let value = env.to_value().unwrap().unwrap();
+
+        assert_eq!(
+            value["db"]["pool_size"].lexical_info().source.as_deref(),
+            Some("APP__DB__POOL_SIZE")
+        );
+
+        assert_eq!(
+            Env::new("NONE__").vars([("APP__X", "1")]).to_value(),
+            Ok(None)
+        );
+
+        assert_eq!(
+            Env::new("APP_")
+                .separator("_")
+                .vars([("APP_DB__X", "1")])
+                .to_value()
+                .unwrap_err()
+                .to_string(),
+            "invalid environment variable name: APP_DB__X"
+        );
+
+        assert!(Env::new("APP__")
+            .vars([("APP__SERVERS__1__HOST", "b")])
+            .to_value()
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_vars_not_unicode() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let invalid = OsStr::from_bytes(b"\xff");
+
+        std::env::set_var("KONFIG_ENV_TEST__PORT", "80");
+        std::env::set_var(OsStr::from_bytes(b"KONFIG_ENV_TEST_\xff"), "1");
+
+        assert_eq!(
+            to_konfig(Env::new("KONFIG_ENV_TEST__").to_value().unwrap()),
+            "> port = 80"
+        );
+
+        std::env::set_var("KONFIG_ENV_TEST__NAME", invalid);
+
+        assert_eq!(
+            Env::new("KONFIG_ENV_TEST__")
+                .to_value()
+                .unwrap_err()
+                .to_string(),
+            "value of environment variable KONFIG_ENV_TEST__NAME is not valid Unicode"
+        );
+
+        std::env::remove_var("KONFIG_ENV_TEST__NAME");
+        std::env::set_var(OsStr::from_bytes(b"KONFIG_ENV_TEST__\xff"), "1");
+
+        assert_eq!(
+            Env::new("KONFIG_ENV_TEST__")
+                .to_value()
+                .unwrap_err()
+                .to_string(),
+            "environment variable name is not valid Unicode: KONFIG_ENV_TEST__\u{fffd}"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn typed() {
+        use std::collections::HashMap;
+
+        #[derive(serde::Deserialize)]
+        #[allow(dead_code)]
+        enum Storage {
+            Disk { path: String },
+            Memory,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Config {
+            #[serde(rename = "poolSize")]
+            pool_size: Option<u32>,
+            version: String,
+            labels: HashMap<String, String>,
+            ports: Vec<u16>,
+            storage: Storage,
+        }
+
+        let env = Env::new("APP_").vars([
+            ("APP_POOLSIZE", "50"),
+            ("APP_VERSION", "1.0"),
+            ("APP_LABELS__TEAM", "core"),
+            ("APP_PORTS__0", "80"),
+            ("APP_STORAGE__DISK__PATH", "42"),
+        ]);
+
+        assert_eq!(
+            to_konfig(env.to_value_for::<Config>().unwrap()),
+            indoc! {"
+                > labels > [\"team\"] = \"core\"
+
+                > poolSize = 50
+
+                > ports = [80]
+
+                > storage > `Disk` > path = \"42\"
+
+                > version = \"1.0\"\
+            "}
+        );
+    }
+}
@@ -0,0 +1,323 @@
+mod env;
+mod shape;
+
+#[cfg(feature = "watch")]
+mod watch;
+
+use self::shape::Shape;
+use crate::error::{Error, Result};
+use crate::parser::parse;
+use crate::value::merge::{MergeConflictResolver, MergeOptions, SequenceMergeStrategy};
+use crate::value::{Path, Value};
+use pest::Position;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub use self::env::Env;
+
+#[cfg(feature = "watch")]
+pub use self::watch::{WatchHandle, Watcher, WeakWatchHandle};
+
+enum Source {
+    File { path: PathBuf, required: bool },
+    Konfig { name: String, konfig: String },
+    Value { name: String, value: Value },
+    Env(Env),
+}
+
+// NOTE: a layer is merged on top of the previous ones. Conflicts are resolved in favour of the
+// layer, unless it has a custom resolver. Sequences of the layer replace the previous ones, unless
+// the layer has custom merge options, e.g. with removal markers.
+pub struct Layer {
+    source: Source,
+    resolver: Option<Box<dyn MergeConflictResolver<Error>>>,
+    merge_options: MergeOptions,
+}
+
+impl Layer {
+    #[inline]
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::new(Source::File {
+            path: path.into(),
+            required: true,
+        })
+    }
+
+    #[inline]
+    pub fn optional_file(path: impl Into<PathBuf>) -> Self {
+        Self::new(Source::File {
+            path: path.into(),
+            required: false,
+        })
+    }
+
+    // NOTE: e.g. overrides from the command line, like `--set "> server > port = 8080"`.
+    #[inline]
+    pub fn konfig(name: impl Into<String>, konfig: impl Into<String>) -> Self {
+        Self::new(Source::Konfig {
+            name: name.into(),
+            konfig: konfig.into(),
+        })
+    }
+
+    #[inline]
+    pub fn value(name: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::new(Source::Value {
+            name: name.into(),
+            value: value.into(),
+        })
+    }
+
+    #[inline]
+    pub fn env(env: Env) -> Self {
+        Self::new(Source::Env(env))
+    }
+
+    #[inline]
+    pub fn resolver(mut self, resolver: impl MergeConflictResolver<Error> + 'static) -> Self {
+        self.resolver = Some(Box::new(resolver));
+        self
+    }
+
+    #[inline]
+    pub fn merge_options(mut self, options: MergeOptions) -> Self {
+        self.merge_options = options;
+        self
+    }
+
+    #[inline]
+    fn new(source: Source) -> Self {
+        Self {
+            source,
+            resolver: None,
+            merge_options: MergeOptions::default().sequences(SequenceMergeStrategy::Replace),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Loader {
+    layers: Vec<Layer>,
+}
+
+impl Loader {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn layer(mut self, layer: Layer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    #[inline]
+    pub fn file(self, path: impl Into<PathBuf>) -> Self {
+        self.layer(Layer::file(path))
+    }
+
+    #[inline]
+    pub fn optional_file(self, path: impl Into<PathBuf>) -> Self {
+        self.layer(Layer::optional_file(path))
+    }
+
+    #[inline]
+    pub fn konfig(self, name: impl Into<String>, konfig: impl Into<String>) -> Self {
+        self.layer(Layer::konfig(name, konfig))
+    }
+
+    #[inline]
+    pub fn value(self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.layer(Layer::value(name, value))
+    }
+
+    #[inline]
+    pub fn env(self, env: Env) -> Self {
+        self.layer(Layer::env(env))
+    }
+
+    // NOTE: values of the result have the name of the layer they came from as the source in
+    // their lexical info.
+    #[inline]
+    pub fn load_value(&self) -> Result<Value> {
+        self.load_layers(&Shape::Unknown).map(|(value, _)| value)
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn load<T>(&self) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.load_with_value().map(|(loaded, _)| loaded)
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn load_with_value<T>(&self) -> Result<(T, Value)>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        // NOTE: the type is used to map environment variables onto its fields.
+        let (value, texts) = self.load_layers(&shape::shape_of::<T>())?;
+
+        match crate::value::from_value_ref(&value) {
+            Ok(loaded) => Ok((loaded, value)),
+            Err(err) => Err(located(&texts, &value, None, err)),
+        }
+    }
+
+    #[cfg(feature = "watch")]
+    pub(crate) fn files(&self) -> impl Iterator<Item = &std::path::Path> {
+        self.layers.iter().filter_map(|layer| match &layer.source {
+            Source::File { path, .. } => Some(path.as_path()),
+            _ => None,
+        })
+    }
+
+    fn load_layers(&self, shape: &Shape) -> Result<(Value, Texts)> {
+        let mut texts = Texts::new();
+        let mut merged: Option<Value> = None;
+
+        for layer in &self.layers {
+            let Some((name, mut value)) = layer.source.load(&mut texts, shape)? else {
+                continue;
+            };
+
+            // NOTE: values of environment variables have the names of the variables as sources.
+            if !matches!(layer.source, Source::Env(_)) {
+                value.set_source(Arc::clone(&name));
+            }
+
+            let Some(current) = merged.take() else {
+                merged = Some(value);
+                continue;
+            };
+
+            let other = value.clone_with_lexical_info();
+
+            let options = &layer.merge_options;
+
+            let result = match &layer.resolver {
+                Some(resolver) => current.merge_with_options(&**resolver, value, options),
+                None => current.merge_with_options(prefer_other, value, options),
+            };
+
+            merged = Some(result.map_err(|err| located(&texts, &other, Some(&name), err))?);
+        }
+
+        merged
+            .map(|value| (value, texts))
+            .ok_or_else(|| Error::custom("none of the configuration sources exist"))
+    }
+}
+
+type Texts = HashMap<Arc<str>, String>;
+
+impl Source {
+    fn load(&self, texts: &mut Texts, shape: &Shape) -> Result<Option<(Arc<str>, Value)>> {
+        let (name, konfig) = match self {
+            Source::File { path, required } => {
+                let name: Arc<str> = Arc::from(path.display().to_string());
+
+                let konfig = match fs::read_to_string(path) {
+                    Ok(konfig) => konfig,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound && !required => {
+                        return Ok(None)
+                    }
+                    Err(err) => {
+                        return Err(Error::Load {
+                            origin: name.to_string(),
+                            error: Box::new(Error::custom(err)),
+                        })
+                    }
+                };
+
+                (name, konfig)
+            }
+            Source::Konfig { name, konfig } => (Arc::from(name.as_str()), konfig.clone()),
+            Source::Value { name, value } => {
+                return Ok(Some((
+                    Arc::from(name.as_str()),
+                    value.clone_with_lexical_info(),
+                )))
+            }
+            Source::Env(env) => {
+                let name: Arc<str> = Arc::from(format!("env {}*", env.prefix()));
+
+                return env
+                    .to_value_with_shape(shape)
+                    .map(|value| value.map(|value| (Arc::clone(&name), value)))
+                    .map_err(|err| Error::Load {
+                        origin: name.to_string(),
+                        error: Box::new(err),
+                    });
+            }
+        };
+
+        let value = parse(&konfig).map_err(|err| Error::Load {
+            origin: name.to_string(),
+            error: Box::new(err),
+        })?;
+
+        texts.insert(Arc::clone(&name), konfig);
+
+        Ok(Some((name, value.into_value())))
+    }
+}
+
+fn prefer_other(_: &Path, _: Value, other: Value) -> Result<Value> {
+    Ok(other)
+}
+
+// NOTE: reports the error with the source and the location of the value at the path of the
+// error. Values don't always have spans, e.g. elements of sequences, so the closest ancestor with
+// a span is used for the location. If the source is unknown, the error is reported with the
+// fallback origin, if any.
+fn located(texts: &Texts, value: &Value, fallback_origin: Option<&str>, error: Error) -> Error {
+    let path = match &error {
+        Error::AtPath { path, .. } | Error::MergeConflict { path } => path.parse::<Path>().ok(),
+        _ => None,
+    };
+
+    let items = path.as_ref().map(Path::items).unwrap_or_default();
+
+    let infos = (1..=items.len())
+        .rev()
+        .filter_map(|len| value.get_path(&items[..len]))
+        .map(|cell| cell.lexical_info())
+        .filter(|info| info.source.is_some())
+        .collect::<Vec<_>>();
+
+    let info = infos
+        .iter()
+        .find(|info| info.span.is_some())
+        .or(infos.first());
+
+    let origin = match info.and_then(|info| Some((info.source.as_ref()?, info))) {
+        Some((source, info)) => {
+            let location = info
+                .span
+                .as_ref()
+                .and_then(|span| Position::new(texts.get(source)?, span.start))
+                .map(|pos| pos.line_col());
+
+            match location {
+                Some((line, col)) => format!("{source}:{line}:{col}"),
+                None => source.to_string(),
+            }
+        }
+        None => match fallback_origin {
+            Some(origin) => origin.to_string(),
+            None => return error,
+        },
+    };
+
+    Error::Load {
+        origin,
+        error: Box::new(error),
+    }
+}
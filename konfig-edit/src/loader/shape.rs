@@ -0,0 +1,372 @@
+// NOTE: the shape of a Rust type as seen by serde. It's used to map flat sources, like
+// environment variables, onto paths, as the type of a container can't be inferred from the names
+// of variables alone.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+pub(crate) enum Shape {
+    #[default]
+    Unknown,
+    String,
+    Struct(Vec<(&'static str, Shape)>),
+    Map(Box<Shape>),
+    Sequence(Box<Shape>),
+    Enum(Vec<(&'static str, Shape)>),
+}
+
+pub(crate) static UNKNOWN: Shape = Shape::Unknown;
+
+#[cfg(feature = "serde")]
+pub(crate) use self::probe::shape_of;
+
+#[cfg(feature = "serde")]
+mod probe {
+    use super::Shape;
+    use crate::error::{Error, Result};
+    use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+    use serde::forward_to_deserialize_any;
+
+    macro_rules! deserialize_with {
+        ( $( $de_fn:ident => $vis_fn:ident($probe_value:expr) ),+ ) => {
+            $(
+                #[inline]
+                fn $de_fn<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                    visitor.$vis_fn($probe_value)
+                }
+            )+
+        };
+    }
+
+    // NOTE: recursive types are only probed up to this depth.
+    const MAX_DEPTH: usize = 32;
+
+    // NOTE: the type is "deserialized" from a probe which records the requested containers.
+    // Only the first variant of enums is probed, as serde requests a single variant. Errors, e.g.
+    // of types that reject the probe values, stop the probing, but everything recorded before
+    // them is kept.
+    pub(crate) fn shape_of<T>() -> Shape
+    where
+        T: de::DeserializeOwned,
+    {
+        let mut shape = Shape::Unknown;
+        let _ = T::deserialize(Probe::new(&mut shape, 0));
+
+        shape
+    }
+
+    struct Probe<'s> {
+        shape: &'s mut Shape,
+        depth: usize,
+    }
+
+    impl<'s> Probe<'s> {
+        #[inline]
+        fn new(shape: &'s mut Shape, depth: usize) -> Self {
+            Self { shape, depth }
+        }
+
+        fn check_depth(&self) -> Result<()> {
+            if self.depth > MAX_DEPTH {
+                Err(de::Error::custom("max probing depth exceeded"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl<'de> de::Deserializer<'de> for Probe<'_> {
+        type Error = Error;
+
+        #[inline]
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            visitor.visit_unit()
+        }
+
+        #[inline]
+        fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            visitor.visit_bool(false)
+        }
+
+        deserialize_with! {
+            deserialize_i8 => visit_i64(1),
+            deserialize_i16 => visit_i64(1),
+            deserialize_i32 => visit_i64(1),
+            deserialize_i64 => visit_i64(1),
+            deserialize_i128 => visit_i64(1),
+            deserialize_u8 => visit_u64(1),
+            deserialize_u16 => visit_u64(1),
+            deserialize_u32 => visit_u64(1),
+            deserialize_u64 => visit_u64(1),
+            deserialize_u128 => visit_u64(1),
+            deserialize_f32 => visit_f64(1.0),
+            deserialize_f64 => visit_f64(1.0),
+            deserialize_char => visit_char('_')
+        }
+
+        #[inline]
+        fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            *self.shape = Shape::String;
+            visitor.visit_str("")
+        }
+
+        #[inline]
+        fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            self.deserialize_str(visitor)
+        }
+
+        #[inline]
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            visitor.visit_some(self)
+        }
+
+        #[inline]
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            self.deserialize_tuple(1, visitor)
+        }
+
+        fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+            self.check_depth()?;
+
+            *self.shape = Shape::Sequence(Default::default());
+
+            let Shape::Sequence(elem) = self.shape else {
+                unreachable!();
+            };
+
+            visitor.visit_seq(SeqProbe {
+                elem: Some(elem),
+                depth: self.depth + 1,
+                remaining: len,
+            })
+        }
+
+        #[inline]
+        fn deserialize_tuple_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value> {
+            self.deserialize_tuple(len, visitor)
+        }
+
+        fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            self.check_depth()?;
+
+            *self.shape = Shape::Map(Default::default());
+
+            let Shape::Map(value) = self.shape else {
+                unreachable!();
+            };
+
+            visitor.visit_map(MapProbe {
+                value,
+                depth: self.depth + 1,
+                remaining: 1,
+            })
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value> {
+            self.check_depth()?;
+
+            *self.shape = Shape::Struct(fields.iter().map(|f| (*f, Shape::Unknown)).collect());
+
+            let Shape::Struct(fields) = self.shape else {
+                unreachable!();
+            };
+
+            visitor.visit_map(StructProbe {
+                fields: fields.iter_mut(),
+                next: None,
+                depth: self.depth + 1,
+            })
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value> {
+            self.check_depth()?;
+
+            *self.shape = Shape::Enum(variants.iter().map(|v| (*v, Shape::Unknown)).collect());
+
+            let Shape::Enum(variants) = self.shape else {
+                unreachable!();
+            };
+
+            match variants.first_mut() {
+                Some((name, shape)) => visitor.visit_enum(EnumProbe {
+                    name,
+                    shape,
+                    depth: self.depth + 1,
+                }),
+                None => Err(de::Error::custom("enum has no variants")),
+            }
+        }
+
+        forward_to_deserialize_any! {
+            bytes byte_buf
+            unit unit_struct
+            identifier ignored_any
+        }
+    }
+
+    struct SeqProbe<'s> {
+        elem: Option<&'s mut Shape>,
+        depth: usize,
+        remaining: usize,
+    }
+
+    impl<'de> de::SeqAccess<'de> for SeqProbe<'_> {
+        type Error = Error;
+
+        fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where
+            T: DeserializeSeed<'de>,
+        {
+            if self.remaining == 0 {
+                return Ok(None);
+            }
+
+            // NOTE: all the elements are probed, as tuples require them, but only the shape of
+            // the first one is recorded.
+            let mut scratch = Shape::Unknown;
+            let shape = self.elem.take().unwrap_or(&mut scratch);
+
+            self.remaining -= 1;
+
+            seed.deserialize(Probe::new(shape, self.depth)).map(Some)
+        }
+    }
+
+    struct MapProbe<'s> {
+        value: &'s mut Shape,
+        depth: usize,
+        remaining: usize,
+    }
+
+    impl<'de> de::MapAccess<'de> for MapProbe<'_> {
+        type Error = Error;
+
+        fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where
+            K: DeserializeSeed<'de>,
+        {
+            if self.remaining == 0 {
+                return Ok(None);
+            }
+
+            self.remaining -= 1;
+
+            seed.deserialize(Probe::new(&mut Shape::Unknown, self.depth))
+                .map(Some)
+        }
+
+        fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where
+            V: DeserializeSeed<'de>,
+        {
+            seed.deserialize(Probe::new(self.value, self.depth))
+        }
+    }
+
+    struct StructProbe<'s> {
+        fields: std::slice::IterMut<'s, (&'static str, Shape)>,
+        next: Option<&'s mut Shape>,
+        depth: usize,
+    }
+
+    impl<'de> de::MapAccess<'de> for StructProbe<'_> {
+        type Error = Error;
+
+        fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where
+            K: DeserializeSeed<'de>,
+        {
+            let Some((name, shape)) = self.fields.next() else {
+                return Ok(None);
+            };
+
+            self.next = Some(shape);
+
+            seed.deserialize((*name).into_deserializer()).map(Some)
+        }
+
+        fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where
+            V: DeserializeSeed<'de>,
+        {
+            let shape = self
+                .next
+                .take()
+                .ok_or_else(|| <Error as de::Error>::custom("value is missing"))?;
+
+            seed.deserialize(Probe::new(shape, self.depth))
+        }
+    }
+
+    struct EnumProbe<'s> {
+        name: &'static str,
+        shape: &'s mut Shape,
+        depth: usize,
+    }
+
+    impl<'de, 's> de::EnumAccess<'de> for EnumProbe<'s> {
+        type Error = Error;
+        type Variant = Probe<'s>;
+
+        fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+        where
+            V: DeserializeSeed<'de>,
+        {
+            let variant = seed.deserialize(self.name.into_deserializer())?;
+
+            Ok((variant, Probe::new(self.shape, self.depth)))
+        }
+    }
+
+    impl<'de> de::VariantAccess<'de> for Probe<'_> {
+        type Error = Error;
+
+        #[inline]
+        fn unit_variant(self) -> Result<()> {
+            Ok(())
+        }
+
+        #[inline]
+        fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+        where
+            T: DeserializeSeed<'de>,
+        {
+            seed.deserialize(self)
+        }
+
+        #[inline]
+        fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+            de::Deserializer::deserialize_tuple(self, len, visitor)
+        }
+
+        #[inline]
+        fn struct_variant<V: Visitor<'de>>(
+            self,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value> {
+            de::Deserializer::deserialize_struct(self, "", fields, visitor)
+        }
+    }
+}
@@ -0,0 +1,467 @@
+use super::Loader;
+use crate::error::{Error, Result};
+use crate::value::diff::{diff_with_options, DiffOp, DiffOptions};
+use crate::value::{Path, Value};
+use serde::de::DeserializeOwned;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+type Subscriber<T> = Arc<dyn Fn(&T, &[Path<'static>]) + Send + Sync>;
+
+// NOTE: the modification time and the length of a file may stay the same after an edit, e.g. on
+// file systems with coarse timestamps, so the hash of the contents is compared as well.
+type Fingerprint = Option<(Option<SystemTime>, u64, u64)>;
+
+enum Command {
+    Reload,
+    Stop,
+}
+
+// NOTE: held by the handles only, so the watcher thread is stopped when the last handle is
+// dropped, even if weak handles are held by the subscribers.
+struct Alive {
+    commands: Sender<Command>,
+}
+
+impl Drop for Alive {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Stop);
+    }
+}
+
+struct Shared<T> {
+    current: RwLock<Arc<T>>,
+    error: Mutex<Option<Error>>,
+    subscribers: Mutex<Vec<Subscriber<T>>>,
+}
+
+// NOTE: reloads the configuration on a background thread when the files of the loader change.
+// Files are polled, so no platform-specific notification APIs are involved. Loaders aren't
+// `Send`, so the loader is created by the given function on the watcher thread.
+pub struct Watcher<F> {
+    make_loader: F,
+    interval: Duration,
+}
+
+impl<F> Watcher<F>
+where
+    F: FnOnce() -> Loader + Send + 'static,
+{
+    #[inline]
+    pub fn new(make_loader: F) -> Self {
+        Self {
+            make_loader,
+            interval: Duration::from_secs(1),
+        }
+    }
+
+    #[inline]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    // NOTE: fails if the initial load fails. Errors of subsequent reloads keep the last good
+    // value. The watcher thread stops when all the handles are dropped. Subscribers are owned by
+    // the watcher, so they should hold weak handles, see `WatchHandle::downgrade`.
+    pub fn start<T>(self) -> Result<WatchHandle<T>>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        let Self {
+            make_loader,
+            interval,
+        } = self;
+
+        let (commands_tx, commands_rx) = mpsc::channel();
+        let (started_tx, started_rx) = mpsc::sync_channel(1);
+
+        thread::Builder::new()
+            .name("konfig-watcher".into())
+            .spawn(move || {
+                let loader = make_loader();
+                let fingerprints = fingerprints(&loader);
+
+                let (loaded, value) = match loader.load_with_value::<T>() {
+                    Ok(loaded) => loaded,
+                    Err(err) => {
+                        let _ = started_tx.send(Err(err));
+                        return;
+                    }
+                };
+
+                let shared = Arc::new(Shared {
+                    current: RwLock::new(Arc::new(loaded)),
+                    error: Mutex::new(None),
+                    subscribers: Mutex::new(vec![]),
+                });
+
+                if started_tx.send(Ok(Arc::clone(&shared))).is_ok() {
+                    watch(loader, shared, value, fingerprints, commands_rx, interval);
+                }
+            })
+            .map_err(Error::custom)?;
+
+        let shared = started_rx
+            .recv()
+            .map_err(|_| Error::custom("watcher thread has stopped unexpectedly"))??;
+
+        Ok(WatchHandle {
+            shared,
+            alive: Arc::new(Alive {
+                commands: commands_tx,
+            }),
+        })
+    }
+}
+
+pub struct WatchHandle<T> {
+    shared: Arc<Shared<T>>,
+    alive: Arc<Alive>,
+}
+
+// NOTE: doesn't keep the watcher thread running, e.g. to be held by subscribers.
+pub struct WeakWatchHandle<T> {
+    shared: Weak<Shared<T>>,
+    alive: Weak<Alive>,
+}
+
+impl<T> WeakWatchHandle<T> {
+    // NOTE: returns `None` if all the handles have been dropped.
+    #[inline]
+    pub fn upgrade(&self) -> Option<WatchHandle<T>> {
+        Some(WatchHandle {
+            shared: self.shared.upgrade()?,
+            alive: self.alive.upgrade()?,
+        })
+    }
+}
+
+impl<T> Clone for WeakWatchHandle<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            shared: Weak::clone(&self.shared),
+            alive: Weak::clone(&self.alive),
+        }
+    }
+}
+
+impl<T> WatchHandle<T> {
+    #[inline]
+    pub fn get(&self) -> Arc<T> {
+        Arc::clone(
+            &self
+                .shared
+                .current
+                .read()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    // NOTE: returns the error of the last reload, if it has failed. The error is cleared by
+    // the next successful reload.
+    #[inline]
+    pub fn take_error(&self) -> Option<Error> {
+        lock(&self.shared.error).take()
+    }
+
+    // NOTE: subscribers are called on the watcher thread with the new value and the paths that
+    // have changed. Subscribers added by a subscriber are called from the next change on. Reloads
+    // that don't change the value, e.g. edits of docs, don't notify the subscribers.
+    pub fn subscribe(&self, subscriber: impl Fn(&T, &[Path<'static>]) + Send + Sync + 'static) {
+        lock(&self.shared.subscribers).push(Arc::new(subscriber));
+    }
+
+    // NOTE: reloads the configuration even if none of the files have changed, e.g. to pick up
+    // new values of environment variables.
+    #[inline]
+    pub fn reload(&self) {
+        let _ = self.alive.commands.send(Command::Reload);
+    }
+
+    #[inline]
+    pub fn downgrade(&self) -> WeakWatchHandle<T> {
+        WeakWatchHandle {
+            shared: Arc::downgrade(&self.shared),
+            alive: Arc::downgrade(&self.alive),
+        }
+    }
+}
+
+impl<T> Clone for WatchHandle<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+            alive: Arc::clone(&self.alive),
+        }
+    }
+}
+
+fn watch<T>(
+    loader: Loader,
+    shared: Arc<Shared<T>>,
+    mut value: Value,
+    mut fingerprints: Vec<(PathBuf, Fingerprint)>,
+    commands: Receiver<Command>,
+    interval: Duration,
+) where
+    T: DeserializeOwned,
+{
+    loop {
+        match commands.recv_timeout(interval) {
+            Ok(Command::Reload) => fingerprints = self::fingerprints(&loader),
+            Err(RecvTimeoutError::Timeout) => {
+                let current = self::fingerprints(&loader);
+
+                if current == fingerprints {
+                    continue;
+                }
+
+                fingerprints = current;
+            }
+            Ok(Command::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let (loaded, new_value) = match loader.load_with_value::<T>() {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                *lock(&shared.error) = Some(err);
+                continue;
+            }
+        };
+
+        *lock(&shared.error) = None;
+
+        let paths = changed_paths(&value, &new_value);
+
+        value = new_value;
+
+        if paths.is_empty() {
+            continue;
+        }
+
+        let loaded = Arc::new(loaded);
+
+        *shared
+            .current
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Arc::clone(&loaded);
+
+        // NOTE: the lock isn't held while the subscribers are called, so they can subscribe.
+        let subscribers = lock(&shared.subscribers).clone();
+
+        for subscriber in subscribers {
+            subscriber(&loaded, &paths);
+        }
+    }
+
+    // NOTE: subscribers may hold weak handles to the shared state.
+    lock(&shared.subscribers).clear();
+}
+
+// NOTE: missing files have no fingerprint, so files that appear or disappear trigger reloads as
+// well.
+fn fingerprints(loader: &Loader) -> Vec<(PathBuf, Fingerprint)> {
+    loader
+        .files()
+        .map(|path| {
+            let fingerprint = fs::metadata(path).ok().and_then(|meta| {
+                let mut hasher = DefaultHasher::new();
+
+                fs::read(path).ok()?.hash(&mut hasher);
+
+                Some((meta.modified().ok(), meta.len(), hasher.finish()))
+            });
+
+            (path.to_path_buf(), fingerprint)
+        })
+        .collect()
+}
+
+fn changed_paths(old: &Value, new: &Value) -> Vec<Path<'static>> {
+    let diff = diff_with_options(old, new, DiffOptions { ignore_docs: true });
+    let mut paths = vec![];
+
+    for op in diff.ops {
+        match op {
+            DiffOp::Added { path, .. }
+            | DiffOp::Removed { path, .. }
+            | DiffOp::Changed { path, .. } => paths.push(path),
+            DiffOp::Moved { from, to, .. } => {
+                paths.push(from);
+                paths.push(to);
+            }
+            DiffOp::DocsChanged { .. } => unreachable!("docs are ignored"),
+        }
+    }
+
+    paths
+}
+
+#[inline]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Config {
+        port: u16,
+        hosts: Vec<String>,
+    }
+
+    fn wait_for<R>(mut check: impl FnMut() -> Option<R>) -> R {
+        let start = Instant::now();
+
+        loop {
+            if let Some(result) = check() {
+                return result;
+            }
+
+            assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn reload() {
+        let path = std::env::temp_dir().join(format!("konfig-watch-{}.konfig", std::process::id()));
+
+        fs::write(&path, "> port = 80\n\n> hosts = [\"a\"]").unwrap();
+
+        let handle = Watcher::new({
+            let path = path.clone();
+
+            move || Loader::new().file(path)
+        })
+        .interval(Duration::from_millis(10))
+        .start::<Config>()
+        .unwrap();
+
+        assert_eq!(handle.get().port, 80);
+
+        let (changes_tx, changes_rx) = mpsc::channel();
+
+        handle.subscribe(move |config: &Config, paths| {
+            let paths = paths.iter().map(ToString::to_string).collect::<Vec<_>>();
+            let _ = changes_tx.send((config.port, paths));
+        });
+
+        fs::write(
+            &path,
+            "Docs.\n\n> port = 8080\n\n> hosts = [\"a\", \"b\"]\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            changes_rx.recv_timeout(Duration::from_secs(10)).unwrap(),
+            (8080, vec!["> port".into(), "> hosts > [1]".into()])
+        );
+
+        // NOTE: the last good value is kept on errors.
+        fs::write(&path, "> port = 100000\n\n> hosts = []").unwrap();
+
+        let err = wait_for(|| handle.take_error());
+
+        assert!(err.to_string().contains("expected u16"));
+        assert_eq!(handle.get().port, 8080);
+
+        fs::write(&path, "> port = 8081\n\n> hosts = [\"a\", \"b\"]").unwrap();
+
+        assert_eq!(
+            changes_rx.recv_timeout(Duration::from_secs(10)).unwrap(),
+            (8081, vec!["> port".into()])
+        );
+
+        assert_eq!(handle.take_error(), None);
+
+        // NOTE: edits that keep the length and the modification time are detected as well.
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+        fs::write(&path, "> port = 8082\n\n> hosts = [\"a\", \"b\"]").unwrap();
+
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        assert_eq!(
+            changes_rx.recv_timeout(Duration::from_secs(10)).unwrap(),
+            (8082, vec!["> port".into()])
+        );
+
+        // NOTE: subscribers can subscribe without deadlocking the watcher.
+        let (nested_tx, nested_rx) = mpsc::channel();
+
+        handle.subscribe({
+            let weak = handle.downgrade();
+            let subscribed = Mutex::new(false);
+
+            move |_: &Config, _| {
+                let Some(handle) = weak.upgrade() else {
+                    return;
+                };
+
+                if !std::mem::replace(&mut *lock(&subscribed), true) {
+                    let nested_tx = nested_tx.clone();
+
+                    handle.subscribe(move |config: &Config, _| {
+                        let _ = nested_tx.send(config.port);
+                    });
+                }
+            }
+        });
+
+        fs::write(&path, "> port = 8083\n\n> hosts = [\"a\", \"b\"]").unwrap();
+
+        assert_eq!(
+            changes_rx.recv_timeout(Duration::from_secs(10)).unwrap().0,
+            8083
+        );
+
+        fs::write(&path, "> port = 8084\n\n> hosts = [\"a\", \"b\"]").unwrap();
+
+        assert_eq!(
+            nested_rx.recv_timeout(Duration::from_secs(10)).unwrap(),
+            8084
+        );
+        assert_eq!(
+            changes_rx.recv_timeout(Duration::from_secs(10)).unwrap().0,
+            8084
+        );
+
+        // NOTE: weak handles held by the subscribers don't keep the watcher running.
+        let weak = handle.downgrade();
+
+        handle.reload();
+        drop(handle);
+
+        assert!(changes_rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        wait_for(|| (weak.shared.strong_count() == 0).then_some(()));
+
+        assert!(weak.upgrade().is_none());
+        assert!(matches!(changes_rx.recv(), Err(mpsc::RecvError)));
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(Watcher::new(move || Loader::new().file(path))
+            .start::<Config>()
+            .is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::value::{PathItem, ValueCell};
+
+// NOTE: an expression that doesn't assign a value of the tree, e.g. a template definition like
+// `> &worker > threads = 4` or an expression of a profile like `> @prod > port = 80`. Expressions
+// of the active profiles are applied to the tree as well. The value is the right hand side of the
+// expression as it's written.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct DetachedExpr {
+    pub docs_before: String,
+    pub kind: DetachedExprKind,
+    pub path: Vec<PathItem<'static>>,
+    pub value: ValueCell,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DetachedExprKind {
+    Profile(String),
+    Template(String),
+}
+
+impl DetachedExpr {
+    #[inline]
+    pub fn new(kind: DetachedExprKind, path: Vec<PathItem<'static>>, value: ValueCell) -> Self {
+        Self {
+            docs_before: Default::default(),
+            kind,
+            path,
+            value,
+        }
+    }
+}
+
+// NOTE: the value keeps its lexical info, e.g. the template it refers to.
+impl Clone for DetachedExpr {
+    fn clone(&self) -> Self {
+        Self {
+            docs_before: self.docs_before.clone(),
+            kind: self.kind.clone(),
+            path: self.path.clone(),
+            value: self.value.clone_with_lexical_info(),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use super::error::{relocate, ParseError, ParseResult};
+use super::imp::{Parser, Rule};
+use super::{parse_rule, Context, ParseOptions, Parsed, BOM};
+use crate::error::Result;
+use crate::serializer::components::{code_fence_start, is_code_fence_end};
+use crate::value::{Path, ValueCell};
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+const BLOCK_INFO_STRING: &str = "konfig";
+
+#[inline]
+pub fn parse_embedded(input: &str) -> Result<ValueCell> {
+    parse_embedded_with_options(input, Default::default()).map(|parsed| parsed.value)
+}
+
+// NOTE: blocks are parsed one by one into the same root, as if they were a single konfig. Docs
+// that trail the last expression of a block are attached to that expression as `docs_after`, so
+// the serializer can put them back into the same block.
+pub fn parse_embedded_with_options(input: &str, options: ParseOptions) -> Result<Parsed> {
+    let input = input.strip_prefix(BOM).unwrap_or(input);
+
+    #[cfg(debug_assertions)]
+    let _guard = crate::value::value_cell::safety_checks::ParsingGuard::new();
+
+    let ctx = Rc::new(RefCell::new(Context::new(options)));
+
+    for block in find_blocks(input) {
+        ctx.borrow_mut().offset = block.start;
+
+        parse_rule(Rule::konfig, &input[block.clone()], Rc::clone(&ctx))
+            .and_then(Parser::konfig)
+            .map_err(|err| ParseError::wrap(relocate(err, input, block.start)))?;
+
+        ctx.borrow_mut().flush_pending_docs();
+    }
+
+    let mut ctx = ctx.borrow_mut();
+
+    ctx.finish(input)
+}
+
+pub(crate) fn find_blocks(input: &str) -> Vec<Range<usize>> {
+    let mut blocks = vec![];
+    let mut open_fence: Option<(&str, Option<usize>)> = None;
+    let mut pos = 0;
+
+    for line in input.split_inclusive('\n') {
+        let line_start = pos;
+
+        pos += line.len();
+
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        match open_fence {
+            Some((fence, block_start)) => {
+                if is_code_fence_end(line, fence) {
+                    if let Some(block_start) = block_start {
+                        blocks.push(block_start..line_start);
+                    }
+
+                    open_fence = None;
+                }
+            }
+            None => {
+                if let Some((fence, info)) = code_fence_start(line) {
+                    let is_konfig = info.split_whitespace().next() == Some(BLOCK_INFO_STRING);
+
+                    open_fence = Some((fence, is_konfig.then_some(pos)));
+                }
+            }
+        }
+    }
+
+    if let Some((_, Some(block_start))) = open_fence {
+        blocks.push(block_start..input.len());
+    }
+
+    blocks
+}
+
+pub(crate) fn block_expr_paths(input: &str, block: Range<usize>) -> Result<Vec<Path<'static>>> {
+    let wrap_err = |err| ParseError::wrap(relocate(err, input, block.start));
+    let konfig =
+        parse_rule(Rule::konfig, &input[block.clone()], Default::default()).map_err(wrap_err)?;
+
+    konfig
+        .into_children()
+        .filter(|node| node.as_rule() == Rule::expr)
+        .map(|expr| {
+            let mut path = Path::default();
+            let path_node = expr.into_children().next().unwrap();
+
+            for node in path_node.into_children() {
+                if node.as_rule() == Rule::path_item {
+                    path.push(Parser::path_item(node)?.into_owned());
+                }
+            }
+
+            Ok(path)
+        })
+        .collect::<ParseResult<_>>()
+        .map_err(wrap_err)
+}
@@ -1,5 +1,6 @@
 use super::imp::Rule;
-use pest::Span;
+use pest::error::InputLocation;
+use pest::{Position, Span};
 use pest_consume::Error as PestError;
 use std::fmt;
 
@@ -15,6 +16,21 @@ impl ParseError {
     }
 }
 
+// NOTE: moves error location to the given offset in the outer input, so that line and column
+// are reported relative to the whole document rather than to its parsed fragment.
+pub(crate) fn relocate(err: PestError<Rule>, outer_input: &str, offset: usize) -> PestError<Rule> {
+    match err.location {
+        InputLocation::Pos(pos) => PestError::new_from_pos(
+            err.variant,
+            Position::new(outer_input, offset + pos).unwrap(),
+        ),
+        InputLocation::Span((start, end)) => PestError::new_from_span(
+            err.variant,
+            Span::new(outer_input, offset + start, offset + end).unwrap(),
+        ),
+    }
+}
+
 impl fmt::Display for ParseError {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -69,6 +85,9 @@ pub(super) fn rename_rules(err: PestError<Rule>) -> PestError<Rule> {
             Rule::path_item => "path item",
             Rule::expr => "expression",
             Rule::path => "value path",
+            Rule::profile | Rule::profile_name => "profile: `@` followed by a profile name",
+            Rule::template | Rule::template_name => "template: `&` followed by a template name",
+            Rule::template_ref => "template reference: `*` followed by a template name",
             Rule::raw_string_lang_ident
             | Rule::raw_string_start => "raw string start: new line, followed by ```, followed by an optional language identifier, followed by a mandatory new line",
             Rule::raw_string_end => "raw string end: a new line followed by ```",
@@ -82,7 +101,10 @@ pub(super) fn rename_rules(err: PestError<Rule>) -> PestError<Rule> {
             Rule::konfig => "expression",
             Rule::docs => "documentation",
             Rule::expr_terminator => "double new line or end of input",
-            Rule::docs_terminator => "new line or end of input"
+            Rule::docs_terminator => "new line or end of input",
+            Rule::docs_code_block | Rule::docs_code_block_line => "fenced code block",
+            Rule::docs_code_fence_start => "code fence: ``` or ~~~, followed by an optional info string",
+            Rule::docs_code_fence_end => "closing code fence"
         }.into()
     })
 }
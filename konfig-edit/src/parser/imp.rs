@@ -1,10 +1,17 @@
 use super::error::{parse_error, IntoParseResult, ParseResult};
-use super::insertion_point::{path_item_to_value, InsertionPoint};
-use super::Context;
-use crate::value::{PathItem, Value, ValueCell};
+use super::insertion_point::{path_item_to_value, InsertionPoint, Lookup, Occupied};
+use super::template::{self, TemplateRef};
+use super::warning::{Lint, Message};
+use super::{
+    Context, DetachedExpr, DetachedExprKind, DuplicateAssignment, Span, TemplateDefinition,
+};
+use crate::serializer::components::code_fence_start;
+use crate::value::{Path, PathItem, Value, ValueCell};
 use pest_consume::{match_nodes, Parser as PestParser};
 use std::cell::RefCell;
+use std::ops::Range;
 use std::rc::Rc;
+use std::sync::Arc;
 
 pub(super) type Node<'i> = pest_consume::Node<'i, Rule, Rc<RefCell<Context>>>;
 
@@ -52,6 +59,17 @@ impl Parser {
             _ => unreachable!(),
         };
 
+        if radix == 10 && digits.as_str().len() > 1 && digits.as_str().starts_with('0') {
+            let mut ctx = node.user_data().borrow_mut();
+            let span = ctx.range(digits.as_span());
+
+            ctx.warn(
+                Lint::LeadingZeros,
+                span,
+                Message::Text("decimal number with leading zeros"),
+            );
+        }
+
         u64::from_str_radix(digits.as_str(), radix).into_parse_result(node.as_span())
     }
 
@@ -91,7 +109,7 @@ impl Parser {
 
     #[inline]
     pub(super) fn raw_string_text(node: Node) -> ParseResult<String> {
-        Ok(node.as_str().to_string())
+        Ok(node.as_str().replace("\r\n", "\n"))
     }
 
     pub(super) fn raw_string(node: Node) -> ParseResult<String> {
@@ -147,19 +165,55 @@ impl Parser {
             node.children();
             [primitive(v)] => v,
             [array_of_primitives(s)] => s,
-            [list_of_primitives(s)] => s
+            [list_of_primitives(s)] => s,
+            [template_ref(t)] => t
         };
 
         let mut ctx = node.user_data().borrow_mut();
+        let mut value_ref = value.borrow_mut();
 
         ctx.last_rhs = Some(value.rc_clone());
 
-        value.borrow_mut().lexical_info.docs_before = ctx.pending_docs.take().unwrap_or_default();
+        value_ref.lexical_info.docs_before = ctx.pending_docs.take().unwrap_or_default();
+        value_ref.lexical_info.exprs_before = std::mem::take(&mut ctx.pending_exprs);
+        value_ref.lexical_info.span = Some(ctx.range(node.as_span()));
+
+        drop(value_ref);
+
+        Ok(value)
+    }
+
+    // NOTE: instances in template definitions are expanded right away, so templates can extend
+    // other templates. The reference is kept either way, so the definition can be written back.
+    pub(super) fn template_ref(node: Node) -> ParseResult<ValueCell> {
+        let ctx = node.user_data().borrow();
+        let name = node.children().single().unwrap().as_str();
+
+        let template = ctx
+            .templates
+            .get(name)
+            .ok_or_else(|| parse_error!(node.as_span(), "unknown template `{}`", name))?;
+
+        // NOTE: templates are exclusively owned by the context, so they can be safely cloned
+        // using the public API.
+        #[cfg(debug_assertions)]
+        let _guard = crate::value::value_cell::safety_checks::SuspendParsingGuard::new();
+
+        let value = if ctx.is_template_definition {
+            ValueCell::from(Value::clone(template))
+        } else {
+            ValueCell::from(template::placeholder(template))
+        };
+
+        value.borrow_mut().lexical_info.template = Some(TemplateRef {
+            name: name.to_string(),
+            template: Arc::clone(template),
+        });
 
         Ok(value)
     }
 
-    pub(super) fn enum_variant(node: Node) -> ParseResult<&str> {
+    pub(super) fn enum_variant(node: Node<'_>) -> ParseResult<&str> {
         Ok(node.children().single().unwrap().as_str())
     }
 
@@ -172,16 +226,29 @@ impl Parser {
             .into_parse_result(node.as_span())
     }
 
-    pub(super) fn field_name(node: Node) -> ParseResult<&str> {
+    pub(super) fn field_name(node: Node<'_>) -> ParseResult<&str> {
         Ok(node.as_str())
     }
 
     pub(super) fn map_key(node: Node) -> ParseResult<String> {
-        Ok(match_nodes! {
+        let key = match_nodes! {
             node.children().single().unwrap().children();
             [single_quoted_string(k)] => k,
             [double_quoted_string(k)] => k,
-        })
+        };
+
+        if key.ends_with(char::is_whitespace) {
+            let mut ctx = node.user_data().borrow_mut();
+            let span = ctx.range(node.as_span());
+
+            ctx.warn(
+                Lint::MapKeyTrailingWhitespace,
+                span,
+                Message::Text("map key has trailing whitespace"),
+            );
+        }
+
+        Ok(key)
     }
 
     pub(super) fn path_item(node: Node) -> ParseResult<PathItem> {
@@ -213,56 +280,442 @@ impl Parser {
         let span = node.as_span();
         let ctx = node.user_data();
 
-        let (mut path, mut new_value) = match_nodes! {
+        let (path, new_value) = match_nodes! {
             node.children();
             [path(p), rhs(r), expr_terminator(_)] => (
-                p.into_children().filter(|n| n.as_rule() == Rule::path_item),
+                p.into_children()
+                    .filter(|n| n.as_rule() == Rule::path_item)
+                    .collect::<Vec<_>>(),
                 r
             ),
         };
 
-        let insertion_point = ctx
-            .borrow()
-            .root
-            .as_ref()
-            .map(|root| InsertionPoint::find(&mut path, span, root.rc_clone()))
-            .transpose()?;
+        assign(ctx, path, new_value, span)
+    }
+
+    pub(super) fn konfig(node: Node) -> ParseResult<()> {
+        node.into_children()
+            .try_for_each(|node| match node.as_rule() {
+                Rule::expr => match expr_prefix(&node) {
+                    Some((Rule::profile, profile)) => profile_expr(node, profile),
+                    Some((_, template)) => template_expr(node, template),
+                    None => Parser::expr(node),
+                },
+                Rule::docs => {
+                    let mut ctx = node.user_data().borrow_mut();
+
+                    let is_code_block = node
+                        .children()
+                        .any(|child| child.as_rule() == Rule::docs_code_block);
+
+                    // NOTE: fences that are not closed are parsed as regular docs, so the
+                    // following expressions are not swallowed by the code block.
+                    if !is_code_block && code_fence_start(node.as_str().trim_end()).is_some() {
+                        let span = ctx.range(node.as_span());
+
+                        ctx.warn(
+                            Lint::UnclosedCodeFence,
+                            span.start..span.start + node.as_str().trim_end().len(),
+                            Message::Text("code fence is not closed"),
+                        );
+                    }
+
+                    if !is_code_block && looks_like_path(node.as_str()) {
+                        let span = ctx.range(node.as_span());
+
+                        ctx.warn(
+                            Lint::DocsLookLikePath,
+                            span.start..span.start + node.as_str().trim_end().len(),
+                            Message::Text("docs look like a path assignment without leading `>`"),
+                        );
+                    }
+
+                    // NOTE: docs are stored with `\n` line endings, output line endings are
+                    // controlled by the formatting options.
+                    ctx.pending_docs
+                        .get_or_insert_with(String::new)
+                        .push_str(&node.as_str().replace("\r\n", "\n"));
 
-        for node in path.rev() {
-            let span = node.as_span();
-            let path_item = Parser::path_item(node)?;
+                    Ok(())
+                }
+                _ => Ok(()),
+            })
+    }
+}
 
-            new_value = path_item_to_value(path_item, new_value, span)?;
+// NOTE: inserts the value into the tree of the context, checking the path for duplicate
+// assignments according to the parse options.
+#[allow(clippy::result_large_err)]
+fn assign<'i>(
+    ctx: &RefCell<Context>,
+    path: Vec<Node<'i>>,
+    mut new_value: ValueCell,
+    span: Span<'i>,
+) -> ParseResult<()> {
+    // NOTE: assignments are only tracked if duplicates are allowed, so we can report the
+    // overridden ones.
+    let assignment = match ctx.borrow().options.duplicate_assignment {
+        DuplicateAssignment::Error => None,
+        _ => {
+            let path = path
+                .iter()
+                .map(|node| Parser::path_item(node.clone()).map(PathItem::into_owned))
+                .collect::<ParseResult<Path>>()?;
+
+            Some((path, ctx.borrow().range(span)))
         }
+    };
+
+    let mut remaining_path = path.into_iter();
+
+    let lookup = ctx
+        .borrow()
+        .root
+        .as_ref()
+        .map(|root| {
+            InsertionPoint::find(
+                &mut remaining_path,
+                span,
+                root.rc_clone(),
+                assignment.is_some(),
+            )
+        })
+        .transpose()?;
+
+    for node in remaining_path.rev() {
+        let span = node.as_span();
+        let path_item = Parser::path_item(node)?;
 
-        match insertion_point {
-            Some(insertion_point) => insertion_point.insert(new_value)?,
-            None => ctx.borrow_mut().root = Some(new_value),
+        new_value = path_item_to_value(path_item, new_value, span)?;
+    }
+
+    let mut ctx = ctx.borrow_mut();
+
+    match lookup {
+        Some(Lookup::Vacant(insertion_point)) => insertion_point.insert(new_value)?,
+        Some(Lookup::Occupied(mut occupied)) => {
+            if let Some((path_item, span)) = occupied.wrap.take() {
+                new_value = path_item_to_value(path_item, new_value, span)?;
+            }
+
+            let (path, range) = assignment.unwrap();
+
+            return assign_occupied(&mut ctx, occupied, new_value, path, range, span);
         }
+        None => ctx.root = Some(new_value),
+    }
 
-        Ok(())
+    if let Some(assignment) = assignment {
+        ctx.assignments.push(assignment);
     }
 
-    pub(super) fn konfig(node: Node) -> ParseResult<()> {
-        node.into_children()
-            .filter(|node| match node.as_rule() {
-                Rule::expr => true,
-                Rule::docs => {
-                    let ctx = node.user_data();
+    Ok(())
+}
 
-                    ctx.borrow_mut()
-                        .pending_docs
-                        .get_or_insert_with(String::new)
-                        .push_str(node.as_str());
+#[allow(clippy::result_large_err)]
+fn assign_occupied(
+    ctx: &mut Context,
+    occupied: Occupied,
+    new_value: ValueCell,
+    path: Path<'static>,
+    range: Range<usize>,
+    span: Span,
+) -> ParseResult<()> {
+    let conflict_path = &path.items()[..occupied.depth];
+    let current = occupied.replace(&mut ctx.root, Value::Null.into());
+
+    let overridden = ctx
+        .assignments
+        .iter()
+        .filter(|(p, _)| p.items().starts_with(conflict_path))
+        .map(|(_, r)| r.clone())
+        .collect::<Vec<_>>();
+
+    let resolved = match &ctx.options.duplicate_assignment {
+        DuplicateAssignment::LastWins => {
+            for overridden_range in overridden {
+                ctx.warn(
+                    Lint::OverriddenAssignment,
+                    overridden_range,
+                    Message::Replaced(range.clone()),
+                );
+            }
 
-                    false
-                }
-                _ => false,
-            })
-            .try_for_each(Parser::expr)
+            ctx.assignments
+                .retain(|(p, _)| !p.items().starts_with(conflict_path));
+
+            new_value
+        }
+        DuplicateAssignment::FirstWins => {
+            if let Some(first_range) = overridden.into_iter().next() {
+                ctx.warn(
+                    Lint::OverriddenAssignment,
+                    range,
+                    Message::Ignored(first_range),
+                );
+            }
+
+            ctx.last_rhs = Some(current.rc_clone());
+            occupied.replace(&mut ctx.root, current);
+
+            return Ok(());
+        }
+        DuplicateAssignment::DeepMerge(resolver) => {
+            // NOTE: the merged values are detached from the tree and the last rhs reference is
+            // released, so both values are exclusively owned and can be safely merged using the
+            // public API.
+            ctx.last_rhs = None;
+
+            let lexical_info = current.borrow().lexical_info.clone();
+
+            let merged = {
+                #[cfg(debug_assertions)]
+                let _guard = crate::value::value_cell::safety_checks::SuspendParsingGuard::new();
+
+                current
+                    .into_value()
+                    .merge_at(
+                        conflict_path.iter().cloned().collect(),
+                        &**resolver,
+                        new_value.into_value(),
+                    )
+                    .map_err(|err| parse_error!(span, "{}", err))?
+            };
+
+            let merged = ValueCell::from(merged);
+
+            merged.borrow_mut().lexical_info = lexical_info;
+            ctx.last_rhs = Some(merged.rc_clone());
+
+            for overridden_range in overridden {
+                ctx.warn(
+                    Lint::OverriddenAssignment,
+                    overridden_range,
+                    Message::Merged(range.clone()),
+                );
+            }
+
+            merged
+        }
+        DuplicateAssignment::Error => unreachable!(),
+    };
+
+    occupied.replace(&mut ctx.root, resolved);
+    ctx.assignments.push((path, range));
+
+    Ok(())
+}
+
+// NOTE: unlike `Parser::expr`, doesn't insert the value into the tree, so the expression can be
+// applied to an existing value.
+#[allow(clippy::result_large_err)]
+pub(super) fn standalone_expr(node: Node) -> ParseResult<(Path<'static>, ValueCell)> {
+    let mut children = node.into_children();
+
+    let path = children
+        .next()
+        .unwrap()
+        .into_children()
+        .filter(|node| node.as_rule() == Rule::path_item)
+        .map(|node| Parser::path_item(node).map(PathItem::into_owned))
+        .collect::<ParseResult<Path>>()?;
+
+    let value = Parser::rhs(children.next().unwrap())?;
+
+    Ok((path, value))
+}
+
+// NOTE: returns the profile or the template of the expression, if any.
+pub(super) fn expr_prefix<'i>(node: &Node<'i>) -> Option<(Rule, &'i str)> {
+    let first = node.children().next()?.children().next()?;
+
+    match first.as_rule() {
+        Rule::profile | Rule::template => {
+            Some((first.as_rule(), first.children().single().unwrap().as_str()))
+        }
+        _ => None,
+    }
+}
+
+// NOTE: expressions of all the profiles are kept as detached expressions, so they are serialized
+// back unchanged. Expressions of the active profiles are applied once parsing is complete.
+#[allow(clippy::result_large_err)]
+fn profile_expr(node: Node, profile: &str) -> ParseResult<()> {
+    let ctx = Rc::clone(node.user_data());
+
+    let range = {
+        let ctx = ctx.borrow();
+        let range = ctx.range(node.as_span());
+
+        range.start..range.start + node.as_str().trim_end().len()
+    };
+
+    let (path, value) = detached_expr(node)?;
+    let mut ctx = ctx.borrow_mut();
+
+    if ctx.options.active_profiles.contains(profile) {
+        // NOTE: the value is detached from the tree and the last rhs reference is released, so
+        // it is exclusively owned and can be safely cloned using the public API.
+        #[cfg(debug_assertions)]
+        let _guard = crate::value::value_cell::safety_checks::SuspendParsingGuard::new();
+
+        let applied = (path.clone(), value.clone_with_lexical_info(), range);
+
+        ctx.profile_exprs.push(applied);
+    }
+
+    push_detached_expr(
+        &mut ctx,
+        DetachedExpr::new(
+            DetachedExprKind::Profile(profile.to_string()),
+            path.items().to_vec(),
+            value,
+        ),
+    );
+
+    Ok(())
+}
+
+// NOTE: template definitions are kept as detached expressions, as the values of templates are not
+// a part of the tree.
+#[allow(clippy::result_large_err)]
+fn template_expr(node: Node, name: &str) -> ParseResult<()> {
+    let ctx = Rc::clone(node.user_data());
+    let span = node.as_span();
+
+    let path_nodes = node
+        .children()
+        .next()
+        .unwrap()
+        .into_children()
+        .filter(|node| node.as_rule() == Rule::path_item)
+        .collect::<Vec<_>>();
+
+    ctx.borrow_mut().is_template_definition = true;
+
+    let expr = detached_expr(node);
+
+    ctx.borrow_mut().is_template_definition = false;
+
+    let (path, value) = expr?;
+
+    // NOTE: the value is detached from the tree and the last rhs reference is released, so it is
+    // exclusively owned and can be safely cloned using the public API.
+    let definition_value = {
+        #[cfg(debug_assertions)]
+        let _guard = crate::value::value_cell::safety_checks::SuspendParsingGuard::new();
+
+        let mut definition_value = value.clone_with_lexical_info();
+
+        template::collapse_templates(&mut definition_value);
+
+        definition_value
+    };
+
+    // NOTE: the definition is assigned in place of the tree of the konfig, so it goes through the
+    // same duplicate assignment checks. The last rhs is kept, as it belongs to the konfig.
+    let definition = {
+        let mut ctx = ctx.borrow_mut();
+        let definition = ctx.template_definitions.remove(name).unwrap_or_default();
+
+        swap_tree(&mut ctx, definition)
+    };
+
+    let last_rhs = ctx.borrow_mut().last_rhs.take();
+    let assigned = assign(&ctx, path_nodes, definition_value, span);
+    let mut ctx = ctx.borrow_mut();
+
+    ctx.last_rhs = last_rhs;
+
+    let definition = swap_tree(&mut ctx, definition);
+
+    assigned?;
+
+    {
+        // NOTE: templates are exclusively owned by the context, so they can be safely cloned
+        // using the public API.
+        #[cfg(debug_assertions)]
+        let _guard = crate::value::value_cell::safety_checks::SuspendParsingGuard::new();
+
+        let mut template = definition.root.as_ref().unwrap().clone_with_lexical_info();
+
+        template::expand_templates(&mut template);
+
+        ctx.templates
+            .insert(name.to_string(), Arc::new(template.into_value()));
+    }
+
+    ctx.template_definitions
+        .insert(name.to_string(), definition);
+
+    push_detached_expr(
+        &mut ctx,
+        DetachedExpr::new(
+            DetachedExprKind::Template(name.to_string()),
+            path.items().to_vec(),
+            value,
+        ),
+    );
+
+    Ok(())
+}
+
+fn swap_tree(ctx: &mut Context, definition: TemplateDefinition) -> TemplateDefinition {
+    TemplateDefinition {
+        root: std::mem::replace(&mut ctx.root, definition.root),
+        assignments: std::mem::replace(&mut ctx.assignments, definition.assignments),
     }
 }
 
+// NOTE: the docs before a detached expression belong to it, so the docs of the next value start
+// after the expression.
+fn push_detached_expr(ctx: &mut Context, mut expr: DetachedExpr) {
+    expr.docs_before = ctx.pending_docs.take().unwrap_or_default();
+    ctx.pending_exprs.push(expr);
+}
+
+// NOTE: parses the expression without inserting the value into the tree. The docs and the
+// detached expressions before the expression stay pending and the last rhs is kept, so that they
+// are attached to the values of the tree.
+#[allow(clippy::result_large_err)]
+fn detached_expr(node: Node) -> ParseResult<(Path<'static>, ValueCell)> {
+    let ctx = Rc::clone(node.user_data());
+    let last_rhs = ctx.borrow_mut().last_rhs.take();
+    let pending_docs = ctx.borrow_mut().pending_docs.take();
+    let pending_exprs = std::mem::take(&mut ctx.borrow_mut().pending_exprs);
+
+    let expr = standalone_expr(node);
+    let mut ctx = ctx.borrow_mut();
+
+    ctx.last_rhs = last_rhs;
+    ctx.pending_docs = pending_docs;
+    ctx.pending_exprs = pending_exprs;
+
+    expr
+}
+
+// NOTE: docs line is considered to be a broken path if it would be a valid assignment with the
+// leading `>`, e.g. `foo > bar = 42`.
+fn looks_like_path(docs_line: &str) -> bool {
+    let line = format!("> {}", docs_line.trim());
+
+    let Ok(mut path) = Parser::parse(Rule::path, &line) else {
+        return false;
+    };
+
+    let path_end = path.next().unwrap().as_span().end();
+
+    let Some(rhs) = line[path_end..].trim_start().strip_prefix('=') else {
+        return false;
+    };
+
+    let rhs = rhs.trim_start();
+
+    Parser::parse(Rule::rhs, rhs)
+        .map(|mut nodes| nodes.next().unwrap().as_span().end() == rhs.len())
+        .unwrap_or_default()
+}
+
 #[allow(clippy::result_large_err)]
 fn parse_quoted_string(node: Node, text_rule: Rule) -> ParseResult<String> {
     let mut string = String::default();
@@ -2,6 +2,12 @@ use super::error::{parse_error, ParseResult};
 use super::imp::Node;
 use super::{Parser, Span};
 use crate::value::{PathItem, Value, ValueCell};
+use std::mem;
+
+pub(super) enum Lookup<'i> {
+    Vacant(InsertionPoint<'i>),
+    Occupied(Occupied<'i>),
+}
 
 pub(super) struct InsertionPoint<'i> {
     host: ValueCell,
@@ -9,32 +15,66 @@ pub(super) struct InsertionPoint<'i> {
     span: Span<'i>,
 }
 
+// NOTE: an occupied entry is either a value that is already assigned to the whole path, or a
+// value on the path that has an incompatible type with the rest of the path. In the latter case
+// the path item that can't be applied to the value is stored in `wrap`.
+pub(super) struct Occupied<'i> {
+    parent: Option<(ValueCell, PathItem<'i>)>,
+    pub(super) depth: usize,
+    pub(super) wrap: Option<(PathItem<'i>, Span<'i>)>,
+}
+
 impl<'i> InsertionPoint<'i> {
     #[allow(clippy::result_large_err)]
     pub(super) fn find(
         path: &mut impl Iterator<Item = Node<'i>>,
         assignment_span: Span,
         root: ValueCell,
-    ) -> ParseResult<InsertionPoint<'i>> {
+        allow_occupied: bool,
+    ) -> ParseResult<Lookup<'i>> {
         let mut host = root;
+        let mut parent = None;
+        let mut depth = 0;
 
         for node in path.by_ref() {
             let span = node.as_span();
             let path_item = Parser::path_item(node)?;
-            let next = index_value_by_path_item(&path_item, &host, span)?;
+
+            let next = match index_value_by_path_item(&path_item, &host, span) {
+                Ok(next) => next,
+                Err(_) if allow_occupied => {
+                    return Ok(Lookup::Occupied(Occupied {
+                        parent,
+                        depth,
+                        wrap: Some((path_item, span)),
+                    }))
+                }
+                Err(err) => return Err(err),
+            };
 
             match next {
-                Some(next) => host = next,
+                Some(next) => {
+                    parent = Some((mem::replace(&mut host, next), path_item));
+                    depth += 1;
+                }
                 None => {
-                    return Ok(InsertionPoint {
+                    return Ok(Lookup::Vacant(InsertionPoint {
                         host,
                         path_item,
                         span,
-                    })
+                    }))
                 }
             }
         }
 
+        if allow_occupied {
+            return Ok(Lookup::Occupied(Occupied {
+                parent,
+                depth,
+                wrap: None,
+            }));
+        }
+
         Err(parse_error!(
             assignment_span,
             "the path already has a value assigned",
@@ -70,25 +110,44 @@ impl<'i> InsertionPoint<'i> {
     }
 }
 
+impl Occupied<'_> {
+    pub(super) fn replace(&self, root: &mut Option<ValueCell>, value: ValueCell) -> ValueCell {
+        let Some((parent, path_item)) = &self.parent else {
+            return mem::replace(root.as_mut().unwrap(), value);
+        };
+
+        let slot = match (&mut parent.borrow_mut().value, path_item) {
+            (Value::Sequence(seq), PathItem::SequenceIndex(idx)) => {
+                mem::replace(&mut seq[*idx], value)
+            }
+            (Value::Struct(fields), PathItem::StructFieldName(name)) => {
+                mem::replace(fields.get_mut::<str>(name).unwrap(), value)
+            }
+            (Value::Map(map), PathItem::MapKey(key)) => {
+                mem::replace(map.get_mut::<str>(key).unwrap(), value)
+            }
+            (Value::Variant(_, variant_value), PathItem::VariantName(_)) => {
+                mem::replace(variant_value, value)
+            }
+            _ => unreachable!(),
+        };
+
+        slot
+    }
+}
+
 #[allow(clippy::result_large_err)]
 pub(super) fn path_item_to_value(
     path_item: PathItem,
     prev: ValueCell,
     span: Span,
 ) -> ParseResult<ValueCell> {
-    match path_item {
-        PathItem::SequenceIndex(0) => Ok(Value::Sequence(vec![prev])),
-        PathItem::SequenceIndex(_) => Err(parse_error!(
+    path_item.wrap(prev).map(Into::into).ok_or_else(|| {
+        parse_error!(
             span,
             "sequence items should be defined in order, with the first item having index `0`"
-        )),
-        PathItem::MapKey(key) => Ok(Value::Map([(key.to_string(), prev)].into_iter().collect())),
-        PathItem::StructFieldName(name) => Ok(Value::Struct(
-            [(name.to_string(), prev)].into_iter().collect(),
-        )),
-        PathItem::VariantName(variant) => Ok(Value::Variant(variant.to_string(), prev)),
-    }
-    .map(Into::into)
+        )
+    })
 }
 
 #[allow(clippy::result_large_err)]
@@ -1,35 +1,220 @@
+mod detached;
+pub(crate) mod embedded;
 pub(crate) mod error;
 mod imp;
 mod insertion_point;
+mod options;
+mod profile;
+mod template;
+mod warning;
 
 use self::error::{parse_error, rename_rules, ParseError, ParseResult};
 use self::imp::{Node, Parser, Rule};
-use crate::error::Result;
-use crate::value::ValueCell;
+use self::warning::{Message, PendingWarning};
+use crate::error::{Error, Result};
+use crate::value::{Path, PathItem, Value, ValueCell};
 use pest::Span;
 use pest_consume::Parser as _;
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
 use std::rc::Rc;
+use std::sync::Arc;
+
+pub use self::detached::{DetachedExpr, DetachedExprKind};
+pub use self::embedded::{parse_embedded, parse_embedded_with_options};
+pub use self::options::{DuplicateAssignment, ParseOptions};
+pub use self::profile::ProfileOverride;
+pub use self::template::TemplateRef;
+pub use self::warning::{Lint, LintLevel, Warning};
+
+pub(crate) const BOM: &str = "\u{feff}";
 
 #[derive(Default)]
 struct Context {
     root: Option<ValueCell>,
     last_rhs: Option<ValueCell>,
     pending_docs: Option<String>,
+    pending_exprs: Vec<DetachedExpr>,
+    options: ParseOptions,
+    offset: usize,
+    assignments: Vec<(Path<'static>, Range<usize>)>,
+    profile_exprs: Vec<(Path<'static>, ValueCell, Range<usize>)>,
+    templates: HashMap<String, Arc<Value>>,
+    template_definitions: HashMap<String, TemplateDefinition>,
+    is_template_definition: bool,
+    warnings: Vec<PendingWarning>,
 }
 
+// NOTE: the tree and the assignments of a template definition, so that the assignments of a
+// template are checked for duplicates the same way as the ones of the konfig.
+#[derive(Default)]
+struct TemplateDefinition {
+    root: Option<ValueCell>,
+    assignments: Vec<(Path<'static>, Range<usize>)>,
+}
+
+impl Context {
+    fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+
+    fn flush_pending_docs(&mut self) {
+        if let Some(ref last_rhs) = self.last_rhs {
+            let lexical_info = &mut last_rhs.borrow_mut().lexical_info;
+
+            lexical_info.exprs_after.append(&mut self.pending_exprs);
+
+            if let Some(docs) = self.pending_docs.take() {
+                lexical_info.docs_after.push_str(&docs);
+            }
+        }
+    }
+
+    #[inline]
+    fn range(&self, span: Span) -> Range<usize> {
+        self.offset + span.start()..self.offset + span.end()
+    }
+
+    fn warn(&mut self, lint: Lint, span: Range<usize>, message: Message) {
+        if self.options.lint_level(lint) != LintLevel::Allow {
+            self.warnings.push(PendingWarning {
+                lint,
+                span,
+                message,
+            });
+        }
+    }
+
+    fn finish(&mut self, input: &str) -> Result<Parsed> {
+        if self.last_rhs.is_none() && self.pending_exprs.is_empty() {
+            let end = input.len().saturating_sub(1);
+
+            return Err(ParseError::wrap(parse_error!(
+                Span::new(input, end, end).unwrap(),
+                "konfig should contain some expressions"
+            )));
+        }
+
+        // NOTE: konfigs with profile expressions and template definitions only are empty
+        // structures, which the expressions of the active profiles are applied to.
+        if self.last_rhs.is_none() {
+            let root = ValueCell::from(Value::Struct(Default::default()));
+
+            {
+                let lexical_info = &mut root.borrow_mut().lexical_info;
+
+                lexical_info.exprs_before.append(&mut self.pending_exprs);
+                lexical_info.docs_after = self.pending_docs.take().unwrap_or_default();
+            }
+
+            self.root = Some(root);
+        }
+
+        self.flush_pending_docs();
+
+        let mut root = self.root.take().unwrap();
+
+        lint_empty_arrays(self, &root);
+
+        self.expand(input, &mut root)?;
+
+        let mut warnings = self
+            .warnings
+            .drain(..)
+            .map(|warning| warning.render(input))
+            .collect::<Vec<_>>();
+
+        warnings.sort_by_key(|warning| warning.span.start);
+
+        let denied = warnings
+            .iter()
+            .find(|warning| self.options.lint_level(warning.lint) == LintLevel::Deny);
+
+        if let Some(denied) = denied {
+            return Err(ParseError::wrap(parse_error!(
+                Span::new(input, denied.span.start, denied.span.end).unwrap(),
+                "{} [{}]",
+                denied.message,
+                denied.lint
+            )));
+        }
+
+        Ok(Parsed {
+            value: root,
+            warnings,
+        })
+    }
+
+    // NOTE: template instances are expanded and expressions of the active profiles are applied
+    // once the whole konfig is parsed, so profiles override the values regardless of where they
+    // are defined. The last rhs reference is released first, so the tree is exclusively owned and
+    // can be modified using the public API.
+    fn expand(&mut self, input: &str, root: &mut ValueCell) -> Result<()> {
+        self.last_rhs = None;
+
+        #[cfg(debug_assertions)]
+        let _guard = crate::value::value_cell::safety_checks::SuspendParsingGuard::new();
+
+        template::expand_templates(root);
+
+        for (path, mut value, range) in self.profile_exprs.drain(..) {
+            template::expand_templates(&mut value);
+
+            profile::apply(root, path.items(), value.into_value()).map_err(|err| {
+                ParseError::wrap(parse_error!(
+                    Span::new(input, range.start, range.end).unwrap(),
+                    "{}",
+                    err
+                ))
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+// NOTE: new lexical information is added over time, so the struct can only be created with
+// `Default` outside of the crate.
 #[derive(Debug, PartialEq, Clone, Default)]
+#[non_exhaustive]
 pub struct LexicalInfo {
     pub is_rhs_seq: bool,
     pub docs_before: String,
     pub docs_after: String,
+    pub span: Option<Range<usize>>,
+    // NOTE: identifies the document the value came from, e.g. a file name. Spans are only
+    // meaningful together with the source when values of multiple documents are merged.
+    pub source: Option<Arc<str>>,
+    pub template: Option<TemplateRef>,
+    // NOTE: detached expressions between the previous value and this one, or after this value if
+    // it's the last one. Docs before the expressions are a part of them.
+    pub exprs_before: Vec<DetachedExpr>,
+    pub exprs_after: Vec<DetachedExpr>,
+    pub profile_override: Option<ProfileOverride>,
 }
 
+#[derive(Debug)]
+pub struct Parsed {
+    pub value: ValueCell,
+    pub warnings: Vec<Warning>,
+}
+
+#[inline]
 pub fn parse(input: &str) -> Result<ValueCell> {
+    parse_with_options(input, Default::default()).map(|parsed| parsed.value)
+}
+
+pub fn parse_with_options(input: &str, options: ParseOptions) -> Result<Parsed> {
+    let input = input.strip_prefix(BOM).unwrap_or(input);
+
     #[cfg(debug_assertions)]
     let _guard = crate::value::value_cell::safety_checks::ParsingGuard::new();
 
-    let ctx = Default::default();
+    let ctx = Rc::new(RefCell::new(Context::new(options)));
 
     parse_rule(Rule::konfig, input, Rc::clone(&ctx))
         .and_then(Parser::konfig)
@@ -37,24 +222,124 @@ pub fn parse(input: &str) -> Result<ValueCell> {
 
     let mut ctx = ctx.borrow_mut();
 
-    let Some(last_rhs) = ctx.last_rhs.take() else {
-        let end = input.len().saturating_sub(1);
+    ctx.finish(input)
+}
 
-        return Err(ParseError::wrap(parse_error!(
-            Span::new(input, end, end).unwrap(),
-            "konfig should contain some expressions"
-        )));
+// NOTE: parses a single expression, e.g. a command line override like `> server > port = 8080`.
+pub fn parse_expr(input: &str) -> Result<(Path<'static>, Value)> {
+    let ctx = Rc::new(RefCell::new(Context::default()));
+
+    let (path, value) = {
+        #[cfg(debug_assertions)]
+        let _guard = crate::value::value_cell::safety_checks::ParsingGuard::new();
+
+        parse_rule(Rule::expr, input, Rc::clone(&ctx))
+            .and_then(|node| {
+                let end = node.as_span().end();
+
+                if end != input.len() {
+                    Err(parse_error!(
+                        Span::new(input, end, end).unwrap(),
+                        "expected a single expression"
+                    ))
+                } else if let Some((rule, _)) = imp::expr_prefix(&node) {
+                    let kind = match rule {
+                        Rule::profile => "profiles",
+                        _ => "template definitions",
+                    };
+
+                    Err(parse_error!(
+                        node.as_span(),
+                        "{} are not allowed in a single expression",
+                        kind
+                    ))
+                } else {
+                    imp::standalone_expr(node)
+                }
+            })
+            .map_err(ParseError::wrap)?
     };
 
-    if let Some(docs) = ctx.pending_docs.take() {
-        last_rhs.borrow_mut().lexical_info.docs_after = docs;
+    ctx.borrow_mut().last_rhs = None;
+
+    Ok((path, value.into_value()))
+}
+
+// NOTE: parses a single path item at the given position of the input, e.g. in a query. Returns the
+// item and the position right after it.
+pub(crate) fn parse_path_item_at(input: &str, pos: usize) -> Result<(PathItem<'static>, usize)> {
+    parse_rule(Rule::path_item, &input[pos..], Default::default())
+        .and_then(|node| {
+            let end = pos + node.as_span().end();
+
+            Parser::path_item(node).map(|item| (item.into_owned(), end))
+        })
+        .map_err(|err| ParseError::wrap(error::relocate(err, input, pos)))
+}
+
+pub(crate) fn parse_primitive_at(input: &str, pos: usize) -> Result<(Value, usize)> {
+    parse_rule(Rule::primitive, &input[pos..], Default::default())
+        .and_then(|node| {
+            let end = pos + node.as_span().end();
+
+            Parser::primitive(node).map(|cell| (cell.into_value(), end))
+        })
+        .map_err(|err| ParseError::wrap(error::relocate(err, input, pos)))
+}
+
+pub(crate) fn error_at(input: &str, pos: usize, message: impl std::fmt::Display) -> Error {
+    ParseError::wrap(parse_error!(
+        Span::new(input, pos, pos).unwrap(),
+        "{}",
+        message
+    ))
+}
+
+// NOTE: an empty array is likely a mistake if its siblings are populated arrays.
+fn lint_empty_arrays(ctx: &mut Context, value: &ValueCell) {
+    let value = value.borrow();
+
+    let children = match &value.value {
+        Value::Sequence(seq) if !value.lexical_info.is_rhs_seq => seq.iter().collect::<Vec<_>>(),
+        Value::Map(map) | Value::Struct(map) => map.values().collect(),
+        Value::Variant(_, value) => vec![value],
+        _ => return,
+    };
+
+    let mut has_populated_arrays = false;
+    let mut empty_arrays = vec![];
+
+    for child in children {
+        let child_ref = child.borrow();
+
+        match &child_ref.value {
+            Value::Sequence(seq) if child_ref.lexical_info.is_rhs_seq => {
+                if seq.is_empty() {
+                    empty_arrays.extend(child_ref.lexical_info.span.clone());
+                } else {
+                    has_populated_arrays = true;
+                }
+            }
+            _ => {
+                drop(child_ref);
+                lint_empty_arrays(ctx, child);
+            }
+        }
     }
 
-    Ok(ctx.root.take().unwrap())
+    if has_populated_arrays {
+        for span in empty_arrays {
+            ctx.warn(
+                Lint::EmptyArray,
+                span,
+                Message::Text("empty array next to populated sibling arrays"),
+            );
+        }
+    }
 }
 
 #[allow(clippy::result_large_err)]
-fn parse_rule(rule: Rule, input: &str, context: Rc<RefCell<Context>>) -> ParseResult<Node> {
+fn parse_rule(rule: Rule, input: &str, context: Rc<RefCell<Context>>) -> ParseResult<Node<'_>> {
     Parser::parse_with_userdata(rule, input, context)
         .map_err(rename_rules)
         .and_then(|p| p.single())
@@ -0,0 +1,46 @@
+use super::warning::{Lint, LintLevel};
+use crate::error::Error;
+use crate::value::merge::MergeConflictResolver;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+pub enum DuplicateAssignment {
+    Error,
+    LastWins,
+    FirstWins,
+    DeepMerge(Box<dyn MergeConflictResolver<Error>>),
+}
+
+impl Default for DuplicateAssignment {
+    #[inline]
+    fn default() -> Self {
+        Self::Error
+    }
+}
+
+impl fmt::Debug for DuplicateAssignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => f.write_str("Error"),
+            Self::LastWins => f.write_str("LastWins"),
+            Self::FirstWins => f.write_str("FirstWins"),
+            Self::DeepMerge(_) => f.debug_tuple("DeepMerge").field(&"Box<dyn ...>").finish(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ParseOptions {
+    pub duplicate_assignment: DuplicateAssignment,
+    pub lint_levels: HashMap<Lint, LintLevel>,
+    // NOTE: expressions of other profiles are preserved in the docs, so they are serialized back
+    // unchanged.
+    pub active_profiles: HashSet<String>,
+}
+
+impl ParseOptions {
+    #[inline]
+    pub fn lint_level(&self, lint: Lint) -> LintLevel {
+        self.lint_levels.get(&lint).copied().unwrap_or_default()
+    }
+}
@@ -0,0 +1,88 @@
+use crate::error::Result;
+use crate::value::{PathItem, Value, ValueCell};
+
+// NOTE: the value of the konfig without the expressions of the active profiles, if they override
+// it. The base is `None` if only the profiles define the value. The overridden value is the one
+// right after parsing, so the serializer writes the base unless the value has changed since.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ProfileOverride {
+    pub base: Option<Value>,
+    pub value: Value,
+}
+
+// NOTE: the base keeps the lexical info of its descendants, e.g. their docs.
+impl Clone for ProfileOverride {
+    fn clone(&self) -> Self {
+        Self {
+            base: self.base.as_ref().map(Value::clone_with_lexical_info),
+            value: self.value.clone(),
+        }
+    }
+}
+
+// NOTE: the override is kept by the deepest existing value on the path if it's replaced, or by
+// the new entry of that value otherwise. Overridden values on the path are updated, so they can be
+// compared with the values after parsing.
+pub(super) fn apply(root: &mut ValueCell, path: &[PathItem], value: Value) -> Result<()> {
+    let depth = (0..path.len())
+        .find(|&depth| root.get_path(&path[..=depth]).is_none())
+        .unwrap_or(path.len());
+
+    let host = cell_at(root, &path[..depth]).unwrap();
+    let is_new_entry = path.get(depth).is_some_and(|item| is_new_entry(host, item));
+
+    let (overridden_depth, mut base) = if is_new_entry {
+        (depth + 1, None)
+    } else {
+        let base = match host.lexical_info_mut().profile_override.take() {
+            Some(profile_override) => profile_override.base,
+            None => Some(host.as_value().clone_with_lexical_info()),
+        };
+
+        (depth, base)
+    };
+
+    root.set_path(path, value)?;
+
+    for depth in 0..=path.len() {
+        let Some(cell) = cell_at(root, &path[..depth]) else {
+            break;
+        };
+
+        if depth == overridden_depth {
+            cell.lexical_info_mut().profile_override = Some(ProfileOverride {
+                base: base.take(),
+                value: cell.as_value().clone(),
+            });
+        } else if cell.lexical_info().profile_override.is_some() {
+            let value = cell.as_value().clone();
+
+            if let Some(ref mut profile_override) = cell.lexical_info_mut().profile_override {
+                profile_override.value = value;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn cell_at<'c>(root: &'c mut ValueCell, path: &[PathItem]) -> Option<&'c mut ValueCell> {
+    if path.is_empty() {
+        Some(root)
+    } else {
+        root.get_path_mut(path)
+    }
+}
+
+// NOTE: mirrors `Value::set_path` for the missing item of the path.
+fn is_new_entry(host: &Value, item: &PathItem) -> bool {
+    match (host, item) {
+        (Value::Sequence(seq), PathItem::SequenceIndex(idx)) => *idx == seq.len(),
+        (Value::Map(_), PathItem::MapKey(_)) | (Value::Struct(_), PathItem::StructFieldName(_)) => {
+            true
+        }
+        _ => false,
+    }
+}
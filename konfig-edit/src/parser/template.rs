@@ -0,0 +1,64 @@
+use crate::value::merge::PreferOtherOnConflict;
+use crate::value::{Value, ValueCell};
+use std::sync::Arc;
+
+// NOTE: the template a value was instantiated from, e.g. with `> workers > [0] = *worker`. The
+// template is a snapshot of its definition at the point of the instantiation.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TemplateRef {
+    pub name: String,
+    pub template: Arc<Value>,
+}
+
+// NOTE: template instances are parsed as empty containers, so the following expressions can
+// assign local overrides to them without conflicts. The overrides are merged on top of the
+// template once parsing is complete.
+pub(super) fn placeholder(template: &Value) -> Value {
+    match template {
+        Value::Sequence(_) => Value::Sequence(Default::default()),
+        Value::Map(_) => Value::Map(Default::default()),
+        Value::Struct(_) => Value::Struct(Default::default()),
+        Value::Variant(name, value) => Value::Variant(name.clone(), placeholder(value).into()),
+        primitive => primitive.clone(),
+    }
+}
+
+// NOTE: instances in template definitions are expanded right away, so they are turned back into
+// placeholders before the definition is assigned, the same way as the instances of the konfig.
+pub(super) fn collapse_templates(cell: &mut ValueCell) {
+    if let Some(template_ref) = cell.lexical_info().template.as_ref() {
+        *cell.as_value_mut() = placeholder(&template_ref.template);
+
+        return;
+    }
+
+    match cell.as_value_mut() {
+        Value::Sequence(seq) => seq.iter_mut().for_each(collapse_templates),
+        Value::Map(map) | Value::Struct(map) => map.values_mut().for_each(collapse_templates),
+        Value::Variant(_, value) => collapse_templates(value),
+        _ => (),
+    }
+}
+
+// NOTE: nested instances are expanded first, so they are a part of the overrides of the
+// enclosing instance. The reference is kept with the expanded value, so the serializer can write
+// the instance back as the template reference and the overrides.
+pub(super) fn expand_templates(cell: &mut ValueCell) {
+    match cell.as_value_mut() {
+        Value::Sequence(seq) => seq.iter_mut().for_each(expand_templates),
+        Value::Map(map) | Value::Struct(map) => map.values_mut().for_each(expand_templates),
+        Value::Variant(_, value) => expand_templates(value),
+        _ => (),
+    }
+
+    let Some(template_ref) = cell.lexical_info().template.as_ref() else {
+        return;
+    };
+
+    let template = Value::clone(&template_ref.template);
+    let overrides = std::mem::replace(cell.as_value_mut(), Value::Null);
+
+    *cell.as_value_mut() = template
+        .merge(PreferOtherOnConflict, overrides)
+        .unwrap_or_else(|err| match err {});
+}
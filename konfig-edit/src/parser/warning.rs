@@ -0,0 +1,126 @@
+use pest::Position;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    OverriddenAssignment,
+    LeadingZeros,
+    DocsLookLikePath,
+    EmptyArray,
+    MapKeyTrailingWhitespace,
+    UnclosedCodeFence,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 6] = [
+        Lint::OverriddenAssignment,
+        Lint::LeadingZeros,
+        Lint::DocsLookLikePath,
+        Lint::EmptyArray,
+        Lint::MapKeyTrailingWhitespace,
+        Lint::UnclosedCodeFence,
+    ];
+
+    // NOTE: codes are part of the public API and should never be changed.
+    pub fn code(self) -> &'static str {
+        match self {
+            Lint::OverriddenAssignment => "overridden-assignment",
+            Lint::LeadingZeros => "leading-zeros",
+            Lint::DocsLookLikePath => "docs-look-like-path",
+            Lint::EmptyArray => "empty-array",
+            Lint::MapKeyTrailingWhitespace => "map-key-trailing-whitespace",
+            Lint::UnclosedCodeFence => "unclosed-code-fence",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.code() == code)
+    }
+}
+
+impl fmt::Display for Lint {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LintLevel {
+    Allow,
+    #[default]
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub lint: Lint,
+    pub message: String,
+    pub span: Range<usize>,
+    pub line_col: (usize, usize),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} [{}]",
+            self.line_col.0, self.line_col.1, self.message, self.lint
+        )
+    }
+}
+
+pub(super) enum Message {
+    Replaced(Range<usize>),
+    Ignored(Range<usize>),
+    Merged(Range<usize>),
+    Text(&'static str),
+}
+
+// NOTE: warnings are recorded with byte offsets and rendered once parsing is complete, as
+// embedded konfig blocks are parsed separately from the rest of the input.
+pub(super) struct PendingWarning {
+    pub(super) lint: Lint,
+    pub(super) span: Range<usize>,
+    pub(super) message: Message,
+}
+
+impl PendingWarning {
+    pub(super) fn render(self, input: &str) -> Warning {
+        let message = match self.message {
+            Message::Replaced(other_span) => {
+                let (line, col) = line_col(input, other_span.start);
+
+                format!("value is overridden by the assignment at {line}:{col}")
+            }
+            Message::Ignored(other_span) => {
+                let (line, col) = line_col(input, other_span.start);
+
+                format!(
+                    "assignment is ignored, the path already has a value assigned at {line}:{col}"
+                )
+            }
+            Message::Merged(other_span) => {
+                let (line, col) = line_col(input, other_span.start);
+
+                format!("value is merged with the assignment at {line}:{col}")
+            }
+            Message::Text(text) => text.to_string(),
+        };
+
+        Warning {
+            lint: self.lint,
+            message,
+            line_col: line_col(input, self.span.start),
+            span: self.span,
+        }
+    }
+}
+
+fn line_col(input: &str, pos: usize) -> (usize, usize) {
+    Position::new(input, pos)
+        .map(|pos| pos.line_col())
+        .unwrap_or_default()
+}
@@ -0,0 +1,902 @@
+use super::validate::Violations;
+use super::Violation;
+use crate::error::{Error, Result};
+use crate::parser::parse;
+use crate::value::{Path, PathItem, Value, ValueCell};
+use indexmap::IndexMap;
+use regex::Regex;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// NOTE: the schemas can nest references to themselves without consuming the value, e.g.
+// `{ "$ref": "#" }`, so the nesting of references is limited.
+const MAX_REF_DEPTH: usize = 128;
+
+type Resolver = Box<dyn Fn(&str) -> Result<Value>>;
+
+// NOTE: validates values against JSON schemas (draft 2020-12), e.g. the ones published for the
+// configuration by other tools. Values are checked in their JSON representation: enum variants
+// are objects with the variant name as the only property and unit variants are strings.
+//
+// The supported keywords are `$ref`, `type`, `enum`, `const`, numeric and string bounds,
+// `pattern`, `items`, `prefixItems`, `contains`, `uniqueItems`, `properties`,
+// `patternProperties`, `additionalProperties`, `required`, `dependentRequired`,
+// `minProperties`, `maxProperties`, `allOf`, `anyOf`, `oneOf`, `not` and `if`/`then`/`else`.
+// Other keywords, e.g. `format`, are ignored.
+//
+// References are resolved within the schema, by JSON pointers or `$anchor`s. Other documents are
+// never fetched, the resolver provides them by their URIs instead.
+pub struct JsonSchemaValidator {
+    schema: Value,
+    resolver: Option<Resolver>,
+    documents: RefCell<HashMap<String, Rc<Value>>>,
+    patterns: RefCell<HashMap<String, Regex>>,
+}
+
+impl JsonSchemaValidator {
+    #[inline]
+    pub fn new(schema: Value) -> Self {
+        Self {
+            schema,
+            resolver: None,
+            documents: Default::default(),
+            patterns: Default::default(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map(Self::new)
+            .map_err(Error::custom)
+    }
+
+    // NOTE: keywords starting with `$` need to be map keys in konfig, e.g. `> ['$ref'] = "#"`, so
+    // the rest of the keywords of the same schema need to be map keys as well.
+    #[inline]
+    pub fn from_konfig(konfig: &str) -> Result<Self> {
+        Ok(Self::new(parse(konfig)?.into_value()))
+    }
+
+    // NOTE: the resolver is called with the URI of the document, i.e. the part of the reference
+    // preceding `#`, once per URI.
+    pub fn resolver(mut self, resolver: impl Fn(&str) -> Result<Value> + 'static) -> Self {
+        self.resolver = Some(Box::new(resolver));
+        self
+    }
+
+    // NOTE: errors are returned for invalid schemas, e.g. for unresolvable references, and the
+    // violations of the schema by the value are returned otherwise.
+    pub fn validate(&self, value: &ValueCell) -> Result<Vec<Violation>> {
+        let mut validation = Validation {
+            validator: self,
+            violations: Violations::default(),
+            ref_depth: 0,
+        };
+
+        validation.validate(&self.schema, &self.schema, value)?;
+
+        Ok(validation.violations.list)
+    }
+
+    #[inline]
+    pub fn check(&self, konfig: &str) -> Result<Vec<Violation>> {
+        self.validate(&parse(konfig)?)
+    }
+
+    fn document(&self, uri: &str) -> Result<Rc<Value>> {
+        if let Some(document) = self.documents.borrow().get(uri) {
+            return Ok(Rc::clone(document));
+        }
+
+        let resolver = self.resolver.as_ref().ok_or_else(|| {
+            Error::custom(format!(
+                "can't resolve the schema document `{uri}` without a resolver"
+            ))
+        })?;
+
+        let document = Rc::new(resolver(uri)?);
+
+        self.documents
+            .borrow_mut()
+            .insert(uri.into(), Rc::clone(&document));
+
+        Ok(document)
+    }
+
+    fn is_match(&self, pattern: &str, string: &str) -> Result<bool> {
+        if let Some(regex) = self.patterns.borrow().get(pattern) {
+            return Ok(regex.is_match(string));
+        }
+
+        let regex = Regex::new(pattern)
+            .map_err(|err| invalid_schema(format!("invalid pattern `{pattern}`: {err}")))?;
+
+        let is_match = regex.is_match(string);
+
+        self.patterns.borrow_mut().insert(pattern.into(), regex);
+
+        Ok(is_match)
+    }
+}
+
+// NOTE: maps a JSON pointer to a location in the value, e.g. from the output of other JSON
+// schema validators, to the path of the value. Returns `None` if there is no such value.
+pub fn path_from_pointer(value: &Value, pointer: &str) -> Option<Path<'static>> {
+    let path = Path::from_json_pointer(pointer, value).ok()?;
+
+    (path.items().is_empty() || value.get_path(path.items()).is_some()).then_some(path)
+}
+
+struct Validation<'s, 'v> {
+    validator: &'s JsonSchemaValidator,
+    violations: Violations<'v>,
+    ref_depth: usize,
+}
+
+impl<'v> Validation<'_, 'v> {
+    fn validate(&mut self, doc: &Value, schema: &Value, cell: &'v ValueCell) -> Result<()> {
+        let keywords = match schema {
+            Value::Bool(true) => return Ok(()),
+            Value::Bool(false) => {
+                self.violation(cell, "value is not allowed here");
+
+                return Ok(());
+            }
+            Value::Map(keywords) | Value::Struct(keywords) => keywords,
+            _ => return Err(invalid_schema("schemas should be objects or booleans")),
+        };
+
+        let keywords = Keywords(keywords);
+
+        if let Some(reference) = keywords.get("$ref") {
+            self.validate_ref(doc, reference, cell)?;
+        }
+
+        if let Some(ty) = keywords.get("type") {
+            self.validate_type(ty, cell)?;
+        }
+
+        if let Some(values) = keywords.get("enum") {
+            let values = values
+                .as_sequence()
+                .ok_or_else(|| invalid_schema("`enum` should be an array"))?;
+
+            if !values.iter().any(|value| value.json_eq(cell)) {
+                let expected = values.iter().map(|v| to_json(v)).collect::<Vec<_>>();
+
+                self.violation(
+                    cell,
+                    format!("value should be one of: {}", expected.join(", ")),
+                );
+            }
+        }
+
+        if let Some(value) = keywords.get("const") {
+            if !value.json_eq(cell) {
+                self.violation(cell, format!("value should be equal to {}", to_json(value)));
+            }
+        }
+
+        match cell.as_value() {
+            Value::UInt(_) | Value::Int(_) | Value::Float(_) => {
+                self.validate_number(keywords, cell)?
+            }
+            Value::String(string) | Value::UnitVariant(string) => {
+                self.validate_string(keywords, string, cell)?
+            }
+            Value::Sequence(seq) => self.validate_array(doc, keywords, seq, cell)?,
+            Value::Map(_) | Value::Struct(_) | Value::Variant(..) => {
+                self.validate_object(doc, keywords, cell)?
+            }
+            Value::Null | Value::Bool(_) => (),
+        }
+
+        self.validate_combinators(doc, keywords, cell)
+    }
+
+    fn validate_ref(&mut self, doc: &Value, reference: &Value, cell: &'v ValueCell) -> Result<()> {
+        let reference = reference
+            .as_str()
+            .ok_or_else(|| invalid_schema("`$ref` should be a string"))?;
+
+        if self.ref_depth == MAX_REF_DEPTH {
+            return Err(invalid_schema(format!(
+                "too many nested references, `{reference}` might refer to itself"
+            )));
+        }
+
+        let (uri, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+
+        let external;
+
+        let doc = match uri {
+            "" => doc,
+            uri => {
+                external = self.validator.document(uri)?;
+                &*external
+            }
+        };
+
+        let schema = resolve_fragment(doc, fragment)
+            .ok_or_else(|| invalid_schema(format!("can't resolve the reference `{reference}`")))?;
+
+        self.ref_depth += 1;
+
+        let result = self.validate(doc, schema, cell);
+
+        self.ref_depth -= 1;
+
+        result
+    }
+
+    fn validate_type(&mut self, ty: &Value, cell: &'v ValueCell) -> Result<()> {
+        let types = match ty {
+            Value::String(ty) => vec![ty.as_str()],
+            Value::Sequence(types) => types
+                .iter()
+                .map(|ty| ty.as_str())
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| {
+                    invalid_schema("`type` should be a string or an array of strings")
+                })?,
+            _ => {
+                return Err(invalid_schema(
+                    "`type` should be a string or an array of strings",
+                ))
+            }
+        };
+
+        let value = cell.as_value();
+
+        let is_match = |ty: &str| match (ty, value) {
+            ("null", Value::Null)
+            | ("boolean", Value::Bool(_))
+            | ("number" | "integer", Value::UInt(_) | Value::Int(_))
+            | ("number", Value::Float(_))
+            | ("string", Value::String(_) | Value::UnitVariant(_))
+            | ("array", Value::Sequence(_))
+            | ("object", Value::Map(_) | Value::Struct(_) | Value::Variant(..)) => true,
+            ("integer", Value::Float(v)) => v.fract() == 0.0,
+            _ => false,
+        };
+
+        if !types.iter().any(|ty| is_match(ty)) {
+            self.violation(
+                cell,
+                format!(
+                    "invalid type: {}, expected {}",
+                    value.kind(),
+                    types.join(" or ")
+                ),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn validate_number(&mut self, keywords: Keywords<'_>, cell: &'v ValueCell) -> Result<()> {
+        let number = cell.as_f64().expect("value is a number");
+        let bound = |name: &str| -> Result<Option<f64>> {
+            keywords
+                .get(name)
+                .map(|bound| {
+                    bound
+                        .as_f64()
+                        .ok_or_else(|| invalid_schema(format!("`{name}` should be a number")))
+                })
+                .transpose()
+        };
+
+        if let Some(min) = bound("minimum")?.filter(|min| number < *min) {
+            self.violation(cell, format!("{number} is less than the minimum of {min}"));
+        }
+
+        if let Some(max) = bound("maximum")?.filter(|max| number > *max) {
+            self.violation(
+                cell,
+                format!("{number} is greater than the maximum of {max}"),
+            );
+        }
+
+        if let Some(min) = bound("exclusiveMinimum")?.filter(|min| number <= *min) {
+            self.violation(cell, format!("{number} should be greater than {min}"));
+        }
+
+        if let Some(max) = bound("exclusiveMaximum")?.filter(|max| number >= *max) {
+            self.violation(cell, format!("{number} should be less than {max}"));
+        }
+
+        if let Some(divisor) = bound("multipleOf")? {
+            if divisor <= 0.0 {
+                return Err(invalid_schema("`multipleOf` should be greater than 0"));
+            }
+
+            if (number / divisor).fract() != 0.0 {
+                self.violation(cell, format!("{number} is not a multiple of {divisor}"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_string(
+        &mut self,
+        keywords: Keywords<'_>,
+        string: &str,
+        cell: &'v ValueCell,
+    ) -> Result<()> {
+        let len = string.chars().count();
+
+        if let Some(min) = keywords.count("minLength")?.filter(|min| len < *min) {
+            self.violation(
+                cell,
+                format!("string should have at least {min} characters, found {len}"),
+            );
+        }
+
+        if let Some(max) = keywords.count("maxLength")?.filter(|max| len > *max) {
+            self.violation(
+                cell,
+                format!("string should have at most {max} characters, found {len}"),
+            );
+        }
+
+        if let Some(pattern) = keywords.get("pattern") {
+            let pattern = pattern
+                .as_str()
+                .ok_or_else(|| invalid_schema("`pattern` should be a string"))?;
+
+            if !self.validator.is_match(pattern, string)? {
+                self.violation(
+                    cell,
+                    format!("string doesn't match the pattern `{pattern}`"),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_array(
+        &mut self,
+        doc: &Value,
+        keywords: Keywords<'_>,
+        seq: &'v [ValueCell],
+        cell: &'v ValueCell,
+    ) -> Result<()> {
+        let len = seq.len();
+
+        if let Some(min) = keywords.count("minItems")?.filter(|min| len < *min) {
+            self.violation(
+                cell,
+                format!("sequence should have at least {min} items, found {len}"),
+            );
+        }
+
+        if let Some(max) = keywords.count("maxItems")?.filter(|max| len > *max) {
+            self.violation(
+                cell,
+                format!("sequence should have at most {max} items, found {len}"),
+            );
+        }
+
+        if keywords.get("uniqueItems").and_then(Value::as_bool) == Some(true) {
+            let duplicate = (0..len).find_map(|idx| {
+                (0..idx)
+                    .find(|prev| seq[*prev].json_eq(&seq[idx]))
+                    .map(|prev| (prev, idx))
+            });
+
+            if let Some((prev, idx)) = duplicate {
+                self.violation(
+                    cell,
+                    format!(
+                        "sequence items should be unique, but items {prev} and {idx} are equal"
+                    ),
+                );
+            }
+        }
+
+        let prefix = match keywords.get("prefixItems") {
+            Some(prefix) => prefix
+                .as_sequence()
+                .ok_or_else(|| invalid_schema("`prefixItems` should be an array"))?
+                .as_slice(),
+            None => &[],
+        };
+
+        for (idx, item) in seq.iter().enumerate() {
+            let schema = match prefix.get(idx) {
+                Some(schema) => Some(schema.as_value()),
+                None => keywords.get("items"),
+            };
+
+            if let Some(schema) = schema {
+                self.violations.enter(cell, PathItem::SequenceIndex(idx));
+                self.validate(doc, schema, item)?;
+                self.violations.leave();
+            }
+        }
+
+        if let Some(contains) = keywords.get("contains") {
+            let mut matching = 0;
+
+            for (idx, item) in seq.iter().enumerate() {
+                self.violations.enter(cell, PathItem::SequenceIndex(idx));
+
+                if self.is_valid(doc, contains, item)? {
+                    matching += 1;
+                }
+
+                self.violations.leave();
+            }
+
+            let min = keywords.count("minContains")?.unwrap_or(1);
+
+            if matching < min {
+                self.violation(
+                    cell,
+                    format!(
+                        "sequence should contain at least {min} matching items, found {matching}"
+                    ),
+                );
+            }
+
+            if let Some(max) = keywords.count("maxContains")?.filter(|max| matching > *max) {
+                self.violation(
+                    cell,
+                    format!(
+                        "sequence should contain at most {max} matching items, found {matching}"
+                    ),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_object(
+        &mut self,
+        doc: &Value,
+        keywords: Keywords<'_>,
+        cell: &'v ValueCell,
+    ) -> Result<()> {
+        let entries = object_entries(cell.as_value()).collect::<Vec<_>>();
+        let len = entries.len();
+
+        if let Some(required) = keywords.get("required") {
+            for name in names(required, "required")? {
+                if !entries.iter().any(|(_, key, _)| *key == name) {
+                    self.violation(cell, format!("missing property `{name}`"));
+                }
+            }
+        }
+
+        if let Some(dependencies) = keywords.get("dependentRequired") {
+            let dependencies = as_object(dependencies)
+                .ok_or_else(|| invalid_schema("`dependentRequired` should be an object"))?;
+
+            for (name, required) in dependencies {
+                if !entries.iter().any(|(_, key, _)| key == name) {
+                    continue;
+                }
+
+                for dependency in names(required, "dependentRequired")? {
+                    if !entries.iter().any(|(_, key, _)| *key == dependency) {
+                        self.violation(
+                            cell,
+                            format!("missing property `{dependency}`, required by `{name}`"),
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(min) = keywords.count("minProperties")?.filter(|min| len < *min) {
+            self.violation(
+                cell,
+                format!("object should have at least {min} properties, found {len}"),
+            );
+        }
+
+        if let Some(max) = keywords.count("maxProperties")?.filter(|max| len > *max) {
+            self.violation(
+                cell,
+                format!("object should have at most {max} properties, found {len}"),
+            );
+        }
+
+        let schemas = |name: &str| -> Result<Option<&IndexMap<String, ValueCell>>> {
+            keywords
+                .get(name)
+                .map(|schemas| {
+                    as_object(schemas)
+                        .ok_or_else(|| invalid_schema(format!("`{name}` should be an object")))
+                })
+                .transpose()
+        };
+
+        let properties = schemas("properties")?;
+        let pattern_properties = schemas("patternProperties")?;
+        let additional = keywords.get("additionalProperties");
+
+        for (item, key, value) in entries {
+            let mut is_evaluated = false;
+
+            self.violations.enter(cell, item);
+
+            if let Some(schema) = properties.and_then(|properties| properties.get(key)) {
+                is_evaluated = true;
+                self.validate(doc, schema, value)?;
+            }
+
+            for (pattern, schema) in pattern_properties.into_iter().flatten() {
+                if self.validator.is_match(pattern, key)? {
+                    is_evaluated = true;
+                    self.validate(doc, schema, value)?;
+                }
+            }
+
+            match additional {
+                Some(Value::Bool(false)) if !is_evaluated => {
+                    self.violation(value, format!("unknown property `{key}`"))
+                }
+                Some(schema) if !is_evaluated => self.validate(doc, schema, value)?,
+                _ => (),
+            }
+
+            self.violations.leave();
+        }
+
+        Ok(())
+    }
+
+    fn validate_combinators(
+        &mut self,
+        doc: &Value,
+        keywords: Keywords<'_>,
+        cell: &'v ValueCell,
+    ) -> Result<()> {
+        let subschemas = |name: &str| -> Result<&[ValueCell]> {
+            match keywords.get(name) {
+                Some(schemas) => schemas
+                    .as_sequence()
+                    .map(Vec::as_slice)
+                    .ok_or_else(|| invalid_schema(format!("`{name}` should be an array"))),
+                None => Ok(&[]),
+            }
+        };
+
+        for schema in subschemas("allOf")? {
+            self.validate(doc, schema, cell)?;
+        }
+
+        let any_of = subschemas("anyOf")?;
+
+        if !any_of.is_empty() && !self.any_valid(doc, any_of, cell)? {
+            self.violation(cell, "value doesn't match any of the schemas in `anyOf`");
+        }
+
+        let one_of = subschemas("oneOf")?;
+
+        if !one_of.is_empty() {
+            let mut matching = 0;
+
+            for schema in one_of {
+                if self.is_valid(doc, schema, cell)? {
+                    matching += 1;
+                }
+            }
+
+            match matching {
+                0 => self.violation(cell, "value doesn't match any of the schemas in `oneOf`"),
+                1 => (),
+                _ => self.violation(
+                    cell,
+                    format!("value matches {matching} schemas in `oneOf`, expected exactly one"),
+                ),
+            }
+        }
+
+        if let Some(schema) = keywords.get("not") {
+            if self.is_valid(doc, schema, cell)? {
+                self.violation(cell, "value shouldn't match the schema in `not`");
+            }
+        }
+
+        if let Some(condition) = keywords.get("if") {
+            let branch = match self.is_valid(doc, condition, cell)? {
+                true => keywords.get("then"),
+                false => keywords.get("else"),
+            };
+
+            if let Some(schema) = branch {
+                self.validate(doc, schema, cell)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn any_valid(
+        &mut self,
+        doc: &Value,
+        schemas: &[ValueCell],
+        cell: &'v ValueCell,
+    ) -> Result<bool> {
+        for schema in schemas {
+            if self.is_valid(doc, schema, cell)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    // NOTE: the violations of the subschema are not reported.
+    fn is_valid(&mut self, doc: &Value, schema: &Value, cell: &'v ValueCell) -> Result<bool> {
+        let len = self.violations.list.len();
+
+        self.validate(doc, schema, cell)?;
+
+        let is_valid = self.violations.list.len() == len;
+
+        self.violations.list.truncate(len);
+
+        Ok(is_valid)
+    }
+
+    #[inline]
+    fn violation(&mut self, cell: &ValueCell, message: impl Into<String>) {
+        self.violations.push(cell, message.into());
+    }
+}
+
+// NOTE: entries of the value in its JSON representation. Variants are objects with the variant
+// name as the only property.
+fn object_entries(value: &Value) -> impl Iterator<Item = (PathItem<'static>, &str, &ValueCell)> {
+    let (map, variant) = match value {
+        Value::Map(map) | Value::Struct(map) => (Some(map), None),
+        Value::Variant(name, value) => (None, Some((name, value))),
+        _ => (None, None),
+    };
+
+    let is_struct = matches!(value, Value::Struct(_));
+
+    let map_entries = map.into_iter().flatten().map(move |(key, value)| {
+        let item = match is_struct {
+            true => PathItem::StructFieldName(key.clone().into()),
+            false => PathItem::MapKey(key.clone().into()),
+        };
+
+        (item, key.as_str(), value)
+    });
+
+    let variant_entry = variant.into_iter().map(|(name, value)| {
+        (
+            PathItem::VariantName(name.clone().into()),
+            name.as_str(),
+            value,
+        )
+    });
+
+    map_entries.chain(variant_entry)
+}
+
+fn resolve_fragment<'d>(doc: &'d Value, fragment: &str) -> Option<&'d Value> {
+    if fragment.is_empty() || fragment.starts_with('/') {
+        let path = Path::from_json_pointer(fragment, doc).ok()?;
+
+        if path.items().is_empty() {
+            return Some(doc);
+        }
+
+        return doc.get_path(path.items()).map(ValueCell::as_value);
+    }
+
+    find_anchor(doc, fragment)
+}
+
+fn find_anchor<'d>(schema: &'d Value, anchor: &str) -> Option<&'d Value> {
+    match schema {
+        Value::Map(keywords) | Value::Struct(keywords) => {
+            if keywords.get("$anchor").and_then(|a| a.as_str()) == Some(anchor) {
+                return Some(schema);
+            }
+
+            keywords
+                .values()
+                .find_map(|value| find_anchor(value, anchor))
+        }
+        Value::Sequence(seq) => seq.iter().find_map(|value| find_anchor(value, anchor)),
+        _ => None,
+    }
+}
+
+fn as_object(value: &Value) -> Option<&IndexMap<String, ValueCell>> {
+    value.as_map().or_else(|| value.as_struct())
+}
+
+fn names<'s>(value: &'s Value, keyword: &str) -> Result<Vec<&'s str>> {
+    value
+        .as_sequence()
+        .and_then(|names| names.iter().map(|name| name.as_str()).collect())
+        .ok_or_else(|| invalid_schema(format!("`{keyword}` should be an array of strings")))
+}
+
+#[derive(Clone, Copy)]
+struct Keywords<'s>(&'s IndexMap<String, ValueCell>);
+
+impl<'s> Keywords<'s> {
+    #[inline]
+    fn get(self, name: &str) -> Option<&'s Value> {
+        self.0.get(name).map(ValueCell::as_value)
+    }
+
+    fn count(self, name: &str) -> Result<Option<usize>> {
+        self.get(name)
+            .map(|count| {
+                count
+                    .as_u64()
+                    .and_then(|count| count.try_into().ok())
+                    .ok_or_else(|| {
+                        invalid_schema(format!("`{name}` should be a non-negative integer"))
+                    })
+            })
+            .transpose()
+    }
+}
+
+fn to_json(value: &Value) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "<invalid value>".into())
+}
+
+#[inline]
+fn invalid_schema(message: impl std::fmt::Display) -> Error {
+    Error::custom(format!("invalid JSON schema: {message}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::tests::{describe, INPUT};
+    use indoc::indoc;
+
+    const SCHEMA: &str = r##"{
+        "type": "object",
+        "properties": {
+            "server": { "$ref": "#/$defs/server" },
+            "mode": { "enum": ["Fast", "Normal"] },
+            "storage": {
+                "oneOf": [
+                    { "const": "Memory" },
+                    {
+                        "type": "object",
+                        "properties": { "Disk": { "$ref": "common.json#size" } },
+                        "additionalProperties": false
+                    }
+                ]
+            },
+            "hosts": { "type": "array", "items": { "type": "string" }, "uniqueItems": true },
+            "labels": { "additionalProperties": { "type": "string" } }
+        },
+        "required": ["server", "timeout"],
+        "additionalProperties": false,
+        "$defs": {
+            "server": {
+                "properties": {
+                    "host": { "type": "string", "pattern": "^[a-z.]+$" },
+                    "port": { "$ref": "common.json#/$defs/port" },
+                    "tls": { "type": "boolean" }
+                }
+            }
+        }
+    }"##;
+
+    const COMMON: &str = r##"{
+        "$defs": {
+            "port": { "type": "integer", "minimum": 1, "maximum": 65535 },
+            "size": {
+                "$anchor": "size",
+                "properties": { "size": { "type": "integer" } }
+            }
+        }
+    }"##;
+
+    #[test]
+    fn validation() {
+        let validator = JsonSchemaValidator::from_json(SCHEMA)
+            .unwrap()
+            .resolver(|uri| match uri {
+                "common.json" => serde_json::from_str(COMMON).map_err(Error::custom),
+                _ => Err(Error::custom(format!("unknown document `{uri}`"))),
+            });
+
+        assert_eq!(
+            describe(INPUT, validator.check(INPUT).unwrap()),
+            [
+                "1:19: missing property `timeout` at path: >",
+                "1:19: string doesn't match the pattern `^[a-z.]+$` at path: > server > host",
+                "3:19: 70000 is greater than the maximum of 65535 at path: > server > port",
+                "5:18: invalid type: unit variant, expected boolean at path: > server > tls",
+                "9:10: value should be one of: \"Fast\", \"Normal\" at path: > mode",
+                "11:29: value doesn't match any of the schemas in `oneOf` at path: > storage",
+                "13:11: sequence items should be unique, but items 0 and 1 are equal at path: > hosts",
+                "15:23: invalid type: integer, expected string at path: > labels > [\"team\"]",
+                "17:11: unknown property `extra` at path: > extra",
+            ]
+        );
+
+        assert!(JsonSchemaValidator::from_json(SCHEMA)
+            .unwrap()
+            .check(INPUT)
+            .is_err());
+
+        assert!(JsonSchemaValidator::new(true.into())
+            .check(INPUT)
+            .unwrap()
+            .is_empty());
+
+        assert!(JsonSchemaValidator::from_json(r##"{ "$ref": "#" }"##)
+            .unwrap()
+            .check(INPUT)
+            .is_err());
+    }
+
+    #[test]
+    fn konfig_schema() {
+        let validator = JsonSchemaValidator::from_konfig(indoc! {"
+            > ['type'] = \"object\"
+
+            > ['properties'] > port > ['$ref'] = \"#/$defs/port\"
+
+            > ['$defs'] > port > type = [\"integer\", \"null\"]
+
+            > ['$defs'] > port > exclusiveMinimum = 0
+        "})
+        .unwrap();
+
+        assert_eq!(
+            describe("> port = 0", validator.check("> port = 0").unwrap()),
+            ["1:10: 0 should be greater than 0 at path: > port"]
+        );
+
+        assert!(validator.check("> port = null").unwrap().is_empty());
+    }
+
+    #[test]
+    fn pointers() {
+        let value = parse(INPUT).unwrap();
+
+        assert_eq!(
+            path_from_pointer(&value, "/server/port")
+                .unwrap()
+                .to_string(),
+            "> server > port"
+        );
+
+        assert_eq!(
+            path_from_pointer(&value, "/storage/Disk/size")
+                .unwrap()
+                .to_string(),
+            "> storage > `Disk` > size"
+        );
+
+        assert_eq!(
+            path_from_pointer(&value, "/hosts/1").unwrap().to_string(),
+            "> hosts > [1]"
+        );
+
+        assert_eq!(
+            path_from_pointer(&value, "/labels/team")
+                .unwrap()
+                .to_string(),
+            "> labels > [\"team\"]"
+        );
+
+        assert_eq!(path_from_pointer(&value, "").unwrap().to_string(), ">");
+        assert_eq!(path_from_pointer(&value, "/hosts/2"), None);
+        assert_eq!(path_from_pointer(&value, "server"), None);
+    }
+}
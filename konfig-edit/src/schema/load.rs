@@ -0,0 +1,323 @@
+use super::{Field, Schema, Type};
+use crate::error::{Error, Result};
+use crate::parser::parse;
+use crate::value::{Path, PathItem, Value, ValueCell};
+use indexmap::IndexMap;
+
+impl Type {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "Null" => Type::Null,
+            "Bool" => Type::Bool,
+            "Integer" => Type::Integer,
+            "Float" => Type::Float,
+            "String" => Type::String,
+            "Enum" => Type::Enum,
+            "Sequence" => Type::Sequence,
+            "Map" => Type::Map,
+            "Struct" => Type::Struct,
+            _ => return None,
+        })
+    }
+}
+
+impl Schema {
+    // NOTE: schemas are konfig documents as well, so they can be written without any Rust code,
+    // e.g.:
+    //
+    // > type = `Struct`
+    //
+    // > fields > port > type = `Integer`
+    //
+    // > fields > port > max = 65535
+    //
+    // > fields > hosts > type = `Sequence`
+    //
+    // > fields > hosts > items > type = `String`
+    //
+    // > fields > hosts > required = false
+    //
+    // Variants are either a sequence of unit variant names or a structure of variant names to
+    // the schemas of their values, with `null` for unit variants.
+    pub fn load(konfig: &str) -> Result<Self> {
+        Self::load_value(&parse(konfig)?.into_value())
+    }
+
+    pub fn load_value(schema: &Value) -> Result<Self> {
+        load(&mut Path::default(), schema, false).map(|field| field.schema)
+    }
+}
+
+fn load(path: &mut Path<'static>, value: &Value, is_field: bool) -> Result<Field> {
+    let mut schema = Schema::default();
+    let mut required = true;
+
+    for (item, key, value) in entries(path, value, "schema should be a structure")? {
+        path.push(item);
+
+        match key.as_str() {
+            "type" => schema.ty = Some(ty(path, value)?),
+            "nullable" => schema.nullable = boolean(path, value)?,
+            "required" if is_field => required = boolean(path, value)?,
+            "min" => schema.min = Some(number(path, value)?),
+            "max" => schema.max = Some(number(path, value)?),
+            "pattern" => {
+                let pattern = value
+                    .as_str()
+                    .ok_or_else(|| error(path, "expected a string"))?;
+
+                schema = schema
+                    .pattern(pattern)
+                    .map_err(|err| error(path, err.to_string()))?;
+            }
+            "variants" => schema.variants = variants(path, value)?,
+            "items" | "values" => schema.items = Some(Box::new(load(path, value, false)?.schema)),
+            "min_items" => schema.min_items = Some(count(path, value)?),
+            "max_items" => schema.max_items = Some(count(path, value)?),
+            "fields" => {
+                for (item, name, value) in entries(path, value, "fields should be a structure")? {
+                    path.push(item);
+                    schema.fields.insert(name.clone(), load(path, value, true)?);
+                    path.pop();
+                }
+            }
+            "deny_unknown_fields" => schema.deny_unknown_fields = boolean(path, value)?,
+            _ => return Err(error(path, format!("unknown schema keyword `{key}`"))),
+        }
+
+        path.pop();
+    }
+
+    Ok(Field { schema, required })
+}
+
+fn entries<'v>(
+    path: &Path,
+    value: &'v Value,
+    message: &str,
+) -> Result<Vec<(PathItem<'static>, &'v String, &'v ValueCell)>> {
+    let item = match value {
+        Value::Struct(_) => |key: &String| PathItem::StructFieldName(key.clone().into()),
+        Value::Map(_) => |key: &String| PathItem::MapKey(key.clone().into()),
+        _ => return Err(error(path, message)),
+    };
+
+    let map = value
+        .as_struct()
+        .or_else(|| value.as_map())
+        .expect("value is a structure or a map");
+
+    Ok(map
+        .iter()
+        .map(|(key, value)| (item(key), key, value))
+        .collect())
+}
+
+fn variants(path: &mut Path<'static>, value: &Value) -> Result<IndexMap<String, Option<Schema>>> {
+    if let Some(seq) = value.as_sequence() {
+        return seq
+            .iter()
+            .map(|name| match name.as_value() {
+                Value::UnitVariant(name) | Value::String(name) => Ok((name.clone(), None)),
+                _ => Err(error(path, "expected a sequence of variant names")),
+            })
+            .collect();
+    }
+
+    let mut variants = IndexMap::new();
+
+    for (item, name, value) in entries(
+        path,
+        value,
+        "variants should be a sequence of names or a structure of schemas",
+    )? {
+        path.push(item);
+
+        let schema = match value.as_value() {
+            Value::Null => None,
+            value => Some(load(path, value, false)?.schema),
+        };
+
+        variants.insert(name.clone(), schema);
+        path.pop();
+    }
+
+    Ok(variants)
+}
+
+fn ty(path: &Path, value: &Value) -> Result<Type> {
+    let name = value
+        .as_unit_variant()
+        .ok_or_else(|| error(path, "expected a unit variant"))?;
+
+    Type::from_name(name).ok_or_else(|| error(path, format!("unknown schema type `{name}`")))
+}
+
+fn boolean(path: &Path, value: &Value) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| error(path, "expected a boolean"))
+}
+
+fn number(path: &Path, value: &Value) -> Result<f64> {
+    value
+        .as_f64()
+        .ok_or_else(|| error(path, "expected a number"))
+}
+
+fn count(path: &Path, value: &Value) -> Result<usize> {
+    value
+        .as_u64()
+        .and_then(|count| count.try_into().ok())
+        .ok_or_else(|| error(path, "expected a non-negative integer"))
+}
+
+fn error(path: &Path, message: impl std::fmt::Display) -> Error {
+    Error::custom(format!("{message} at path: {path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::tests::{describe, INPUT};
+    use indoc::indoc;
+
+    #[test]
+    fn load_schema() {
+        let schema = Schema::load(indoc! {"
+            > type = `Struct`
+
+            > deny_unknown_fields = true
+
+            > fields > server > type = `Struct`
+
+            > fields > server > fields > host > type = `String`
+
+            > fields > server > fields > host > pattern = \"^[a-z.]+$\"
+
+            > fields > server > fields > port > type = `Integer`
+
+            > fields > server > fields > port > min = 1
+
+            > fields > server > fields > port > max = 65535
+
+            > fields > server > fields > tls > type = `Bool`
+
+            > fields > server > fields > tls > required = false
+
+            > fields > mode > type = `Enum`
+
+            > fields > mode > variants = [`Fast`, `Normal`]
+
+            > fields > storage > type = `Enum`
+
+            > fields > storage > variants > Memory = null
+
+            > fields > storage > variants > Disk > type = `Struct`
+
+            > fields > storage > variants > Disk > fields > size > type = `Integer`
+
+            > fields > hosts > type = `Sequence`
+
+            > fields > hosts > items > type = `String`
+
+            > fields > hosts > max_items = 1
+
+            > fields > labels > type = `Map`
+
+            > fields > labels > values > type = `String`
+
+            > fields > labels > nullable = true
+        "})
+        .unwrap();
+
+        assert_eq!(
+            describe(INPUT, schema.check(INPUT).unwrap()),
+            [
+                "1:19: string doesn't match the pattern `^[a-z.]+$` at path: > server > host",
+                "3:19: 70000 is greater than the maximum of 65535 at path: > server > port",
+                "5:18: invalid type: unit variant, expected boolean at path: > server > tls",
+                "9:10: unknown variant `Slow`, expected one of `Fast`, `Normal` at path: > mode",
+                "11:29: invalid type: string, expected integer at path: > storage > `Disk` > size",
+                "13:11: sequence should have at most 1 items, found 2 at path: > hosts",
+                "15:23: invalid type: integer, expected string at path: > labels > [\"team\"]",
+                "17:11: unknown field `extra` at path: > extra",
+            ]
+        );
+    }
+
+    #[test]
+    fn variants() {
+        let schema = Schema::load("> type = `Enum`\n\n> variants = [`Fast`, \"Normal\"]").unwrap();
+
+        assert_eq!(schema.ty(), Some(Type::Enum));
+        assert_eq!(
+            schema.variants.keys().collect::<Vec<_>>(),
+            ["Fast", "Normal"]
+        );
+        assert!(schema.variants.values().all(Option::is_none));
+
+        let schema = Schema::load(indoc! {"
+            > variants > Memory = null
+
+            > variants > Disk > type = `Integer`
+        "})
+        .unwrap();
+
+        assert!(schema.variants["Memory"].is_none());
+        assert_eq!(
+            schema.variants["Disk"].as_ref().and_then(Schema::ty),
+            Some(Type::Integer)
+        );
+    }
+
+    #[test]
+    fn load_errors() {
+        let error = |schema: &str| Schema::load(schema).unwrap_err().to_string();
+
+        assert_eq!(
+            error("> type = `Number`"),
+            "unknown schema type `Number` at path: > type"
+        );
+
+        assert_eq!(
+            error("> type = \"Integer\""),
+            "expected a unit variant at path: > type"
+        );
+
+        assert_eq!(
+            error("> fields > port > maximum = 1"),
+            "unknown schema keyword `maximum` at path: > fields > port > maximum"
+        );
+
+        assert_eq!(
+            error("> required = false"),
+            "unknown schema keyword `required` at path: > required"
+        );
+
+        assert_eq!(
+            error("> min_items = -1"),
+            "expected a non-negative integer at path: > min_items"
+        );
+
+        assert_eq!(error("> min = true"), "expected a number at path: > min");
+
+        assert_eq!(
+            error("> nullable = 1"),
+            "expected a boolean at path: > nullable"
+        );
+
+        assert_eq!(
+            error("> fields = [1]"),
+            "fields should be a structure at path: > fields"
+        );
+
+        assert_eq!(
+            error("> variants = [1]"),
+            "expected a sequence of variant names at path: > variants"
+        );
+
+        assert!(error("> pattern = \"(\"").starts_with("invalid pattern `(`"));
+        assert!(Schema::load("> a =").is_err());
+    }
+}
@@ -0,0 +1,359 @@
+mod json;
+mod load;
+mod validate;
+
+pub use self::json::{path_from_pointer, JsonSchemaValidator};
+
+use crate::error::{Error, Result};
+use crate::parser::parse;
+use crate::value::{Path, ValueCell};
+use indexmap::IndexMap;
+use pest::Position;
+use regex::Regex;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    Null,
+    Bool,
+    // NOTE: signed and unsigned integers.
+    Integer,
+    // NOTE: floats and integers.
+    Float,
+    String,
+    // NOTE: unit variants and variants with values.
+    Enum,
+    Sequence,
+    // NOTE: maps and structures are interchangeable, as they are for deserialization.
+    Map,
+    Struct,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Type::Null => "null",
+            Type::Bool => "boolean",
+            Type::Integer => "integer",
+            Type::Float => "float",
+            Type::String => "string",
+            Type::Enum => "enum",
+            Type::Sequence => "sequence",
+            Type::Map => "map",
+            Type::Struct => "structure",
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Field {
+    schema: Schema,
+    required: bool,
+}
+
+// NOTE: describes the expected shape of a value independently of Rust types, e.g.:
+//
+// Schema::structure()
+//     .field("port", Schema::integer().min(1).max(65535))
+//     .optional_field("hosts", Schema::sequence(Schema::string()).min_items(1))
+//
+// Constraints which don't apply to the type of the schema are ignored. A schema without a type
+// accepts any value.
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    ty: Option<Type>,
+    nullable: bool,
+    min: Option<f64>,
+    max: Option<f64>,
+    pattern: Option<Regex>,
+    variants: IndexMap<String, Option<Schema>>,
+    items: Option<Box<Schema>>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    fields: IndexMap<String, Field>,
+    deny_unknown_fields: bool,
+}
+
+impl Schema {
+    #[inline]
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn new(ty: Type) -> Self {
+        Self {
+            ty: Some(ty),
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn null() -> Self {
+        Self::new(Type::Null)
+    }
+
+    #[inline]
+    pub fn boolean() -> Self {
+        Self::new(Type::Bool)
+    }
+
+    #[inline]
+    pub fn integer() -> Self {
+        Self::new(Type::Integer)
+    }
+
+    #[inline]
+    pub fn float() -> Self {
+        Self::new(Type::Float)
+    }
+
+    #[inline]
+    pub fn string() -> Self {
+        Self::new(Type::String)
+    }
+
+    #[inline]
+    pub fn sequence(items: Schema) -> Self {
+        Self::new(Type::Sequence).items(items)
+    }
+
+    #[inline]
+    pub fn map(values: Schema) -> Self {
+        Self::new(Type::Map).items(values)
+    }
+
+    #[inline]
+    pub fn structure() -> Self {
+        Self::new(Type::Struct)
+    }
+
+    // NOTE: an enum of unit variants. Variants with values are added with `variant`.
+    pub fn enumeration<S>(variants: impl IntoIterator<Item = S>) -> Self
+    where
+        S: Into<String>,
+    {
+        let mut schema = Self::new(Type::Enum);
+
+        schema.variants = variants.into_iter().map(|v| (v.into(), None)).collect();
+        schema
+    }
+
+    #[inline]
+    pub fn variant(mut self, name: impl Into<String>, value: Schema) -> Self {
+        self.variants.insert(name.into(), Some(value));
+        self
+    }
+
+    // NOTE: accepts `null` in addition to the values of the type, e.g. for `Option` fields.
+    #[inline]
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    #[inline]
+    pub fn min(mut self, min: impl Into<f64>) -> Self {
+        self.min = Some(min.into());
+        self
+    }
+
+    #[inline]
+    pub fn max(mut self, max: impl Into<f64>) -> Self {
+        self.max = Some(max.into());
+        self
+    }
+
+    // NOTE: the pattern matches anywhere in the string unless it's anchored with `^` and `$`.
+    pub fn pattern(mut self, pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern)
+            .map_err(|err| Error::custom(format!("invalid pattern `{pattern}`: {err}")))?;
+
+        self.pattern = Some(regex);
+
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn items(mut self, items: Schema) -> Self {
+        self.items = Some(Box::new(items));
+        self
+    }
+
+    #[inline]
+    pub fn min_items(mut self, min: usize) -> Self {
+        self.min_items = Some(min);
+        self
+    }
+
+    #[inline]
+    pub fn max_items(mut self, max: usize) -> Self {
+        self.max_items = Some(max);
+        self
+    }
+
+    #[inline]
+    pub fn field(mut self, name: impl Into<String>, schema: Schema) -> Self {
+        let field = Field {
+            schema,
+            required: true,
+        };
+
+        self.fields.insert(name.into(), field);
+        self
+    }
+
+    #[inline]
+    pub fn optional_field(mut self, name: impl Into<String>, schema: Schema) -> Self {
+        let field = Field {
+            schema,
+            required: false,
+        };
+
+        self.fields.insert(name.into(), field);
+        self
+    }
+
+    #[inline]
+    pub fn deny_unknown_fields(mut self) -> Self {
+        self.deny_unknown_fields = true;
+        self
+    }
+
+    #[inline]
+    pub fn ty(&self) -> Option<Type> {
+        self.ty
+    }
+
+    // NOTE: returns all the violations rather than the first one, in the order of the values.
+    #[inline]
+    pub fn validate(&self, value: &ValueCell) -> Vec<Violation> {
+        validate::validate(self, value)
+    }
+
+    // NOTE: parses the konfig document and validates it. Parsing errors are returned as errors.
+    #[inline]
+    pub fn check(&self, konfig: &str) -> Result<Vec<Violation>> {
+        Ok(self.validate(&parse(konfig)?))
+    }
+}
+
+// NOTE: the span and the source are the ones of the value violating the schema. Containers have
+// no spans of their own, so they are located by the first expression they are defined with, or
+// by the closest enclosing value which has one, e.g. for missing fields of empty structures.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub path: Path<'static>,
+    pub message: String,
+    pub span: Option<Range<usize>>,
+    pub source: Option<Arc<str>>,
+}
+
+impl Violation {
+    // NOTE: 1-based line and column of the value in the input it was parsed from.
+    pub fn line_col(&self, input: &str) -> Option<(usize, usize)> {
+        let span = self.span.as_ref()?;
+
+        Position::new(input, span.start).map(|pos| pos.line_col())
+    }
+}
+
+impl fmt::Display for Violation {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at path: {}", self.message, self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    pub(super) fn describe(input: &str, violations: Vec<Violation>) -> Vec<String> {
+        violations
+            .iter()
+            .map(|violation| match violation.line_col(input) {
+                Some((line, col)) => format!("{line}:{col}: {violation}"),
+                None => violation.to_string(),
+            })
+            .collect()
+    }
+
+    // NOTE: violates the schemas of the tests of all the schema modules.
+    pub(super) const INPUT: &str = indoc! {"
+        > server > host = \"Example.com\"
+
+        > server > port = 70000
+
+        > server > tls = `Enabled`
+
+        > server > backlog = 1.5
+
+        > mode = `Slow`
+
+        > storage > `Disk` > size = \"1G\"
+
+        > hosts = [\"a\", \"a\"]
+
+        > labels > ['team'] = 1
+
+        > extra = true
+    "};
+
+    #[test]
+    fn validation() {
+        let schema = Schema::structure()
+            .field(
+                "server",
+                Schema::structure()
+                    .field("host", Schema::string().pattern("^[a-z.]+$").unwrap())
+                    .field("port", Schema::integer().min(1).max(65535))
+                    .field("tls", Schema::boolean())
+                    .field("backlog", Schema::integer().nullable())
+                    .field("timeout", Schema::float()),
+            )
+            .field("mode", Schema::enumeration(["Fast", "Normal"]))
+            .field(
+                "storage",
+                Schema::enumeration(["Memory"]).variant(
+                    "Disk",
+                    Schema::structure().field("size", Schema::integer().min(0)),
+                ),
+            )
+            .field("hosts", Schema::sequence(Schema::string()).max_items(1))
+            .field("labels", Schema::map(Schema::string()))
+            .optional_field("tags", Schema::any())
+            .deny_unknown_fields();
+
+        assert_eq!(
+            describe(INPUT, schema.check(INPUT).unwrap()),
+            [
+                "1:19: missing field `timeout` at path: > server",
+                "1:19: string doesn't match the pattern `^[a-z.]+$` at path: > server > host",
+                "3:19: 70000 is greater than the maximum of 65535 at path: > server > port",
+                "5:18: invalid type: unit variant, expected boolean at path: > server > tls",
+                "7:22: invalid type: float, expected integer at path: > server > backlog",
+                "9:10: unknown variant `Slow`, expected one of `Fast`, `Normal` at path: > mode",
+                "11:29: invalid type: string, expected integer at path: > storage > `Disk` > size",
+                "13:11: sequence should have at most 1 items, found 2 at path: > hosts",
+                "15:23: invalid type: integer, expected string at path: > labels > [\"team\"]",
+                "17:11: unknown field `extra` at path: > extra",
+            ]
+        );
+
+        let value = parse("> server > port = 80\n\n> server > tls = true").unwrap();
+        let violation = &Schema::structure()
+            .field("host", Schema::string())
+            .validate(&value["server"])[0];
+
+        assert_eq!(violation.to_string(), "missing field `host` at path: >");
+        assert_eq!(violation.span, Some(18..20));
+
+        assert!(Schema::any().check(INPUT).unwrap().is_empty());
+        assert!(Schema::string().pattern("(").is_err());
+        assert!(Schema::any().check("> a =").is_err());
+    }
+}
@@ -0,0 +1,378 @@
+use super::{Schema, Type, Violation};
+use crate::parser::LexicalInfo;
+use crate::value::{Path, PathItem, Value, ValueCell};
+
+pub(super) fn validate(schema: &Schema, value: &ValueCell) -> Vec<Violation> {
+    let mut validator = Validator {
+        violations: Violations::default(),
+    };
+
+    validator.validate(schema, value);
+
+    validator.violations.list
+}
+
+// NOTE: tracks the path of the validated value and the enclosing values, which locate the
+// violations of values without a span.
+#[derive(Default)]
+pub(super) struct Violations<'v> {
+    pub(super) path: Path<'static>,
+    ancestors: Vec<&'v ValueCell>,
+    pub(super) list: Vec<Violation>,
+}
+
+impl<'v> Violations<'v> {
+    #[inline]
+    pub(super) fn enter(&mut self, parent: &'v ValueCell, item: PathItem<'static>) {
+        self.path.push(item);
+        self.ancestors.push(parent);
+    }
+
+    #[inline]
+    pub(super) fn leave(&mut self) {
+        self.ancestors.pop();
+        self.path.pop();
+    }
+
+    pub(super) fn push(&mut self, cell: &ValueCell, message: String) {
+        let info = std::iter::once(cell)
+            .chain(self.ancestors.iter().rev().copied())
+            .find_map(first_located);
+
+        self.list.push(Violation {
+            path: self.path.clone(),
+            message,
+            span: info.and_then(|info| info.span.clone()),
+            source: info.and_then(|info| info.source.clone()),
+        });
+    }
+}
+
+struct Validator<'v> {
+    violations: Violations<'v>,
+}
+
+impl<'v> Validator<'v> {
+    fn validate(&mut self, schema: &Schema, cell: &'v ValueCell) {
+        let value = cell.as_value();
+
+        let Some(ty) = schema.ty else {
+            return;
+        };
+
+        if value.is_null() && schema.nullable {
+            return;
+        }
+
+        match (ty, value) {
+            (Type::Null, Value::Null) | (Type::Bool, Value::Bool(_)) => (),
+            (Type::Integer, Value::Int(_) | Value::UInt(_))
+            | (Type::Float, Value::Int(_) | Value::UInt(_) | Value::Float(_)) => {
+                self.validate_number(schema, cell)
+            }
+            (Type::String, Value::String(string)) => {
+                if let Some(pattern) = &schema.pattern {
+                    if !pattern.is_match(string) {
+                        self.violation(
+                            cell,
+                            format!("string doesn't match the pattern `{pattern}`"),
+                        );
+                    }
+                }
+            }
+            (Type::Enum, Value::UnitVariant(name)) => match schema.variants.get(name) {
+                Some(None) => (),
+                Some(Some(_)) => {
+                    self.violation(cell, format!("variant `{name}` should have a value"))
+                }
+                None => self.unknown_variant(schema, cell, name),
+            },
+            (Type::Enum, Value::Variant(name, inner)) => match schema.variants.get(name) {
+                Some(Some(variant)) => self.nested(
+                    cell,
+                    PathItem::VariantName(name.clone().into()),
+                    variant,
+                    inner,
+                ),
+                Some(None) => {
+                    self.violation(cell, format!("variant `{name}` should be a unit variant"))
+                }
+                None => self.unknown_variant(schema, cell, name),
+            },
+            (Type::Sequence, Value::Sequence(seq)) => {
+                self.validate_len(schema, cell, seq.len());
+
+                if let Some(items) = &schema.items {
+                    for (idx, item) in seq.iter().enumerate() {
+                        self.nested(cell, PathItem::SequenceIndex(idx), items, item);
+                    }
+                }
+            }
+            (Type::Map, Value::Map(_) | Value::Struct(_)) => {
+                if let Some(values) = &schema.items {
+                    for (item, value) in entries(value) {
+                        self.nested(cell, item, values, value);
+                    }
+                }
+            }
+            (Type::Struct, Value::Struct(fields) | Value::Map(fields)) => {
+                for (name, field) in &schema.fields {
+                    if field.required && !fields.contains_key(name) {
+                        self.violation(cell, format!("missing field `{name}`"));
+                    }
+                }
+
+                for (item, value) in entries(value) {
+                    let name = match &item {
+                        PathItem::StructFieldName(name) | PathItem::MapKey(name) => name,
+                        _ => unreachable!("structures and maps have named entries"),
+                    };
+
+                    match schema.fields.get(name.as_ref()) {
+                        Some(field) => self.nested(cell, item, &field.schema, value),
+                        None if schema.deny_unknown_fields => {
+                            let message = format!("unknown field `{name}`");
+
+                            self.violations.enter(cell, item);
+                            self.violation(value, message);
+                            self.violations.leave();
+                        }
+                        None => (),
+                    }
+                }
+            }
+            (ty, value) => self.violation(
+                cell,
+                format!("invalid type: {}, expected {ty}", value.kind()),
+            ),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parent: &'v ValueCell,
+        item: PathItem<'static>,
+        schema: &Schema,
+        cell: &'v ValueCell,
+    ) {
+        self.violations.enter(parent, item);
+        self.validate(schema, cell);
+        self.violations.leave();
+    }
+
+    fn validate_number(&mut self, schema: &Schema, cell: &ValueCell) {
+        let Some(number) = cell.as_f64() else {
+            return;
+        };
+
+        if let Some(min) = schema.min.filter(|min| number < *min) {
+            self.violation(cell, format!("{number} is less than the minimum of {min}"));
+        }
+
+        if let Some(max) = schema.max.filter(|max| number > *max) {
+            self.violation(
+                cell,
+                format!("{number} is greater than the maximum of {max}"),
+            );
+        }
+    }
+
+    fn validate_len(&mut self, schema: &Schema, cell: &ValueCell, len: usize) {
+        if let Some(min) = schema.min_items.filter(|min| len < *min) {
+            self.violation(
+                cell,
+                format!("sequence should have at least {min} items, found {len}"),
+            );
+        }
+
+        if let Some(max) = schema.max_items.filter(|max| len > *max) {
+            self.violation(
+                cell,
+                format!("sequence should have at most {max} items, found {len}"),
+            );
+        }
+    }
+
+    fn unknown_variant(&mut self, schema: &Schema, cell: &ValueCell, name: &str) {
+        let expected = schema
+            .variants
+            .keys()
+            .map(|variant| format!("`{variant}`"))
+            .collect::<Vec<_>>();
+
+        let message = match expected.is_empty() {
+            true => format!("unknown variant `{name}`, there are no variants"),
+            false => format!(
+                "unknown variant `{name}`, expected one of {}",
+                expected.join(", ")
+            ),
+        };
+
+        self.violation(cell, message);
+    }
+
+    #[inline]
+    fn violation(&mut self, cell: &ValueCell, message: String) {
+        self.violations.push(cell, message);
+    }
+}
+
+fn entries(value: &Value) -> impl Iterator<Item = (PathItem<'static>, &ValueCell)> {
+    let (map, is_struct) = match value {
+        Value::Struct(map) => (Some(map), true),
+        Value::Map(map) => (Some(map), false),
+        _ => (None, false),
+    };
+
+    map.into_iter().flatten().map(move |(key, value)| {
+        let item = match is_struct {
+            true => PathItem::StructFieldName(key.clone().into()),
+            false => PathItem::MapKey(key.clone().into()),
+        };
+
+        (item, value)
+    })
+}
+
+// NOTE: the lexical info of the value itself if it has a span, or of its descendant which comes
+// first in the source.
+fn first_located(cell: &ValueCell) -> Option<&LexicalInfo> {
+    let info = cell.lexical_info();
+
+    if info.span.is_some() {
+        return Some(info);
+    }
+
+    let children: Box<dyn Iterator<Item = &ValueCell>> = match cell.as_value() {
+        Value::Sequence(seq) => Box::new(seq.iter()),
+        Value::Map(map) | Value::Struct(map) => Box::new(map.values()),
+        Value::Variant(_, value) => Box::new(std::iter::once(value)),
+        _ => return None,
+    };
+
+    children
+        .filter_map(first_located)
+        .min_by_key(|info| info.span.as_ref().map(|span| span.start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::schema::tests::describe;
+    use indoc::indoc;
+
+    #[test]
+    fn types() {
+        let schema = Schema::structure()
+            .field("int", Schema::integer().min(-1))
+            .field("float", Schema::float().max(1.5))
+            .field("nullable", Schema::string().nullable())
+            .field("map", Schema::map(Schema::boolean()))
+            .field("structure", Schema::structure().field("a", Schema::null()))
+            .field("seq", Schema::sequence(Schema::any()).min_items(2));
+
+        let input = indoc! {"
+            > int = -2
+
+            > float = 2
+
+            > nullable = null
+
+            > map > a = true
+
+            > map > b = 1
+
+            > structure > ['a'] = null
+
+            > seq = [1]
+        "};
+
+        assert_eq!(
+            describe(input, schema.check(input).unwrap()),
+            [
+                "1:9: -2 is less than the minimum of -1 at path: > int",
+                "3:11: 2 is greater than the maximum of 1.5 at path: > float",
+                "9:13: invalid type: integer, expected boolean at path: > map > b",
+                "13:9: sequence should have at least 2 items, found 1 at path: > seq",
+            ]
+        );
+
+        let schema = Schema::structure().field("a", Schema::integer());
+
+        assert_eq!(
+            describe("> a = 1.0", schema.check("> a = 1.0").unwrap()),
+            ["1:7: invalid type: float, expected integer at path: > a"]
+        );
+    }
+
+    #[test]
+    fn variants() {
+        let schema = Schema::enumeration(["Memory"]).variant("Disk", Schema::integer());
+        let check = |value: Value| describe("", schema.validate(&value.into()));
+
+        assert!(check(Value::UnitVariant("Memory".into())).is_empty());
+        assert!(check(Value::Variant("Disk".into(), Value::UInt(1).into())).is_empty());
+
+        assert_eq!(
+            check(Value::UnitVariant("Disk".into())),
+            ["variant `Disk` should have a value at path: >"]
+        );
+
+        assert_eq!(
+            check(Value::Variant("Memory".into(), Value::Null.into())),
+            ["variant `Memory` should be a unit variant at path: >"]
+        );
+
+        assert_eq!(
+            check(Value::Variant("Disk".into(), Value::from("1G").into())),
+            ["invalid type: string, expected integer at path: > `Disk`"]
+        );
+
+        assert_eq!(
+            describe(
+                "",
+                Schema::enumeration::<&str>([]).validate(&Value::UnitVariant("A".into()).into())
+            ),
+            ["unknown variant `A`, there are no variants at path: >"]
+        );
+    }
+
+    // NOTE: values without spans are located by their descendants or by the enclosing values.
+    #[test]
+    fn locations() {
+        let schema = Schema::structure().field(
+            "a",
+            Schema::structure()
+                .field("b", Schema::any())
+                .optional_field("d", Schema::structure().field("e", Schema::any())),
+        );
+
+        let input = "> x = 1\n\n> a > c = 1";
+        let mut value = parse(input).unwrap();
+
+        value["a"]
+            .as_value_mut()
+            .as_struct_mut()
+            .unwrap()
+            .insert("d".into(), Value::Struct(Default::default()).into());
+
+        assert_eq!(
+            describe(input, schema.validate(&value)),
+            [
+                "3:11: missing field `b` at path: > a",
+                "3:11: missing field `e` at path: > a > d",
+            ]
+        );
+
+        assert_eq!(
+            schema.validate(&Value::Null.into()),
+            [Violation {
+                path: Path::default(),
+                message: "invalid type: null, expected structure".into(),
+                span: None,
+                source: None,
+            }]
+        );
+    }
+}
@@ -45,9 +45,16 @@ pub fn write_escaped_str(out: &mut impl Write, v: &str) -> fmt::Result {
 
 pub fn escape_docs<'d>(docs: &'d str, escape: &dyn DocLineEscape) -> Cow<'d, str> {
     let mut out = Cow::Borrowed(docs);
+    let code_block_lines = code_block_lines(docs);
 
     for (line_idx, line) in docs.lines().enumerate() {
-        if let Some(gt_sign_pos) = doc_line_leading_gt_sign_pos(line) {
+        let gt_sign_pos = if code_block_lines[line_idx] {
+            None
+        } else {
+            doc_line_leading_gt_sign_pos(line)
+        };
+
+        if let Some(gt_sign_pos) = gt_sign_pos {
             if matches!(out, Cow::Borrowed(_)) {
                 out = docs
                     .lines()
@@ -91,6 +98,76 @@ pub fn doc_line_leading_gt_sign_pos(doc_line: &str) -> Option<usize> {
     None
 }
 
+// NOTE: mirrors the `docs_code_block` grammar rule, so lines that the parser treats as a part of a
+// Markdown fenced code block don't need to be escaped. Fences that are not closed don't start code
+// blocks, so the lines after them are escaped as usual.
+pub fn code_block_lines(docs: &str) -> Vec<bool> {
+    let lines = docs.lines().collect::<Vec<_>>();
+    let mut is_code_block_line = vec![false; lines.len()];
+    let mut idx = 0;
+
+    while idx < lines.len() {
+        let end = code_fence_start(lines[idx]).and_then(|(fence, _)| {
+            lines[idx + 1..]
+                .iter()
+                .position(|line| is_code_fence_end(line, fence))
+                .map(|len| idx + 1 + len)
+        });
+
+        match end {
+            Some(end) => {
+                is_code_block_line[idx..=end].fill(true);
+                idx = end + 1;
+            }
+            None => idx += 1,
+        }
+    }
+
+    is_code_block_line
+}
+
+pub(crate) fn code_fence_start(line: &str) -> Option<(&str, &str)> {
+    let line = strip_code_fence_indentation(line);
+
+    let fence_char = match line.chars().next() {
+        Some(c @ ('`' | '~')) => c,
+        _ => return None,
+    };
+
+    let fence_len = line.len() - line.trim_start_matches(fence_char).len();
+    let (fence, info) = line.split_at(fence_len);
+
+    if fence_len < 3 || (fence_char == '`' && info.contains('`')) {
+        None
+    } else {
+        Some((fence, info))
+    }
+}
+
+pub(crate) fn is_code_fence_end(line: &str, fence: &str) -> bool {
+    let Some(rest) = strip_code_fence_indentation(line).strip_prefix(fence) else {
+        return false;
+    };
+
+    // NOTE: the closing fence can be longer than the opening one, but not mixed with the other
+    // fence character.
+    let fence_char = fence.chars().next().unwrap_or('`');
+
+    rest.trim_start_matches(fence_char)
+        .trim_start_matches([' ', '\t'])
+        .is_empty()
+}
+
+fn strip_code_fence_indentation(line: &str) -> &str {
+    let indentation = line.len() - line.trim_start_matches(' ').len();
+
+    if indentation <= 3 {
+        &line[indentation..]
+    } else {
+        line
+    }
+}
+
 fn escape_char(c: char) -> Option<&'static str> {
     const UNICODE_ESCAPES: &[&str] = &[
         "\\u000000",
@@ -0,0 +1,131 @@
+use super::formatting::{FormattingOptions, LineEnding};
+use super::KonfigSerializer;
+use crate::error::Result;
+use crate::parser::embedded::{block_expr_paths, find_blocks};
+use crate::parser::BOM;
+use crate::value::{Path, PathItem, ValueCell};
+
+pub fn serialize_embedded(
+    host: &str,
+    value: &ValueCell,
+    formatting: FormattingOptions,
+) -> Result<String> {
+    let (bom, host) = match host.strip_prefix(BOM) {
+        Some(host) => (BOM, host),
+        None => ("", host),
+    };
+
+    let line_ending = formatting.line_ending;
+    let blocks = find_blocks(host);
+
+    let block_paths = blocks
+        .iter()
+        .map(|block| block_expr_paths(host, block.clone()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut serializer = KonfigSerializer {
+        out: Default::default(),
+        path: Default::default(),
+        have_docs_after: false,
+        formatting,
+        expr_ends: Some(vec![]),
+    };
+
+    serializer.serialize(value)?;
+
+    let expr_ends = serializer.expr_ends.take().unwrap_or_default();
+    let last_expr_idx = expr_ends.len().saturating_sub(1);
+    let mut contents = vec![String::new(); blocks.len().max(1)];
+    let mut chunk_start = 0;
+
+    for (idx, (path, expr_end)) in expr_ends.iter().enumerate() {
+        // NOTE: each chunk contains an expression with the docs before and after it. Anything
+        // that is serialized after the last expression belongs to its chunk as well.
+        let chunk_end = if idx == last_expr_idx {
+            serializer.out.len()
+        } else {
+            *expr_end
+        };
+
+        contents[find_block_for_path(&block_paths, path)]
+            .push_str(&serializer.out[chunk_start..chunk_end]);
+
+        chunk_start = chunk_end;
+    }
+
+    let mut out = String::with_capacity(bom.len() + host.len() + serializer.out.len());
+    let nl = line_ending.as_str();
+
+    out.push_str(bom);
+
+    if blocks.is_empty() {
+        out.push_str(host);
+
+        if !host.is_empty() {
+            if !host.ends_with('\n') {
+                out.push_str(nl);
+            }
+
+            out.push_str(nl);
+        }
+
+        out.push_str("```konfig");
+        out.push_str(nl);
+        write_block_content(&mut out, &contents[0], line_ending);
+        out.push_str("```");
+        out.push_str(nl);
+
+        return Ok(out);
+    }
+
+    let mut host_pos = 0;
+
+    for (block, content) in blocks.iter().zip(contents) {
+        out.push_str(&host[host_pos..block.start]);
+        write_block_content(&mut out, &content, line_ending);
+        host_pos = block.end;
+    }
+
+    out.push_str(&host[host_pos..]);
+
+    Ok(out)
+}
+
+// NOTE: expressions are written to the block that originally defined their path. New expressions
+// go to the block that defines the closest sibling or, if there is none, to the last block.
+fn find_block_for_path(block_paths: &[Vec<Path>], path: &Path) -> usize {
+    let mut best_match = (0, block_paths.len().saturating_sub(1));
+
+    for (block_idx, paths) in block_paths.iter().enumerate() {
+        for block_path in paths {
+            if block_path == path {
+                return block_idx;
+            }
+
+            let common_prefix_len = common_prefix_len(block_path.items(), path.items());
+
+            if common_prefix_len > 0 && common_prefix_len >= best_match.0 {
+                best_match = (common_prefix_len, block_idx);
+            }
+        }
+    }
+
+    best_match.1
+}
+
+fn common_prefix_len(path1: &[PathItem], path2: &[PathItem]) -> usize {
+    path1
+        .iter()
+        .zip(path2)
+        .take_while(|(item1, item2)| item1 == item2)
+        .count()
+}
+
+fn write_block_content(out: &mut String, content: &str, line_ending: LineEnding) {
+    let content = content.trim_end_matches('\n');
+
+    if !content.is_empty() {
+        out.push_str(&line_ending.apply(content));
+        out.push_str(line_ending.as_str());
+    }
+}
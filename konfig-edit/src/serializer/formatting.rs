@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::fmt;
 
 pub trait DocLineEscape {
@@ -26,9 +27,63 @@ impl DocLineEscape for MarkdowDocLineEscape {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    #[inline]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    // NOTE: line ending of the first line is used, so files can be written back with the same
+    // line endings they were read with.
+    pub fn detect(text: &str) -> Self {
+        match text.find('\n') {
+            Some(pos) if text[..pos].ends_with('\r') => LineEnding::CrLf,
+            _ => LineEnding::Lf,
+        }
+    }
+
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            LineEnding::Lf if text.contains("\r\n") => text.replace("\r\n", "\n").into(),
+            LineEnding::CrLf if text.matches('\n').count() > text.matches("\r\n").count() => {
+                let mut out = String::with_capacity(text.len() + text.len() / 8);
+
+                for (idx, line) in text.split('\n').enumerate() {
+                    if idx != 0 {
+                        out.push_str("\r\n");
+                    }
+
+                    out.push_str(line.strip_suffix('\r').unwrap_or(line));
+                }
+
+                out.into()
+            }
+            _ => text.into(),
+        }
+    }
+
+    pub fn apply_owned(self, text: String) -> String {
+        match self.apply(&text) {
+            Cow::Borrowed(_) => text,
+            Cow::Owned(out) => out,
+        }
+    }
+}
+
 pub struct FormattingOptions {
     pub doc_line_escape: Box<dyn DocLineEscape>,
     pub path_wrap_at_len: usize,
+    pub line_ending: LineEnding,
 }
 
 impl Default for FormattingOptions {
@@ -37,6 +92,7 @@ impl Default for FormattingOptions {
         Self {
             doc_line_escape: Box::new(MarkdowDocLineEscape),
             path_wrap_at_len: 100,
+            line_ending: Default::default(),
         }
     }
 }
@@ -46,6 +102,7 @@ impl fmt::Debug for FormattingOptions {
         f.debug_struct("FormattingOptions")
             .field("doc_line_escape", &"Box<dyn ...>")
             .field("path_wrap_at_len", &self.path_wrap_at_len)
+            .field("line_ending", &self.line_ending)
             .finish()
     }
 }
@@ -1,31 +1,54 @@
 pub mod components;
+mod embedded;
 pub mod formatting;
 
 use self::components::{escape_docs, write_escaped_str, write_float, write_int};
 use self::formatting::FormattingOptions;
 use crate::error::{Error, Result};
-use crate::value::{Path, Value, ValueCell};
+use crate::parser::{DetachedExpr, DetachedExprKind, TemplateRef};
+use crate::value::{Path, PathItem, Value, ValueCell};
 use indexmap::IndexMap;
 
+pub use self::embedded::serialize_embedded;
+
+#[inline]
 pub fn serialize(value: &ValueCell, formatting: FormattingOptions) -> Result<String> {
+    serialize_at(&Path::default(), value, formatting)
+}
+
+// NOTE: serializes the value as if it was located at the given path of a document, e.g. to show
+// a part of a document.
+pub(crate) fn serialize_at(
+    path: &Path,
+    value: &ValueCell,
+    formatting: FormattingOptions,
+) -> Result<String> {
     let mut serializer = KonfigSerializer {
         out: Default::default(),
-        path: Default::default(),
+        path: path
+            .items()
+            .iter()
+            .map(|item| item.clone().into_owned())
+            .collect::<Path<'static>>(),
         have_docs_after: false,
         formatting,
+        expr_ends: None,
     };
 
     serializer.serialize(value)?;
 
     // NOTE: trim last expression separator. It's much simpler to implement it this way,
-    // even though it's not the most elegant approach.s
-    if !serializer.have_docs_after {
+    // even though it's not the most elegant approach. Empty containers produce no expressions.
+    if !serializer.have_docs_after && !serializer.out.is_empty() {
         let removed = (serializer.out.pop(), serializer.out.pop());
 
         debug_assert_eq!(removed, (Some('\n'), Some('\n')));
     }
 
-    Ok(serializer.out)
+    Ok(serializer
+        .formatting
+        .line_ending
+        .apply_owned(serializer.out))
 }
 
 struct KonfigSerializer<'v> {
@@ -33,20 +56,67 @@ struct KonfigSerializer<'v> {
     path: Path<'v>,
     have_docs_after: bool,
     formatting: FormattingOptions,
+    expr_ends: Option<Vec<(Path<'v>, usize)>>,
 }
 
 impl<'v> KonfigSerializer<'v> {
-    fn serialize(&mut self, value: &'v ValueCell) -> Result<()> {
+    fn serialize(&mut self, cell: &'v ValueCell) -> Result<()> {
+        let Some(value) = written_value(cell) else {
+            return Ok(());
+        };
+
         self.have_docs_after = false;
 
-        let docs_before = &value.lexical_info().docs_before;
+        for expr in &cell.lexical_info().exprs_before {
+            self.serialize_detached_expr(expr)?;
+        }
+
+        let docs_before = &cell.lexical_info().docs_before;
 
         self.out.push_str(&escape_docs(
             docs_before,
             self.formatting.doc_line_escape.as_ref(),
         ));
 
-        match **value {
+        let template_ref = cell
+            .lexical_info()
+            .template
+            .as_ref()
+            .filter(|template_ref| is_instance_of(&template_ref.template, value));
+
+        if let Some(template_ref) = template_ref {
+            self.serialize_template_instance(template_ref, value)
+        } else {
+            self.serialize_value(value)
+        }?;
+
+        for expr in &cell.lexical_info().exprs_after {
+            self.serialize_detached_expr(expr)?;
+            self.have_docs_after = false;
+        }
+
+        let docs_after = &cell.lexical_info().docs_after;
+
+        if !docs_after.is_empty() {
+            self.out.push_str(&escape_docs(
+                docs_after,
+                self.formatting.doc_line_escape.as_ref(),
+            ));
+
+            self.have_docs_after = true;
+        }
+
+        if let Some(ref mut expr_ends) = self.expr_ends {
+            if is_expr(value) {
+                expr_ends.push((self.path.clone(), self.out.len()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn serialize_value(&mut self, value: &'v Value) -> Result<()> {
+        match *value {
             Value::Null => self.write_rhs_infallible(|s| s.write_null()),
             Value::Bool(v) => self.write_rhs_infallible(|s| s.write_bool(v)),
             Value::Int(v) => self.write_rhs(|s| write_int(&mut s.out, v).map_err(Error::custom)),
@@ -59,18 +129,137 @@ impl<'v> KonfigSerializer<'v> {
             Value::Map(ref v) => self.serialize_map(v),
             Value::Struct(ref v) => self.serialize_struct(v),
             Value::Variant(ref n, ref v) => self.serialize_variant(n, v),
-        }?;
+        }
+    }
 
-        let docs_after = &value.lexical_info().docs_after;
+    fn serialize_template_instance(
+        &mut self,
+        template_ref: &TemplateRef,
+        value: &'v Value,
+    ) -> Result<()> {
+        validate_name("template", &template_ref.name)?;
 
-        if !docs_after.is_empty() {
-            self.out.push_str(&escape_docs(
-                docs_after,
-                self.formatting.doc_line_escape.as_ref(),
-            ));
+        self.write_rhs(|s| {
+            s.out.push('*');
+            s.out.push_str(&template_ref.name);
 
-            self.have_docs_after = true;
+            Ok(())
+        })?;
+
+        self.serialize_overrides(&template_ref.template, value)
+    }
+
+    // NOTE: writes the values that differ from the template. Values that are missing in the
+    // template or have a different type are written as a whole.
+    fn serialize_overrides(&mut self, template: &Value, value: &'v Value) -> Result<()> {
+        match (template, value) {
+            (template, value) if template == value => Ok(()),
+            (Value::Sequence(template), Value::Sequence(seq)) => {
+                for (idx, v) in seq.iter().enumerate() {
+                    self.path.push_sequence_index(idx);
+                    self.serialize_override(template.get(idx), v)?;
+                    self.path.pop();
+                }
+
+                Ok(())
+            }
+            (Value::Map(template), Value::Map(map)) => {
+                for (k, v) in in_source_order(map) {
+                    self.path.push_map_key(k);
+                    self.serialize_override(template.get(k), v)?;
+                    self.path.pop();
+                }
+
+                Ok(())
+            }
+            (Value::Struct(template), Value::Struct(map)) => {
+                for (k, v) in in_source_order(map) {
+                    validate_ident(k)?;
+                    self.path.push_struct_field_name(k);
+                    self.serialize_override(template.get(k), v)?;
+                    self.path.pop();
+                }
+
+                Ok(())
+            }
+            (Value::Variant(template_name, template), Value::Variant(name, v))
+                if template_name == name =>
+            {
+                self.path.push_variant_name(name);
+                self.serialize_override(Some(template), v)?;
+                self.path.pop();
+
+                Ok(())
+            }
+            _ => unreachable!("the value is an instance of the template"),
         }
+    }
+
+    // NOTE: values with docs or detached expressions are written even if they are the same as in
+    // the template, so the docs and the expressions are kept.
+    fn serialize_override(
+        &mut self,
+        template: Option<&ValueCell>,
+        cell: &'v ValueCell,
+    ) -> Result<()> {
+        match (template, written_value(cell)) {
+            (Some(template), Some(value))
+                if is_instance_of(template, value) && !has_docs_or_exprs(cell) =>
+            {
+                self.serialize_overrides(template, value)
+            }
+            _ => self.serialize(cell),
+        }
+    }
+
+    fn serialize_detached_expr(&mut self, expr: &DetachedExpr) -> Result<()> {
+        self.out.push_str(&escape_docs(
+            &expr.docs_before,
+            self.formatting.doc_line_escape.as_ref(),
+        ));
+
+        self.out.push_str("> ");
+
+        match expr.kind {
+            DetachedExprKind::Profile(ref name) => {
+                validate_name("profile", name)?;
+                self.out.push('@');
+                self.out.push_str(name);
+            }
+            DetachedExprKind::Template(ref name) => {
+                validate_name("template", name)?;
+                self.out.push('&');
+                self.out.push_str(name);
+            }
+        }
+
+        for item in &expr.path {
+            if let PathItem::StructFieldName(name) | PathItem::VariantName(name) = item {
+                validate_ident(name)?;
+            }
+
+            self.out.push_str(" > ");
+            item.write(&mut self.out).map_err(Error::custom)?;
+        }
+
+        self.out.push_str(" = ");
+
+        match (&expr.value.lexical_info().template, expr.value.as_value()) {
+            (Some(template_ref), _) => {
+                validate_name("template", &template_ref.name)?;
+                self.out.push('*');
+                self.out.push_str(&template_ref.name);
+            }
+            (None, Value::Sequence(seq)) if is_all_primitive(seq) => self.write_array(seq)?,
+            (None, value) if is_expr(value) => self.write_primitive(value)?,
+            _ => {
+                return Err(Error::custom(
+                    "detached expressions can only have primitive values or arrays of primitives",
+                ))
+            }
+        }
+
+        self.out.push_str("\n\n");
 
         Ok(())
     }
@@ -112,30 +301,41 @@ impl<'v> KonfigSerializer<'v> {
         Ok(())
     }
 
+    #[inline]
     fn serialize_array_of_primitives(&mut self, seq: &'v [ValueCell]) -> Result<()> {
+        self.write_rhs(|s| s.write_array(seq))
+    }
+
+    fn write_array(&mut self, seq: &[ValueCell]) -> Result<()> {
+        let seq = seq.iter().filter_map(written_value).collect::<Vec<_>>();
         let last = seq.len().saturating_sub(1);
 
-        self.path.write(&mut self.out).map_err(Error::custom)?;
-        self.out.push_str(" = [");
+        self.out.push('[');
 
-        for (idx, v) in seq.iter().enumerate() {
-            match **v {
-                Value::Null => self.write_null(),
-                Value::Bool(v) => self.write_bool(v),
-                Value::Int(v) => write_int(&mut self.out, v).map_err(Error::custom)?,
-                Value::UInt(v) => write_int(&mut self.out, v).map_err(Error::custom)?,
-                Value::Float(v) => write_float(&mut self.out, v)?,
-                Value::String(ref v) => self.write_string(v)?,
-                Value::UnitVariant(ref v) => self.write_unit_variant(v)?,
-                _ => unreachable!(),
-            }
+        for (idx, v) in seq.into_iter().enumerate() {
+            self.write_primitive(v)?;
 
             if idx != last {
                 self.out.push_str(", ");
             }
         }
 
-        self.out.push_str("]\n\n");
+        self.out.push(']');
+
+        Ok(())
+    }
+
+    fn write_primitive(&mut self, value: &Value) -> Result<()> {
+        match *value {
+            Value::Null => self.write_null(),
+            Value::Bool(v) => self.write_bool(v),
+            Value::Int(v) => write_int(&mut self.out, v).map_err(Error::custom)?,
+            Value::UInt(v) => write_int(&mut self.out, v).map_err(Error::custom)?,
+            Value::Float(v) => write_float(&mut self.out, v)?,
+            Value::String(ref v) => self.write_string(v)?,
+            Value::UnitVariant(ref v) => self.write_unit_variant(v)?,
+            _ => unreachable!(),
+        }
 
         Ok(())
     }
@@ -194,11 +394,7 @@ impl<'v> KonfigSerializer<'v> {
 }
 
 fn is_all_primitive(seq: &[ValueCell]) -> bool {
-    if seq.is_empty() {
-        return true;
-    }
-
-    seq.iter().all(|v| match **v {
+    seq.iter().filter_map(written_value).all(|v| match *v {
         Value::Null
         | Value::Bool(_)
         | Value::Float(_)
@@ -210,6 +406,105 @@ fn is_all_primitive(seq: &[ValueCell]) -> bool {
     })
 }
 
+// NOTE: values overridden by the active profiles are written as they are without the profiles,
+// unless they have changed after parsing. Values only defined by the profiles aren't written.
+fn written_value(cell: &ValueCell) -> Option<&Value> {
+    match cell.lexical_info().profile_override {
+        Some(ref profile_override) if profile_override.value == **cell => {
+            profile_override.base.as_ref()
+        }
+        _ => Some(cell),
+    }
+}
+
+fn has_docs_or_exprs(cell: &ValueCell) -> bool {
+    let lexical_info = cell.lexical_info();
+
+    !lexical_info.docs_before.is_empty()
+        || !lexical_info.docs_after.is_empty()
+        || !lexical_info.exprs_before.is_empty()
+        || !lexical_info.exprs_after.is_empty()
+}
+
+fn is_expr(value: &Value) -> bool {
+    match value {
+        Value::Sequence(v) => is_all_primitive(v),
+        Value::Map(_) | Value::Struct(_) | Value::Variant(_, _) => false,
+        _ => true,
+    }
+}
+
+// NOTE: overrides are merged on top of the template, so the value can be written as an instance
+// of the template only if it has all the values of the template. The instance itself is parsed as
+// a placeholder of the template, so it must have the same type, variant or primitive value.
+fn is_instance_of(template: &Value, value: &Value) -> bool {
+    match (template, value) {
+        (Value::Sequence(_), Value::Sequence(_))
+        | (Value::Map(_), Value::Map(_))
+        | (Value::Struct(_), Value::Struct(_)) => has_values_of(template, value),
+        (Value::Variant(template_name, template), Value::Variant(name, v)) => {
+            template_name == name && has_values_of(template, v)
+        }
+        _ => template == value,
+    }
+}
+
+// NOTE: nested values of other types, variants or primitive values are overrides.
+fn has_values_of(template: &Value, value: &Value) -> bool {
+    match (template, value) {
+        (Value::Sequence(template), Value::Sequence(seq)) => {
+            template.len() <= seq.len()
+                && template.iter().zip(seq).all(|(t, v)| has_values_of(t, v))
+        }
+        (Value::Map(template), Value::Map(map)) | (Value::Struct(template), Value::Struct(map)) => {
+            template
+                .iter()
+                .all(|(k, t)| map.get(k).is_some_and(|v| has_values_of(t, v)))
+        }
+        (Value::Variant(template_name, template), Value::Variant(name, v)) => {
+            template_name != name || has_values_of(template, v)
+        }
+        _ => true,
+    }
+}
+
+// NOTE: merged overrides follow the order of the template, so they are sorted by their position
+// in the source to be written back in the original order. Values without a position keep their
+// relative order and go last.
+fn in_source_order(map: &IndexMap<String, ValueCell>) -> Vec<(&String, &ValueCell)> {
+    fn start(cell: &ValueCell) -> Option<usize> {
+        let children_start = match cell.as_value() {
+            Value::Sequence(seq) => seq.iter().filter_map(start).min(),
+            Value::Map(map) | Value::Struct(map) => map.values().filter_map(start).min(),
+            Value::Variant(_, value) => start(value),
+            _ => None,
+        };
+
+        let span_start = cell.lexical_info().span.as_ref().map(|span| span.start);
+
+        span_start.into_iter().chain(children_start).min()
+    }
+
+    let mut entries = map.iter().collect::<Vec<_>>();
+
+    entries.sort_by_key(|(_, v)| start(v).unwrap_or(usize::MAX));
+
+    entries
+}
+
+fn validate_name(kind: &str, name: &str) -> Result<()> {
+    let is_valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if is_valid {
+        Ok(())
+    } else {
+        Err(Error::custom(format!("invalid {kind} name: {name}")))
+    }
+}
+
 fn validate_ident(ident: &str) -> Result<()> {
     let mut chars = ident.chars();
     let first_ok = chars.next().map(char::is_alphabetic).unwrap_or_default();
@@ -0,0 +1,195 @@
+use super::flatten::wrap;
+use super::{Path, PathItem, Value};
+use crate::error::{Error, Result};
+use crate::parser::parse_expr;
+
+impl Value {
+    // NOTE: unlike the parser, overrides existing values instead of reporting duplicate
+    // assignments. Missing containers are created the same way as in the parser and primitives
+    // and enum variants on the path are replaced, but containers of other types are not.
+    pub fn set_path(&mut self, path: &[PathItem], value: impl Into<Value>) -> Result<()> {
+        let value = value.into();
+        let full_path = || path.iter().cloned().collect::<Path>();
+        let mut host = self;
+
+        for (depth, item) in path.iter().enumerate() {
+            let is_compatible = match (&*host, item) {
+                (Value::Sequence(_), PathItem::SequenceIndex(_))
+                | (Value::Map(_), PathItem::MapKey(_))
+                | (Value::Struct(_), PathItem::StructFieldName(_)) => true,
+                (Value::Variant(name, _), PathItem::VariantName(item)) => name == item,
+                _ => false,
+            };
+
+            if !is_compatible {
+                if host.is_container() && !matches!(host, Value::Variant(..)) {
+                    return Err(Error::custom(format!(
+                        "path item has incompatible type with the existing value at path: {}",
+                        full_path()
+                    )));
+                }
+
+                *host = wrap(&full_path(), &path[depth..], value)?;
+
+                return Ok(());
+            }
+
+            let rest = &path[depth + 1..];
+
+            let next = match (host, item) {
+                (Value::Sequence(seq), PathItem::SequenceIndex(idx)) => {
+                    if *idx == seq.len() {
+                        seq.push(wrap(&full_path(), rest, value)?.into());
+
+                        return Ok(());
+                    }
+
+                    seq.get_mut(*idx).ok_or_else(|| {
+                        Error::custom(format!(
+                            "sequence index is out of bounds at path: {}",
+                            full_path()
+                        ))
+                    })?
+                }
+                (Value::Map(map), PathItem::MapKey(key))
+                | (Value::Struct(map), PathItem::StructFieldName(key)) => {
+                    match map.get_index_of(key.as_ref()) {
+                        Some(idx) => &mut map[idx],
+                        None => {
+                            map.insert(key.to_string(), wrap(&full_path(), rest, value)?.into());
+
+                            return Ok(());
+                        }
+                    }
+                }
+                (Value::Variant(_, cell), _) => cell,
+                _ => unreachable!("path item is compatible with the value"),
+            };
+
+            host = next.as_value_mut();
+        }
+
+        *host = value;
+
+        Ok(())
+    }
+
+    // NOTE: applies a single konfig expression, e.g. a command line override like
+    // `> server > port = 8080`.
+    pub fn apply_expr(&mut self, expr: &str) -> Result<()> {
+        let (path, value) = parse_expr(expr)?;
+
+        self.set_path(path.items(), value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::serializer::serialize;
+    use indoc::indoc;
+
+    fn apply(konfig: &str, exprs: &[&str]) -> Result<String> {
+        let mut value = parse(konfig).unwrap();
+
+        for expr in exprs {
+            value.apply_expr(expr)?;
+        }
+
+        Ok(serialize(&value, Default::default()).unwrap())
+    }
+
+    const INPUT: &str = indoc! {"
+        Docs for port.
+
+        > server > port = 80
+
+        > server > hosts = [\"a\"]
+
+        > server > tls = `Disabled`
+
+        > env > ['HOME'] = \"/root\"
+    "};
+
+    #[test]
+    fn parse_expression() {
+        let (path, value) = parse_expr("> server > [0] > ['port'] = 8080").unwrap();
+
+        assert_eq!(path.to_string(), "> server > [0] > [\"port\"]");
+        assert_eq!(value, Value::UInt(8080));
+
+        let (path, value) = parse_expr(">  ports = [1, 2]\n").unwrap();
+
+        assert_eq!(path.to_string(), "> ports");
+        assert_eq!(
+            value,
+            Value::Sequence(vec![Value::UInt(1).into(), Value::UInt(2).into()])
+        );
+
+        assert!(parse_expr("> a = 1\n\n> b = 2")
+            .unwrap_err()
+            .to_string()
+            .contains("expected a single expression"));
+
+        assert!(parse_expr("> a =").is_err());
+    }
+
+    #[test]
+    fn override_values() {
+        assert_eq!(
+            apply(
+                INPUT,
+                &[
+                    "> server > port = 8080",
+                    "> server > hosts > [1] = \"b\"",
+                    "> server > tls > `Enabled` > cert = \"cert.pem\"",
+                    "> env > ['USER'] = \"root\"",
+                    "> server > port = 8081",
+                    "> limits > [0] > rps = 100",
+                ]
+            )
+            .unwrap(),
+            indoc! {"
+                Docs for port.
+
+                > server > port = 8081
+
+                > server > hosts = [\"a\", \"b\"]
+
+                > server > tls > `Enabled` > cert = \"cert.pem\"
+
+                > env > [\"HOME\"] = \"/root\"
+
+                > env > [\"USER\"] = \"root\"
+
+                > limits > [0] > rps = 100\
+            "}
+        );
+
+        assert_eq!(apply(INPUT, &["> = 42"]).unwrap(), "> = 42");
+    }
+
+    #[test]
+    fn override_errors() {
+        assert_eq!(
+            apply(INPUT, &["> env > HOME = 1"]).unwrap_err().to_string(),
+            "path item has incompatible type with the existing value at path: > env > HOME"
+        );
+
+        assert_eq!(
+            apply(INPUT, &["> server > hosts > [2] = \"c\""])
+                .unwrap_err()
+                .to_string(),
+            "sequence index is out of bounds at path: > server > hosts > [2]"
+        );
+
+        assert_eq!(
+            apply(INPUT, &["> limits > [1] > rps = 1"])
+                .unwrap_err()
+                .to_string(),
+            "sequence items should be defined in order, with the first item having index `0` \
+            at path: > limits > [1] > rps"
+        );
+    }
+}
@@ -162,6 +162,73 @@ impl Value {
     pub fn is_variant(&self) -> bool {
         self.as_variant().is_some()
     }
+
+    // NOTE: human-readable name of the kind of the value, e.g. for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::UInt(_) | Value::Int(_) => "integer",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::UnitVariant(_) => "unit variant",
+            Value::Sequence(_) => "sequence",
+            Value::Map(_) => "map",
+            Value::Struct(_) => "structure",
+            Value::Variant(..) => "variant",
+        }
+    }
+
+    // NOTE: compares values as JSON would see them: integers are compared exactly and other
+    // numbers by value, structures, maps and variants are all objects, and unit variants are
+    // strings.
+    pub(crate) fn json_eq(&self, other: &Value) -> bool {
+        fn object(value: &Value) -> Option<Vec<(&str, &Value)>> {
+            match value {
+                Value::Map(entries) | Value::Struct(entries) => Some(
+                    entries
+                        .iter()
+                        .map(|(key, value)| (key.as_str(), value.as_value()))
+                        .collect(),
+                ),
+                Value::Variant(name, value) => Some(vec![(name.as_str(), value.as_value())]),
+                _ => None,
+            }
+        }
+
+        fn integer(value: &Value) -> Option<i128> {
+            match value {
+                Value::UInt(v) => Some((*v).into()),
+                Value::Int(v) => Some((*v).into()),
+                _ => None,
+            }
+        }
+
+        match (self, other) {
+            (
+                Value::String(a) | Value::UnitVariant(a),
+                Value::String(b) | Value::UnitVariant(b),
+            ) => a == b,
+            (Value::Sequence(a), Value::Sequence(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.json_eq(b))
+            }
+            _ => match (object(self), object(other)) {
+                (Some(a), Some(b)) => {
+                    a.len() == b.len()
+                        && a.iter()
+                            .all(|(key, a)| b.iter().any(|(other, b)| key == other && a.json_eq(b)))
+                }
+                (None, None) => match (integer(self), integer(other)) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => match (self.as_f64(), other.as_f64()) {
+                        (Some(a), Some(b)) => a == b,
+                        _ => self == other,
+                    },
+                },
+                _ => false,
+            },
+        }
+    }
 }
 
 impl From<ValueCell> for Value {
@@ -261,3 +328,25 @@ impl_from_primitive! {
     [f32 f64] => Float
     [String &str] => String
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_eq() {
+        let value = |s: &str| s.parse::<Value>().unwrap();
+
+        assert!(Value::UInt(1).json_eq(&Value::Float(1.0)));
+        assert!(Value::UInt(1).json_eq(&Value::Int(1)));
+        assert!(!Value::UInt(u64::MAX).json_eq(&Value::UInt(u64::MAX - 1)));
+        assert!(!Value::Int(i64::MIN).json_eq(&Value::UInt(1 << 63)));
+        assert!(Value::UnitVariant("A".into()).json_eq(&Value::String("A".into())));
+        assert!(!Value::Null.json_eq(&Value::Bool(false)));
+
+        assert!(value("> a = 1\n\n> b = [true]")
+            .json_eq(&value("> [\"b\"] = [true]\n\n> [\"a\"] = 1.0")));
+        assert!(value("> a > `B` > c = 1").json_eq(&value("> a > [\"B\"] > c = 1")));
+        assert!(!value("> a = 1").json_eq(&value("> a = 1\n\n> b = 2")));
+    }
+}
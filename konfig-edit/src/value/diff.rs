@@ -0,0 +1,565 @@
+use super::{Path, PathItem, Value, ValueCell};
+use crate::error::{Error, Result};
+use crate::serializer::formatting::LineEnding;
+use crate::serializer::serialize_at;
+use indexmap::IndexMap;
+use std::fmt::Write;
+
+// NOTE: paths of removed values point into the old value, paths of all other operations point
+// into the new value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffOp {
+    Added {
+        path: Path<'static>,
+        value: Value,
+    },
+    Removed {
+        path: Path<'static>,
+        value: Value,
+    },
+    Changed {
+        path: Path<'static>,
+        old: Value,
+        new: Value,
+    },
+    Moved {
+        from: Path<'static>,
+        to: Path<'static>,
+        value: Value,
+    },
+    DocsChanged {
+        path: Path<'static>,
+        old: Docs,
+        new: Docs,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Docs {
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    pub ignore_docs: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diff {
+    pub ops: Vec<DiffOp>,
+}
+
+#[inline]
+pub fn diff(old: &Value, new: &Value) -> Diff {
+    diff_with_options(old, new, Default::default())
+}
+
+pub fn diff_with_options(old: &Value, new: &Value, options: DiffOptions) -> Diff {
+    let mut differ = Differ {
+        path: Path::default(),
+        options,
+        ops: vec![],
+    };
+
+    differ.diff_values(old, new);
+
+    Diff { ops: differ.ops }
+}
+
+impl Value {
+    #[inline]
+    pub fn diff(&self, new: &Value) -> Diff {
+        diff(self, new)
+    }
+}
+
+impl Diff {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, DiffOp> {
+        self.ops.iter()
+    }
+
+    #[inline]
+    pub fn to_markdown(&self) -> Result<String> {
+        self.to_markdown_with_line_ending(Default::default())
+    }
+
+    // NOTE: values are rendered as konfig expressions, so the diff reads like a konfig document.
+    // It's not meant to be parsed back though, as the same path can appear multiple times.
+    pub fn to_markdown_with_line_ending(&self, line_ending: LineEnding) -> Result<String> {
+        let mut out = String::new();
+
+        let write_value = |out: &mut String, path: &Path, value: &Value| -> Result<()> {
+            let exprs = serialize_at(path, &value.clone().into_cell(), Default::default())?;
+
+            if exprs.is_empty() {
+                out.push_str("_empty_\n\n");
+            } else {
+                out.push_str(&exprs);
+                out.push_str("\n\n");
+            }
+
+            Ok(())
+        };
+
+        for op in &self.ops {
+            match op {
+                DiffOp::Added { path, value } => {
+                    writeln!(out, "**Added** `{path}`:\n").map_err(Error::custom)?;
+                    write_value(&mut out, path, value)?;
+                }
+                DiffOp::Removed { path, value } => {
+                    writeln!(out, "**Removed** `{path}`:\n").map_err(Error::custom)?;
+                    write_value(&mut out, path, value)?;
+                }
+                DiffOp::Changed { path, old, new } => {
+                    writeln!(out, "**Changed** `{path}` from:\n").map_err(Error::custom)?;
+                    write_value(&mut out, path, old)?;
+                    out.push_str("to:\n\n");
+                    write_value(&mut out, path, new)?;
+                }
+                DiffOp::Moved { from, to, .. } => {
+                    writeln!(out, "**Moved** `{from}` to `{to}`.\n").map_err(Error::custom)?;
+                }
+                DiffOp::DocsChanged { path, .. } => {
+                    writeln!(out, "**Docs changed** for `{path}`.\n").map_err(Error::custom)?;
+                }
+            }
+        }
+
+        out.truncate(out.trim_end().len());
+
+        Ok(line_ending.apply_owned(out))
+    }
+}
+
+impl IntoIterator for Diff {
+    type Item = DiffOp;
+    type IntoIter = std::vec::IntoIter<DiffOp>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.ops.into_iter()
+    }
+}
+
+impl<'d> IntoIterator for &'d Diff {
+    type Item = &'d DiffOp;
+    type IntoIter = std::slice::Iter<'d, DiffOp>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.ops.iter()
+    }
+}
+
+struct Differ {
+    path: Path<'static>,
+    options: DiffOptions,
+    ops: Vec<DiffOp>,
+}
+
+impl Differ {
+    fn diff_cells(&mut self, old: &ValueCell, new: &ValueCell) {
+        if !self.options.ignore_docs {
+            let (old_docs, new_docs) = (docs(old), docs(new));
+
+            if old_docs != new_docs {
+                self.ops.push(DiffOp::DocsChanged {
+                    path: self.path.clone(),
+                    old: old_docs,
+                    new: new_docs,
+                });
+            }
+        }
+
+        self.diff_values(old, new);
+    }
+
+    fn diff_values(&mut self, old: &Value, new: &Value) {
+        // NOTE: equal values can still have different docs deeper in the tree.
+        if self.options.ignore_docs && old == new {
+            return;
+        }
+
+        match (old, new) {
+            (Value::Sequence(old), Value::Sequence(new)) => self.diff_sequences(old, new),
+            (Value::Map(old), Value::Map(new)) => {
+                self.diff_entries(old, new, |k| PathItem::MapKey(k.into()))
+            }
+            (Value::Struct(old), Value::Struct(new)) => {
+                self.diff_entries(old, new, |n| PathItem::StructFieldName(n.into()))
+            }
+            (Value::Variant(old_name, old), Value::Variant(new_name, new))
+                if old_name == new_name =>
+            {
+                self.path.push_variant_name(old_name.clone());
+                self.diff_cells(old, new);
+                self.path.pop();
+            }
+            _ if old == new => (),
+            _ => self.ops.push(DiffOp::Changed {
+                path: self.path.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            }),
+        }
+    }
+
+    fn diff_entries(
+        &mut self,
+        old: &IndexMap<String, ValueCell>,
+        new: &IndexMap<String, ValueCell>,
+        to_path_item: impl Fn(String) -> PathItem<'static>,
+    ) {
+        let mut removed = vec![];
+
+        for (key, old_value) in old {
+            match new.get(key) {
+                Some(new_value) => {
+                    self.path.push(to_path_item(key.clone()));
+                    self.diff_cells(old_value, new_value);
+                    self.path.pop();
+                }
+                None => removed.push((to_path_item(key.clone()), old_value)),
+            }
+        }
+
+        let added = new
+            .iter()
+            .filter(|(key, _)| !old.contains_key(*key))
+            .map(|(key, value)| (to_path_item(key.clone()), value))
+            .collect();
+
+        let (removed, added) = self.detect_moves(removed, added);
+
+        self.report_removed(removed);
+        self.report_added(added);
+    }
+
+    // NOTE: elements are aligned by the longest common subsequence, so insertions and removals
+    // don't make all the following elements look changed. Unaligned elements that are equal
+    // are reported as moved, the rest of them are compared pairwise between alignment points.
+    fn diff_sequences(&mut self, old: &[ValueCell], new: &[ValueCell]) {
+        let aligned = lcs(old, new);
+
+        if !self.options.ignore_docs {
+            for &(i, j) in &aligned {
+                self.path.push_sequence_index(j);
+                self.diff_cells(&old[i], &new[j]);
+                self.path.pop();
+            }
+        }
+
+        let mut gaps = vec![];
+        let (mut old_start, mut new_start) = (0, 0);
+
+        for (i, j) in aligned.into_iter().chain([(old.len(), new.len())]) {
+            gaps.push((old_start..i, new_start..j));
+            (old_start, new_start) = (i + 1, j + 1);
+        }
+
+        let removed = gaps
+            .iter()
+            .flat_map(|(old_range, _)| old_range.clone())
+            .map(|i| (PathItem::SequenceIndex(i), &old[i]))
+            .collect();
+
+        let added = gaps
+            .iter()
+            .flat_map(|(_, new_range)| new_range.clone())
+            .map(|j| (PathItem::SequenceIndex(j), &new[j]))
+            .collect();
+
+        let (removed, added) = self.detect_moves(removed, added);
+        let mut removed = removed.into_iter().peekable();
+        let mut added = added.into_iter().peekable();
+
+        for (old_range, new_range) in gaps {
+            let in_range = |item: &PathItem, range: &std::ops::Range<usize>| matches!(item, PathItem::SequenceIndex(idx) if range.contains(idx));
+
+            let mut gap_removed = vec![];
+            let mut gap_added = vec![];
+
+            while let Some(item) = removed.next_if(|(item, _)| in_range(item, &old_range)) {
+                gap_removed.push(item);
+            }
+
+            while let Some(item) = added.next_if(|(item, _)| in_range(item, &new_range)) {
+                gap_added.push(item);
+            }
+
+            let paired = gap_removed.len().min(gap_added.len());
+            let unpaired_removed = gap_removed.split_off(paired);
+            let unpaired_added = gap_added.split_off(paired);
+
+            for ((_, old_value), (new_item, new_value)) in gap_removed.into_iter().zip(gap_added) {
+                self.path.push(new_item);
+                self.diff_cells(old_value, new_value);
+                self.path.pop();
+            }
+
+            self.report_removed(unpaired_removed);
+            self.report_added(unpaired_added);
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn detect_moves<'v>(
+        &mut self,
+        removed: Vec<(PathItem<'static>, &'v ValueCell)>,
+        added: Vec<(PathItem<'static>, &'v ValueCell)>,
+    ) -> (
+        Vec<(PathItem<'static>, &'v ValueCell)>,
+        Vec<(PathItem<'static>, &'v ValueCell)>,
+    ) {
+        let mut added = added.into_iter().map(Some).collect::<Vec<_>>();
+        let mut not_moved = vec![];
+
+        for (from_item, value) in removed {
+            let to = added
+                .iter_mut()
+                .find(|added| added.as_ref().is_some_and(|(_, v)| *v == value));
+
+            match to.and_then(Option::take) {
+                Some((to_item, _)) => {
+                    let from = self.child_path(from_item);
+                    let to = self.child_path(to_item);
+
+                    self.ops.push(DiffOp::Moved {
+                        from,
+                        to,
+                        value: value.as_value().clone(),
+                    });
+                }
+                None => not_moved.push((from_item, value)),
+            }
+        }
+
+        (not_moved, added.into_iter().flatten().collect())
+    }
+
+    fn report_removed(&mut self, removed: Vec<(PathItem<'static>, &ValueCell)>) {
+        for (item, value) in removed {
+            let path = self.child_path(item);
+
+            self.ops.push(DiffOp::Removed {
+                path,
+                value: value.as_value().clone(),
+            });
+        }
+    }
+
+    fn report_added(&mut self, added: Vec<(PathItem<'static>, &ValueCell)>) {
+        for (item, value) in added {
+            let path = self.child_path(item);
+
+            self.ops.push(DiffOp::Added {
+                path,
+                value: value.as_value().clone(),
+            });
+        }
+    }
+
+    fn child_path(&self, item: PathItem<'static>) -> Path<'static> {
+        self.path.items().iter().cloned().chain([item]).collect()
+    }
+}
+
+fn docs(value: &ValueCell) -> Docs {
+    let lexical_info = value.lexical_info();
+
+    Docs {
+        before: lexical_info.docs_before.clone(),
+        after: lexical_info.docs_after.clone(),
+    }
+}
+
+// NOTE: returns index pairs of equal elements in the longest common subsequence.
+fn lcs(old: &[ValueCell], new: &[ValueCell]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut aligned = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            aligned.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    aligned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use indoc::indoc;
+
+    fn ops(old: &str, new: &str, options: DiffOptions) -> Vec<String> {
+        let (old, new) = (parse(old).unwrap(), parse(new).unwrap());
+
+        diff_with_options(&old, &new, options)
+            .into_iter()
+            .map(|op| match op {
+                DiffOp::Added { path, value } => format!("+ {path} = {value:?}"),
+                DiffOp::Removed { path, value } => format!("- {path} = {value:?}"),
+                DiffOp::Changed { path, old, new } => format!("~ {path} = {old:?} -> {new:?}"),
+                DiffOp::Moved { from, to, .. } => format!("{from} -> {to}"),
+                DiffOp::DocsChanged { path, .. } => format!("docs {path}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn structs_and_maps() {
+        let old = indoc! {"
+            > server > host = \"localhost\"
+
+            > server > port = 80
+
+            > env > ['HOME'] = \"/root\"
+
+            > mode > `Fast` > level = 1
+        "};
+
+        let new = indoc! {"
+            > server > port = 8080
+
+            > server > tls = true
+
+            > env > ['USER_HOME'] = \"/root\"
+
+            > mode > `Slow` > level = 1
+        "};
+
+        assert_eq!(
+            ops(old, new, Default::default()),
+            [
+                "~ > server > port = UInt(80) -> UInt(8080)",
+                "- > server > host = String(\"localhost\")",
+                "+ > server > tls = Bool(true)",
+                "> env > [\"HOME\"] -> > env > [\"USER_HOME\"]",
+                "~ > mode = Variant(\"Fast\", Struct({\"level\": UInt(1)})) -> \
+                Variant(\"Slow\", Struct({\"level\": UInt(1)}))",
+            ]
+        );
+
+        assert!(diff(&parse(old).unwrap(), &parse(old).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn sequences() {
+        let options = Default::default();
+
+        assert_eq!(
+            ops("> = [1, 2, 3, 4]", "> = [0, 1, 3, 4, 5]", options),
+            [
+                "+ > [0] = UInt(0)",
+                "- > [1] = UInt(2)",
+                "+ > [4] = UInt(5)"
+            ]
+        );
+
+        assert_eq!(
+            ops("> = [1, 2, 3]", "> = [3, 1, 2]", options),
+            ["> [2] -> > [0]"]
+        );
+
+        let old = indoc! {"
+            > servers > [0] > name = \"a\"
+
+            > servers > [1] > name = \"b\"
+
+            > servers > [1] > port = 2
+        "};
+
+        let new = indoc! {"
+            > servers > [0] > name = \"z\"
+
+            > servers > [1] > name = \"a\"
+
+            > servers > [2] > name = \"b\"
+
+            > servers > [2] > port = 3
+        "};
+
+        assert_eq!(
+            ops(old, new, options),
+            [
+                "+ > servers > [0] = Struct({\"name\": String(\"z\")})",
+                "~ > servers > [2] > port = UInt(2) -> UInt(3)",
+            ]
+        );
+    }
+
+    #[test]
+    fn docs() {
+        let old = "Port.\n\n> server > port = 80\n\n> list = [1]";
+        let new = "Server port.\n\n> server > port = 80\n\n> list = [1]\n\nThe end.";
+
+        assert_eq!(
+            ops(old, new, Default::default()),
+            ["docs > server > port", "docs > list"]
+        );
+
+        let options = DiffOptions { ignore_docs: true };
+
+        assert!(ops(old, new, options).is_empty());
+    }
+
+    #[test]
+    fn markdown() {
+        let old =
+            parse("> server > port = 80\n\n> server > legacy = true\n\n> list = [1, 2]").unwrap();
+        let new =
+            parse("> server > port = 8080\n\n> server > tls > cert = \"a.pem\"\n\n> list = [2, 1]")
+                .unwrap();
+
+        assert_eq!(
+            old.diff(&new).to_markdown().unwrap(),
+            indoc! {"
+                **Changed** `> server > port` from:
+
+                > server > port = 80
+
+                to:
+
+                > server > port = 8080
+
+                **Removed** `> server > legacy`:
+
+                > server > legacy = true
+
+                **Added** `> server > tls`:
+
+                > server > tls > cert = \"a.pem\"
+
+                **Moved** `> list > [0]` to `> list > [1]`."
+            }
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use super::index::index_matches_variant;
+use super::{Value, ValueCell};
+use indexmap::map;
+use std::mem;
+
+pub enum Entry<'v> {
+    Occupied(OccupiedEntry<'v>),
+    Vacant(VacantEntry<'v>),
+}
+
+pub struct OccupiedEntry<'v>(OccupiedInner<'v>);
+
+enum OccupiedInner<'v> {
+    Map(map::OccupiedEntry<'v, String, ValueCell>),
+    Variant(&'v String, &'v mut ValueCell),
+}
+
+pub struct VacantEntry<'v>(VacantInner<'v>);
+
+enum VacantInner<'v> {
+    Map(map::VacantEntry<'v, String, ValueCell>),
+    Variant {
+        name: &'v mut String,
+        value: &'v mut ValueCell,
+        new_name: String,
+    },
+}
+
+impl Value {
+    // NOTE: variants have a single entry which key is the variant name in backticks, same as for
+    // indexing. Inserting into a vacant variant entry changes the variant.
+    pub fn entry(&mut self, key: &str) -> Option<Entry<'_>> {
+        match self {
+            Value::Struct(m) | Value::Map(m) => Some(match m.entry(key.to_string()) {
+                map::Entry::Occupied(e) => Entry::Occupied(OccupiedEntry(OccupiedInner::Map(e))),
+                map::Entry::Vacant(e) => Entry::Vacant(VacantEntry(VacantInner::Map(e))),
+            }),
+            Value::Variant(name, value) => {
+                if index_matches_variant(key, name) {
+                    return Some(Entry::Occupied(OccupiedEntry(OccupiedInner::Variant(
+                        name, value,
+                    ))));
+                }
+
+                let new_name = key.strip_prefix('`')?.strip_suffix('`')?.to_string();
+
+                Some(Entry::Vacant(VacantEntry(VacantInner::Variant {
+                    name,
+                    value,
+                    new_name,
+                })))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'v> Entry<'v> {
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Occupied(e) => e.key(),
+            Entry::Vacant(e) => e.key(),
+        }
+    }
+
+    #[inline]
+    pub fn or_insert(self, default: impl Into<Value>) -> &'v mut ValueCell {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<V: Into<Value>>(self, default: impl FnOnce() -> V) -> &'v mut ValueCell {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    #[inline]
+    pub fn or_default(self) -> &'v mut ValueCell {
+        self.or_insert_with(Value::default)
+    }
+
+    pub fn and_modify(mut self, f: impl FnOnce(&mut ValueCell)) -> Self {
+        if let Entry::Occupied(ref mut e) = self {
+            f(e.get_mut());
+        }
+
+        self
+    }
+
+    // NOTE: replaces the value, but keeps the lexical info of the occupied entry, so the docs
+    // are not lost on serialization.
+    pub fn insert(self, value: impl Into<Value>) -> &'v mut ValueCell {
+        match self {
+            Entry::Occupied(mut e) => {
+                e.insert(value);
+                e.into_mut()
+            }
+            Entry::Vacant(e) => e.insert(value),
+        }
+    }
+}
+
+impl<'v> OccupiedEntry<'v> {
+    pub fn key(&self) -> &str {
+        match self.0 {
+            OccupiedInner::Map(ref e) => e.key(),
+            OccupiedInner::Variant(name, _) => name,
+        }
+    }
+
+    pub fn get(&self) -> &ValueCell {
+        match self.0 {
+            OccupiedInner::Map(ref e) => e.get(),
+            OccupiedInner::Variant(_, ref value) => value,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut ValueCell {
+        match self.0 {
+            OccupiedInner::Map(ref mut e) => e.get_mut(),
+            OccupiedInner::Variant(_, ref mut value) => value,
+        }
+    }
+
+    pub fn into_mut(self) -> &'v mut ValueCell {
+        match self.0 {
+            OccupiedInner::Map(e) => e.into_mut(),
+            OccupiedInner::Variant(_, value) => value,
+        }
+    }
+
+    #[inline]
+    pub fn insert(&mut self, value: impl Into<Value>) -> Value {
+        mem::replace(self.get_mut().as_value_mut(), value.into())
+    }
+}
+
+impl<'v> VacantEntry<'v> {
+    pub fn key(&self) -> &str {
+        match self.0 {
+            VacantInner::Map(ref e) => e.key(),
+            VacantInner::Variant { ref new_name, .. } => new_name,
+        }
+    }
+
+    pub fn insert(self, value: impl Into<Value>) -> &'v mut ValueCell {
+        match self.0 {
+            VacantInner::Map(e) => e.insert(value.into().into_cell()),
+            VacantInner::Variant {
+                name,
+                value: variant_value,
+                new_name,
+            } => {
+                *name = new_name;
+                *variant_value.as_value_mut() = value.into();
+
+                variant_value
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn map_and_struct_entries() {
+        let mut value = parse("> foo > ['bar'] = 42\n\nDocs for baz.\n\n> baz = 1").unwrap();
+
+        assert_eq!(
+            *value["foo"].entry("bar").unwrap().or_insert(43),
+            Value::UInt(42)
+        );
+
+        assert_eq!(
+            *value["foo"].entry("qux").unwrap().or_insert_with(|| "quz"),
+            Value::String("quz".into())
+        );
+
+        let entry = value.entry("baz").unwrap().and_modify(|v| {
+            *v.as_value_mut() = Value::UInt(2);
+        });
+
+        assert_eq!(entry.key(), "baz");
+        assert_eq!(*entry.insert(3u64), Value::UInt(3));
+        assert_eq!(value["baz"].lexical_info().docs_before, "Docs for baz.\n\n");
+        assert_eq!(*value.entry("new").unwrap().or_default(), Value::Null);
+
+        assert_eq!(
+            value["foo"].as_map().unwrap().keys().collect::<Vec<_>>(),
+            ["bar", "qux"]
+        );
+    }
+
+    #[test]
+    fn variant_entries() {
+        let mut value = parse("Docs for foo.\n\n> `foo` = 42").unwrap();
+
+        assert_eq!(
+            *value.entry("`foo`").unwrap().or_insert(43),
+            Value::UInt(42)
+        );
+        assert!(value.entry("foo").is_none());
+
+        let Some(Entry::Vacant(entry)) = value.entry("`bar`") else {
+            panic!("expected vacant entry");
+        };
+
+        assert_eq!(entry.key(), "bar");
+
+        entry.insert(43u64);
+
+        assert_eq!(*value, Value::Variant("bar".into(), Value::UInt(43).into()));
+        assert_eq!(
+            value["`bar`"].lexical_info().docs_before,
+            "Docs for foo.\n\n"
+        );
+    }
+
+    #[test]
+    fn no_entries() {
+        let mut value = parse("> = [1, 2]").unwrap();
+
+        assert!(value.entry("foo").is_none());
+        assert!(value[0].entry("foo").is_none());
+    }
+}
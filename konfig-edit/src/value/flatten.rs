@@ -0,0 +1,334 @@
+use super::{Path, PathItem, Value, ValueCell};
+use crate::error::{Error, Result};
+use indexmap::IndexMap;
+
+// NOTE: arrays of primitives are assigned as a whole in konfig, so by default they are kept as
+// leaves. Empty containers are always leaves, as otherwise they would be lost on unflattening.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PrimitiveArrays {
+    #[default]
+    Leaf,
+    Expand,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlattenOptions {
+    pub primitive_arrays: PrimitiveArrays,
+}
+
+impl Value {
+    #[inline]
+    pub fn flatten(&self) -> IndexMap<Path<'static>, Value> {
+        self.flatten_with_options(Default::default())
+    }
+
+    pub fn flatten_with_options(&self, options: FlattenOptions) -> IndexMap<Path<'static>, Value> {
+        let mut flat = IndexMap::new();
+
+        flatten_into(&mut flat, &mut Path::default(), self, options);
+
+        flat
+    }
+
+    // NOTE: containers are inferred from path items the same way as in the parser: struct field
+    // names create structures, map keys create maps, variant names create variants and sequence
+    // items must be defined in order, starting from `[0]`.
+    pub fn unflatten<'i>(flat: impl IntoIterator<Item = (Path<'i>, Value)>) -> Result<Value> {
+        let mut root = None;
+
+        for (path, value) in flat {
+            insert(&mut root, &path, value)?;
+        }
+
+        root.ok_or_else(|| Error::custom("can't unflatten an empty set of paths"))
+    }
+}
+
+fn flatten_into(
+    flat: &mut IndexMap<Path<'static>, Value>,
+    path: &mut Path<'static>,
+    value: &Value,
+    options: FlattenOptions,
+) {
+    let mut flatten_child = |path: &mut Path<'static>, item, cell: &ValueCell| {
+        path.push(item);
+        flatten_into(flat, path, cell, options);
+        path.pop();
+    };
+
+    match value {
+        Value::Sequence(seq)
+            if !seq.is_empty()
+                && (options.primitive_arrays == PrimitiveArrays::Expand
+                    || seq.iter().any(|v| v.is_container())) =>
+        {
+            for (idx, cell) in seq.iter().enumerate() {
+                flatten_child(path, PathItem::SequenceIndex(idx), cell);
+            }
+        }
+        Value::Map(map) if !map.is_empty() => {
+            for (key, cell) in map {
+                flatten_child(path, PathItem::MapKey(key.clone().into()), cell);
+            }
+        }
+        Value::Struct(fields) if !fields.is_empty() => {
+            for (name, cell) in fields {
+                flatten_child(path, PathItem::StructFieldName(name.clone().into()), cell);
+            }
+        }
+        Value::Variant(name, cell) => {
+            flatten_child(path, PathItem::VariantName(name.clone().into()), cell)
+        }
+        _ => {
+            flat.insert(path.clone(), value.clone());
+        }
+    }
+}
+
+fn insert(root: &mut Option<Value>, path: &Path, value: Value) -> Result<()> {
+    let items = path.items();
+
+    let Some(mut host) = root.as_mut() else {
+        *root = Some(wrap(path, items, value)?);
+
+        return Ok(());
+    };
+
+    for (depth, item) in items.iter().enumerate() {
+        let err = |msg: &str| Error::custom(format!("{msg} at path: {path}"));
+
+        let next = match (host, item) {
+            (Value::Sequence(seq), PathItem::SequenceIndex(idx)) => {
+                if *idx > seq.len() {
+                    return Err(err("sequence items must be defined in order"));
+                }
+
+                if *idx == seq.len() {
+                    let rest = wrap(path, &items[depth + 1..], value)?;
+
+                    seq.push(rest.into());
+
+                    return Ok(());
+                }
+
+                &mut seq[*idx]
+            }
+            (Value::Map(map), PathItem::MapKey(key)) => match map.get_index_of(key.as_ref()) {
+                Some(idx) => &mut map[idx],
+                None => {
+                    let rest = wrap(path, &items[depth + 1..], value)?;
+
+                    map.insert(key.to_string(), rest.into());
+
+                    return Ok(());
+                }
+            },
+            (Value::Struct(fields), PathItem::StructFieldName(name)) => {
+                match fields.get_index_of(name.as_ref()) {
+                    Some(idx) => &mut fields[idx],
+                    None => {
+                        let rest = wrap(path, &items[depth + 1..], value)?;
+
+                        fields.insert(name.to_string(), rest.into());
+
+                        return Ok(());
+                    }
+                }
+            }
+            (Value::Variant(name, cell), PathItem::VariantName(item)) if name == item => cell,
+            _ => {
+                return Err(err(
+                    "path item has incompatible type with the previously specified values",
+                ))
+            }
+        };
+
+        host = next.as_value_mut();
+    }
+
+    Err(Error::custom(format!(
+        "the path already has a value assigned: {path}"
+    )))
+}
+
+pub(super) fn wrap(path: &Path, items: &[PathItem], value: Value) -> Result<Value> {
+    items.iter().rev().try_fold(value, |value, item| {
+        item.clone().wrap(value.into()).ok_or_else(|| {
+            Error::custom(format!(
+                "sequence items should be defined in order, with the first item having index `0` \
+                at path: {path}"
+            ))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        > server > host = \"localhost\"
+
+        > server > ports = [80, 443]
+
+        > server > tags = []
+
+        > env > ['HOME'] = \"/root\"
+
+        > env > ['EMPTY'] > `None` = null
+
+        > users > [0] > name = \"root\"
+
+        > users > [1] > `Guest` > ttl = 60
+
+        > matrix > [0] = [1, 2]
+    "};
+
+    fn keys(flat: &IndexMap<Path, Value>) -> Vec<String> {
+        flat.keys().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn flatten() {
+        let value = parse(INPUT).unwrap();
+        let flat = value.flatten();
+
+        assert_eq!(
+            keys(&flat),
+            [
+                "> server > host",
+                "> server > ports",
+                "> server > tags",
+                "> env > [\"HOME\"]",
+                "> env > [\"EMPTY\"] > `None`",
+                "> users > [0] > name",
+                "> users > [1] > `Guest` > ttl",
+                "> matrix > [0]",
+            ]
+        );
+
+        assert_eq!(flat[3], Value::String("/root".into()));
+
+        let flat = value.flatten_with_options(FlattenOptions {
+            primitive_arrays: PrimitiveArrays::Expand,
+        });
+
+        assert_eq!(
+            keys(&flat)[1..4],
+            [
+                "> server > ports > [0]",
+                "> server > ports > [1]",
+                "> server > tags",
+            ]
+        );
+
+        assert_eq!(keys(&flat).last().unwrap(), "> matrix > [0] > [1]");
+        assert_eq!(keys(&parse("> = 42").unwrap().flatten()), [">"]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = parse(INPUT).unwrap().into_value();
+
+        assert_eq!(Value::unflatten(value.flatten()).unwrap(), value);
+
+        let flat = value.flatten_with_options(FlattenOptions {
+            primitive_arrays: PrimitiveArrays::Expand,
+        });
+
+        assert_eq!(Value::unflatten(flat).unwrap(), value);
+
+        let value = Value::UInt(42);
+
+        assert_eq!(Value::unflatten(value.flatten()).unwrap(), value);
+    }
+
+    #[test]
+    fn unflatten_errors() {
+        const SEQ: &str = "> foo > [0] > a = 1\n\n> foo > [1] > a = 2\n\n> foo > [2] > a = 3";
+
+        let mut flat = parse(SEQ).unwrap().flatten();
+
+        flat.reverse();
+
+        assert_eq!(
+            Value::unflatten(flat).unwrap_err().to_string(),
+            "sequence items should be defined in order, with the first item having index `0` \
+            at path: > foo > [2] > a"
+        );
+
+        let mut flat = parse(SEQ).unwrap().flatten();
+
+        flat.shift_remove_index(1);
+
+        assert_eq!(
+            Value::unflatten(flat).unwrap_err().to_string(),
+            "sequence items must be defined in order at path: > foo > [2] > a"
+        );
+
+        let foo = |item| [PathItem::StructFieldName("foo".into()), item];
+
+        assert_eq!(
+            Value::unflatten([
+                (
+                    foo(PathItem::StructFieldName("bar".into()))
+                        .into_iter()
+                        .collect(),
+                    Value::Null
+                ),
+                (
+                    foo(PathItem::MapKey("bar".into())).into_iter().collect(),
+                    Value::Null
+                ),
+            ])
+            .unwrap_err()
+            .to_string(),
+            "path item has incompatible type with the previously specified values at path: \
+            > foo > [\"bar\"]"
+        );
+
+        assert_eq!(
+            Value::unflatten([
+                (Path::default(), Value::Null),
+                (Path::default(), Value::Null),
+            ])
+            .unwrap_err()
+            .to_string(),
+            "the path already has a value assigned: >"
+        );
+
+        assert!(Value::unflatten(IndexMap::<Path, Value>::new()).is_err());
+    }
+
+    #[test]
+    fn unflatten_out_of_order() {
+        let flat = [
+            (
+                [PathItem::SequenceIndex(0), PathItem::SequenceIndex(0)]
+                    .into_iter()
+                    .collect::<Path>(),
+                Value::UInt(1),
+            ),
+            (
+                [PathItem::SequenceIndex(1)].into_iter().collect(),
+                Value::UInt(2),
+            ),
+            (
+                [PathItem::SequenceIndex(0), PathItem::SequenceIndex(1)]
+                    .into_iter()
+                    .collect(),
+                Value::UInt(3),
+            ),
+        ];
+
+        assert_eq!(
+            Value::unflatten(flat).unwrap(),
+            Value::Sequence(vec![
+                Value::Sequence(vec![Value::UInt(1).into(), Value::UInt(3).into()]).into(),
+                Value::UInt(2).into(),
+            ])
+        );
+    }
+}
@@ -1,11 +1,11 @@
-use super::{Value, ValueCell};
-use crate::error::{Error, Result};
-use indexmap::IndexMap;
+use super::from_value_ref::{from_value_ref, Copied, ValueRef};
+use super::{Path, PathItem, Value, ValueCell};
+use crate::error::{ConversionError, Error, Result};
 use serde::de::IntoDeserializer;
 use serde::de::Unexpected;
-use serde::de::{Deserialize, DeserializeSeed, Visitor};
+use serde::de::{Deserialize, DeserializeOwned, Visitor};
 use serde::forward_to_deserialize_any;
-use std::vec;
+use std::{any, vec};
 
 pub fn from_value<'a, T>(value: Value) -> Result<T>
 where
@@ -14,170 +14,185 @@ where
     T::deserialize(value)
 }
 
-impl<'de> serde::de::Deserializer<'de> for Value {
-    type Error = Error;
-
-    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        match self {
-            Value::Null => visitor.visit_unit(),
-            Value::Bool(v) => visitor.visit_bool(v),
-            Value::Float(v) => visitor.visit_f64(v),
-            Value::Int(v) => visitor.visit_i64(v),
-            Value::UInt(v) => visitor.visit_u64(v),
-            Value::String(v) => visitor.visit_string(v),
-            Value::Sequence(v) => deserialize_seq(v, visitor),
-            Value::Map(v) | Value::Struct(v) => deserialize_map(v, visitor),
-            Value::UnitVariant(v) => visitor.visit_enum(EnumDeserializer {
-                variant: v,
-                value: None,
-            }),
-            Value::Variant(v, value) => visitor.visit_enum(EnumDeserializer {
-                variant: v,
-                value: Some(value),
-            }),
-        }
-    }
-
-    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+impl Value {
+    // NOTE: converts the value at the path, e.g. `> server > port`, with `from_value`. Missing
+    // values are converted from null, so they can be read as `Option`s.
+    #[inline]
+    pub fn get_as<T>(&self, path: &str) -> Result<T>
     where
-        V: Visitor<'de>,
+        T: DeserializeOwned,
     {
-        match self {
-            Value::Null => visitor.visit_none(),
-            _ => visitor.visit_some(self),
-        }
+        get_as(self, vec![], path)
     }
+}
 
+impl ValueCell {
+    // NOTE: the same as `Value::get_as`, but the root can locate the errors as well.
     #[inline]
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    pub fn get_as<T>(&self, path: &str) -> Result<T>
     where
-        V: Visitor<'de>,
+        T: DeserializeOwned,
     {
-        visitor.visit_newtype_struct(self)
-    }
-
-    forward_to_deserialize_any! {
-        bool
-        u8 u16 u32 u64
-        i8 i16 i32 i64
-        f32 f64
-        char str string
-        bytes byte_buf
-        unit
-        unit_struct tuple_struct struct
-        seq tuple
-        map
-        enum
-        identifier
-        ignored_any
+        get_as(self, vec![self], path)
     }
 }
 
-fn deserialize_seq<'de, V>(elems: Vec<ValueCell>, visitor: V) -> Result<V::Value>
+fn get_as<'v, T>(root: &'v Value, mut cells: Vec<&'v ValueCell>, path: &str) -> Result<T>
 where
-    V: Visitor<'de>,
+    T: DeserializeOwned,
 {
-    let len = elems.len();
+    let path: Path<'static> = path.parse()?;
+    let mut value = root;
 
-    let mut deserializer = SeqDeserializer {
-        len,
-        iter: elems.into_iter(),
-    };
+    for (idx, item) in path.items().iter().enumerate() {
+        let Some(cell) = value.get_path(std::slice::from_ref(item)) else {
+            return from_value(Value::Null).map_err(|_| {
+                let missing = format!("missing value for `{}`", short_type_name::<T>());
 
-    let seq = visitor.visit_seq(&mut deserializer)?;
+                conversion_error::<T>(path.items()[..=idx].to_vec(), None, missing, &cells)
+            });
+        };
 
-    if deserializer.iter.len() == 0 {
-        Ok(seq)
-    } else {
-        Err(Error::de_fewer_elements_in_seq(len))
+        cells.push(cell);
+        value = cell;
     }
-}
-
-fn deserialize_map<'de, V>(elems: IndexMap<String, ValueCell>, visitor: V) -> Result<V::Value>
-where
-    V: Visitor<'de>,
-{
-    let len = elems.len();
 
-    let mut deserializer = MapDeserializer {
-        len,
-        iter: elems.into_iter(),
-        next_value: None,
-    };
-
-    let map = visitor.visit_map(&mut deserializer)?;
+    from_value_ref(value).map_err(|err| {
+        let mut items = path.items().to_vec();
+        let requested = value;
+
+        // NOTE: errors in nested values are located at the nested values, but the expected type
+        // and the actual kind are the ones at the requested path.
+        let err = match err {
+            Error::AtPath {
+                path: nested,
+                error,
+            } => match nested.parse::<Path>() {
+                Ok(nested) => {
+                    for item in nested.items() {
+                        if let Some(cell) = value.get_path(std::slice::from_ref(item)) {
+                            cells.push(cell);
+                            value = cell;
+                        }
+
+                        items.push(item.clone());
+                    }
+
+                    *error
+                }
+                Err(_) => Error::AtPath {
+                    path: nested,
+                    error,
+                },
+            },
+            err => err,
+        };
 
-    if deserializer.iter.len() == 0 {
-        Ok(map)
-    } else {
-        Err(Error::de_fewer_elements_in_map(len))
-    }
+        conversion_error::<T>(items, Some(requested), err.to_string(), &cells)
+    })
 }
 
-struct SeqDeserializer {
-    len: usize,
-    iter: vec::IntoIter<ValueCell>,
+fn conversion_error<T>(
+    path: Vec<PathItem<'static>>,
+    actual: Option<&Value>,
+    message: String,
+    cells: &[&ValueCell],
+) -> Error {
+    // NOTE: values without a span, e.g. items of primitive arrays, are located at the closest
+    // ancestor with a span.
+    let info = cells
+        .iter()
+        .rev()
+        .map(|cell| cell.lexical_info())
+        .find(|info| info.span.is_some());
+
+    Error::Conversion(Box::new(ConversionError {
+        path,
+        expected: short_type_name::<T>(),
+        actual: actual.map(Value::kind),
+        message,
+        span: info.and_then(|info| info.span.clone()),
+        source: info.and_then(|info| info.source.as_deref().map(Into::into)),
+    }))
 }
 
-impl<'de> serde::de::SeqAccess<'de> for SeqDeserializer {
-    type Error = Error;
+// NOTE: e.g. `Option<Vec<String>>` for
+// `core::option::Option<alloc::vec::Vec<alloc::string::String>>`.
+fn short_type_name<T>() -> String {
+    let name = any::type_name::<T>();
+    let mut short = String::with_capacity(name.len());
+    let mut segment_start = 0;
 
-    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
-    where
-        T: DeserializeSeed<'de>,
-    {
-        match self.iter.next() {
-            Some(value) => seed.deserialize(value.into_value()).map(Some),
-            None => Ok(None),
+    for (idx, c) in name.char_indices() {
+        if c.is_alphanumeric() || c == '_' {
+            continue;
         }
-    }
 
-    #[inline]
-    fn size_hint(&self) -> Option<usize> {
-        Some(self.len)
+        if c == ':' {
+            segment_start = idx + 1;
+            continue;
+        }
+
+        short.push_str(&name[segment_start..idx]);
+        short.push(c);
+        segment_start = idx + c.len_utf8();
     }
-}
 
-struct MapDeserializer {
-    len: usize,
-    iter: indexmap::map::IntoIter<String, ValueCell>,
-    next_value: Option<ValueCell>,
+    short.push_str(&name[segment_start..]);
+    short
 }
 
-impl<'de> serde::de::MapAccess<'de> for MapDeserializer {
+// NOTE: values are deserialized by the borrowed value deserializer with strings copied. Errors are
+// reported without paths, `Value::get_as` and the loader locate them instead.
+impl<'de> serde::de::Deserializer<'de> for Value {
     type Error = Error;
 
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    #[inline]
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        ValueRef::<Copied>::new(&self)
+            .deserialize_any(visitor)
+            .map_err(Error::without_path)
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
-        K: DeserializeSeed<'de>,
+        V: Visitor<'de>,
     {
-        match self.iter.next() {
-            Some((key, value)) => {
-                self.next_value = Some(value);
-                seed.deserialize(MapKeyDeserializer { key }).map(Some)
-            }
-            None => Ok(None),
-        }
+        ValueRef::<Copied>::new(&self)
+            .deserialize_option(visitor)
+            .map_err(Error::without_path)
     }
 
-    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    #[inline]
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
-        V: DeserializeSeed<'de>,
+        V: Visitor<'de>,
     {
-        match self.next_value.take() {
-            Some(value) => seed.deserialize(value.into_value()),
-            None => Err(Error::de_map_value_missing()),
-        }
+        ValueRef::<Copied>::new(&self)
+            .deserialize_newtype_struct(name, visitor)
+            .map_err(Error::without_path)
     }
 
-    #[inline]
-    fn size_hint(&self) -> Option<usize> {
-        Some(self.len)
+    forward_to_deserialize_any! {
+        bool
+        u8 u16 u32 u64
+        i8 i16 i32 i64
+        f32 f64
+        char str string
+        bytes byte_buf
+        unit
+        unit_struct tuple_struct struct
+        seq tuple
+        map
+        enum
+        identifier
+        ignored_any
     }
 }
 
-struct MapKeyDeserializer {
-    key: String,
+pub(super) struct MapKeyDeserializer {
+    pub(super) key: String,
 }
 
 macro_rules! deserialize_with_from_str {
@@ -262,75 +277,8 @@ impl<'de> serde::de::Deserializer<'de> for MapKeyDeserializer {
     }
 }
 
-struct EnumDeserializer {
-    variant: String,
-    value: Option<ValueCell>,
-}
-
-impl<'de> serde::de::EnumAccess<'de> for EnumDeserializer {
-    type Error = Error;
-    type Variant = EnumVariantDeserializer;
-
-    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
-    where
-        V: DeserializeSeed<'de>,
-    {
-        let variant = seed.deserialize(self.variant.into_deserializer())?;
-        let deserializer = EnumVariantDeserializer { value: self.value };
-
-        Ok((variant, deserializer))
-    }
-}
-
-struct EnumVariantDeserializer {
-    value: Option<ValueCell>,
-}
-
-impl<'de> serde::de::VariantAccess<'de> for EnumVariantDeserializer {
-    type Error = Error;
-
-    fn unit_variant(self) -> Result<()> {
-        match self.value {
-            Some(_) => Err(Error::de_expected_unit_variant(Unexpected::NewtypeVariant)),
-            None => Ok(()),
-        }
-    }
-
-    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
-    where
-        T: DeserializeSeed<'de>,
-    {
-        match self.value {
-            Some(value) => seed.deserialize(value.into_value()),
-            None => Err(Error::de_expected_newtype_variant(Unexpected::UnitVariant)),
-        }
-    }
-
-    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        match self.value.map(ValueCell::into_value) {
-            Some(Value::Sequence(value)) => deserialize_seq(value, visitor),
-            Some(value) => Err(Error::de_expected_tuple_variant(value.as_unexpected())),
-            None => Err(Error::de_expected_tuple_variant(Unexpected::UnitVariant)),
-        }
-    }
-
-    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        match self.value.map(ValueCell::into_value) {
-            Some(Value::Struct(value)) => deserialize_map(value, visitor),
-            Some(value) => Err(Error::de_expected_struct_variant(value.as_unexpected())),
-            None => Err(Error::de_expected_struct_variant(Unexpected::UnitVariant)),
-        }
-    }
-}
-
 impl Value {
-    fn as_unexpected(&self) -> Unexpected {
+    pub(super) fn as_unexpected(&self) -> Unexpected<'_> {
         match *self {
             Value::Null => Unexpected::Unit,
             Value::Bool(v) => Unexpected::Bool(v),
@@ -433,7 +381,7 @@ mod tests {
             where
                 A: serde::de::MapAccess<'de>,
             {
-                map.next_value()?;
+                map.next_value::<()>()?;
                 Ok(TestDeserialized)
             }
         }
@@ -473,7 +421,7 @@ mod tests {
             where
                 A: serde::de::EnumAccess<'de>,
             {
-                data.variant::<String>()?.1.newtype_variant()?;
+                data.variant::<String>()?.1.newtype_variant::<()>()?;
                 Ok(TestDeserialized)
             }
         }
@@ -539,4 +487,86 @@ mod tests {
             Error::de_expected_struct_variant(Unexpected::UnitVariant)
         );
     }
+
+    #[test]
+    fn get_as() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Server {
+            host: String,
+            port: u16,
+        }
+
+        let input = indoc::indoc! {"
+            > server > host = \"localhost\"
+
+            > server > port = 70000
+
+            > server > tags = [\"a\", 1]
+        "};
+
+        let value = parse(input).unwrap();
+
+        let describe = |err: Error| match err {
+            Error::Conversion(err) => {
+                let (line, col) = err.line_col(input).unwrap();
+
+                format!(
+                    "{line}:{col}: {err} (expected {}, found {})",
+                    err.expected,
+                    err.actual.unwrap_or("nothing")
+                )
+            }
+            err => panic!("unexpected error: {err}"),
+        };
+
+        assert_eq!(
+            value.get_as::<String>("> server > host").unwrap(),
+            "localhost"
+        );
+
+        assert_eq!(value.get_as::<u32>("> server > port").unwrap(), 70000);
+        assert_eq!(value.get_as::<Option<u16>>("> server > tls").unwrap(), None);
+
+        assert_eq!(
+            describe(value.get_as::<u16>("> server > port").unwrap_err()),
+            "3:19: invalid value: integer `70000`, expected u16 at path: > server > port \
+            (expected u16, found integer)"
+        );
+
+        assert_eq!(
+            describe(value.get_as::<Server>("> server").unwrap_err()),
+            "3:19: invalid value: integer `70000`, expected u16 at path: > server > port \
+            (expected Server, found structure)"
+        );
+
+        assert_eq!(
+            describe(value.get_as::<Vec<String>>("> server > tags").unwrap_err()),
+            "5:19: invalid type: integer `1`, expected a string at path: > server > tags > [1] \
+            (expected Vec<String>, found sequence)"
+        );
+
+        let Error::Conversion(missing) = value.get_as::<u16>("> server > tls > port").unwrap_err()
+        else {
+            panic!("expected a conversion error");
+        };
+
+        assert_eq!(
+            missing.to_string(),
+            "missing value for `u16` at path: > server > tls"
+        );
+
+        assert_eq!(missing.actual, None);
+        assert_eq!(missing.span, None);
+
+        assert!(value.get_as::<u16>("> server > *").is_err());
+
+        assert_eq!(
+            value
+                .as_value()
+                .get_as::<Option<String>>("> server > host")
+                .unwrap()
+                .as_deref(),
+            Some("localhost")
+        );
+    }
 }
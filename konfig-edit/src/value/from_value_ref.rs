@@ -0,0 +1,394 @@
+use super::from_value::MapKeyDeserializer;
+use super::{PathItem, Value, ValueCell};
+use crate::error::{Error, Result};
+use indexmap::IndexMap;
+use serde::de::{Deserialize, DeserializeSeed, IntoDeserializer, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+use std::marker::PhantomData;
+use std::{iter, slice};
+
+// NOTE: deserializes from a borrowed value, so values are not cloned, e.g. to load the merged
+// configuration or to convert values at a path. Errors of nested values are reported with
+// `Error::AtPath`, so they can be located in the source documents.
+pub(crate) fn from_value_ref<'v, T>(value: &'v Value) -> Result<T>
+where
+    T: Deserialize<'v>,
+{
+    T::deserialize(ValueRef::<Borrowed>::new(value))
+}
+
+// NOTE: strings are borrowed from the value if it outlives the deserialized data, and copied
+// otherwise, e.g. when the value is deserialized by value.
+pub(super) trait VisitStr<'v, 'de> {
+    fn visit_str<V: Visitor<'de>>(v: &'v str, visitor: V) -> Result<V::Value>;
+}
+
+pub(super) enum Borrowed {}
+
+pub(super) enum Copied {}
+
+impl<'de> VisitStr<'de, 'de> for Borrowed {
+    #[inline]
+    fn visit_str<V: Visitor<'de>>(v: &'de str, visitor: V) -> Result<V::Value> {
+        visitor.visit_borrowed_str(v)
+    }
+}
+
+impl<'v, 'de> VisitStr<'v, 'de> for Copied {
+    #[inline]
+    fn visit_str<V: Visitor<'de>>(v: &'v str, visitor: V) -> Result<V::Value> {
+        visitor.visit_str(v)
+    }
+}
+
+pub(super) struct ValueRef<'v, S> {
+    value: &'v Value,
+    strs: PhantomData<S>,
+}
+
+impl<'v, S> ValueRef<'v, S> {
+    #[inline]
+    pub(super) fn new(value: &'v Value) -> Self {
+        Self {
+            value,
+            strs: PhantomData,
+        }
+    }
+}
+
+impl<'v, 'de, S> serde::de::Deserializer<'de> for ValueRef<'v, S>
+where
+    S: VisitStr<'v, 'de>,
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(v) => visitor.visit_bool(*v),
+            Value::Float(v) => visitor.visit_f64(*v),
+            Value::Int(v) => visitor.visit_i64(*v),
+            Value::UInt(v) => visitor.visit_u64(*v),
+            Value::String(v) => S::visit_str(v, visitor),
+            Value::Sequence(v) => deserialize_seq::<S, _>(v, visitor),
+            Value::Map(v) => deserialize_map::<S, _>(v, false, visitor),
+            Value::Struct(v) => deserialize_map::<S, _>(v, true, visitor),
+            Value::UnitVariant(v) => visitor.visit_enum(EnumDeserializer::<S> {
+                variant: v,
+                value: None,
+                strs: PhantomData,
+            }),
+            Value::Variant(v, value) => visitor.visit_enum(EnumDeserializer::<S> {
+                variant: v,
+                value: Some(value),
+                strs: PhantomData,
+            }),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool
+        u8 u16 u32 u64
+        i8 i16 i32 i64
+        f32 f64
+        char str string
+        bytes byte_buf
+        unit
+        unit_struct tuple_struct struct
+        seq tuple
+        map
+        enum
+        identifier
+        ignored_any
+    }
+}
+
+fn deserialize_seq<'v, 'de, S, V>(elems: &'v [ValueCell], visitor: V) -> Result<V::Value>
+where
+    S: VisitStr<'v, 'de>,
+    V: Visitor<'de>,
+{
+    let len = elems.len();
+
+    let mut deserializer = SeqDeserializer::<S> {
+        len,
+        iter: elems.iter().enumerate(),
+        strs: PhantomData,
+    };
+
+    let seq = visitor.visit_seq(&mut deserializer)?;
+
+    if deserializer.iter.len() == 0 {
+        Ok(seq)
+    } else {
+        Err(Error::de_fewer_elements_in_seq(len))
+    }
+}
+
+fn deserialize_map<'v, 'de, S, V>(
+    elems: &'v IndexMap<String, ValueCell>,
+    is_struct: bool,
+    visitor: V,
+) -> Result<V::Value>
+where
+    S: VisitStr<'v, 'de>,
+    V: Visitor<'de>,
+{
+    let len = elems.len();
+
+    let mut deserializer = MapDeserializer::<S> {
+        len,
+        is_struct,
+        iter: elems.iter(),
+        next_value: None,
+        strs: PhantomData,
+    };
+
+    let map = visitor.visit_map(&mut deserializer)?;
+
+    if deserializer.iter.len() == 0 {
+        Ok(map)
+    } else {
+        Err(Error::de_fewer_elements_in_map(len))
+    }
+}
+
+struct SeqDeserializer<'v, S> {
+    len: usize,
+    iter: iter::Enumerate<slice::Iter<'v, ValueCell>>,
+    strs: PhantomData<S>,
+}
+
+impl<'v, 'de, S> serde::de::SeqAccess<'de> for SeqDeserializer<'v, S>
+where
+    S: VisitStr<'v, 'de>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((idx, value)) => seed
+                .deserialize(ValueRef::<S>::new(value))
+                .map(Some)
+                .map_err(|err| err.at_path_item(&PathItem::SequenceIndex(idx))),
+            None => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+struct MapDeserializer<'v, S> {
+    len: usize,
+    is_struct: bool,
+    iter: indexmap::map::Iter<'v, String, ValueCell>,
+    next_value: Option<(&'v String, &'v ValueCell)>,
+    strs: PhantomData<S>,
+}
+
+impl<'v, 'de, S> serde::de::MapAccess<'de> for MapDeserializer<'v, S>
+where
+    S: VisitStr<'v, 'de>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.next_value = Some((key, value));
+                seed.deserialize(MapKeyDeserializer { key: key.clone() })
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.next_value.take() {
+            Some((key, value)) => seed.deserialize(ValueRef::<S>::new(value)).map_err(|err| {
+                let item = if self.is_struct {
+                    PathItem::StructFieldName(key.into())
+                } else {
+                    PathItem::MapKey(key.into())
+                };
+
+                err.at_path_item(&item)
+            }),
+            None => Err(Error::de_map_value_missing()),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+struct EnumDeserializer<'v, S> {
+    variant: &'v str,
+    value: Option<&'v ValueCell>,
+    strs: PhantomData<S>,
+}
+
+impl<'v, 'de, S> serde::de::EnumAccess<'de> for EnumDeserializer<'v, S>
+where
+    S: VisitStr<'v, 'de>,
+{
+    type Error = Error;
+    type Variant = EnumVariantDeserializer<'v, S>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+
+        let deserializer = EnumVariantDeserializer {
+            name: self.variant,
+            value: self.value,
+            strs: PhantomData,
+        };
+
+        Ok((variant, deserializer))
+    }
+}
+
+struct EnumVariantDeserializer<'v, S> {
+    name: &'v str,
+    value: Option<&'v ValueCell>,
+    strs: PhantomData<S>,
+}
+
+impl<S> EnumVariantDeserializer<'_, S> {
+    #[inline]
+    fn at_variant<T>(&self, result: Result<T>) -> Result<T> {
+        result.map_err(|err| err.at_path_item(&PathItem::VariantName(self.name.into())))
+    }
+}
+
+impl<'v, 'de, S> serde::de::VariantAccess<'de> for EnumVariantDeserializer<'v, S>
+where
+    S: VisitStr<'v, 'de>,
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.value {
+            Some(_) => Err(Error::de_expected_unit_variant(Unexpected::NewtypeVariant)),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value {
+            Some(value) => self.at_variant(seed.deserialize(ValueRef::<S>::new(value))),
+            None => Err(Error::de_expected_newtype_variant(Unexpected::UnitVariant)),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value.map(ValueCell::as_value) {
+            Some(Value::Sequence(value)) => {
+                self.at_variant(deserialize_seq::<S, _>(value, visitor))
+            }
+            Some(value) => Err(Error::de_expected_tuple_variant(value.as_unexpected())),
+            None => Err(Error::de_expected_tuple_variant(Unexpected::UnitVariant)),
+        }
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value.map(ValueCell::as_value) {
+            Some(Value::Struct(value)) => {
+                self.at_variant(deserialize_map::<S, _>(value, true, visitor))
+            }
+            Some(value) => Err(Error::de_expected_struct_variant(value.as_unexpected())),
+            None => Err(Error::de_expected_struct_variant(Unexpected::UnitVariant)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::value::from_value;
+
+    #[test]
+    fn error_paths() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        enum Protocol<'a> {
+            Tcp { port: u16 },
+            Unix(&'a str),
+        }
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Config<'a> {
+            #[serde(borrow)]
+            servers: Vec<std::collections::HashMap<String, Protocol<'a>>>,
+        }
+
+        let value = parse("> servers > [0] > ['web'] > `Tcp` > port = -1")
+            .unwrap()
+            .into_value();
+
+        assert_eq!(
+            from_value_ref::<Config>(&value).unwrap_err().to_string(),
+            "invalid value: integer `-1`, expected u16 at path: \
+            > servers > [0] > [\"web\"] > `Tcp` > port"
+        );
+
+        // NOTE: `from_value` reports errors without paths.
+        assert_eq!(
+            from_value::<Config>(value).unwrap_err().to_string(),
+            "invalid value: integer `-1`, expected u16"
+        );
+
+        let value = parse("> servers > [0] > ['local'] > `Unix` = \"/tmp/sock\"")
+            .unwrap()
+            .into_value();
+
+        assert_eq!(
+            from_value_ref::<Config>(&value).unwrap().servers[0]["local"],
+            Protocol::Unix("/tmp/sock")
+        );
+    }
+}
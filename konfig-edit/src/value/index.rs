@@ -7,6 +7,32 @@ static ERR_CANT_INDEX_BY_STR: &str =
     "value is not a map, a structure, or a variant and can't be indexed by `&str`";
 static ERR_NO_ENTRY_FOR_KEY: &str = "no entry found for key";
 
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&ValueCell> {
+        match self {
+            Value::Struct(m) | Value::Map(m) => m.get(key),
+            Value::Variant(name, value) if index_matches_variant(key, name) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut ValueCell> {
+        match self {
+            Value::Struct(m) | Value::Map(m) => m.get_mut(key),
+            Value::Variant(name, value) if index_matches_variant(key, name) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&ValueCell> {
+        self.as_sequence().and_then(|s| s.get(index))
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut ValueCell> {
+        self.as_sequence_mut().and_then(|s| s.get_mut(index))
+    }
+}
+
 impl Index<usize> for Value {
     type Output = ValueCell;
 
@@ -77,7 +103,7 @@ impl IndexMut<String> for Value {
     }
 }
 
-fn index_matches_variant(index: &str, variant_name: &str) -> bool {
+pub(super) fn index_matches_variant(index: &str, variant_name: &str) -> bool {
     index
         .strip_prefix('`')
         .and_then(|index| index.strip_suffix('`'))
@@ -106,6 +132,33 @@ mod tests {
         assert_eq!(value[0]["foo"]["bar".to_string()]["`baz`"], Value::UInt(43));
     }
 
+    #[test]
+    fn get() {
+        let mut value = parse("> [0] > foo > ['bar'] > `baz` = 42").unwrap();
+
+        assert_eq!(
+            value
+                .get_index(0)
+                .and_then(|v| v.get("foo"))
+                .and_then(|v| v.get("bar"))
+                .and_then(|v| v.get("`baz`")),
+            Some(&Value::UInt(42).into())
+        );
+
+        assert_eq!(value.get_index(1), None);
+        assert_eq!(value.get("foo"), None);
+        assert_eq!(value[0].get_index(0), None);
+        assert_eq!(value[0]["foo"]["bar"].get("baz"), None);
+        assert_eq!(value[0]["foo"]["bar"].get("`qux`"), None);
+
+        *value[0]["foo"].get_mut("bar").unwrap() = Value::Null.into();
+        *value.get_index_mut(0).unwrap() = Value::Bool(true).into();
+
+        assert_eq!(value[0], Value::Bool(true));
+        assert_eq!(value.get_mut("foo"), None);
+        assert_eq!(value.get_index_mut(1), None);
+    }
+
     #[test]
     #[should_panic(expected = "index is out of bounds")]
     fn index_out_of_bounds() {
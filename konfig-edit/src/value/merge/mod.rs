@@ -0,0 +1,1394 @@
+mod rules;
+
+use super::{Path, Query, Value, ValueCell};
+use crate::error::{Error, Result};
+use indexmap::IndexMap;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::mem;
+use std::result::Result as StdResult;
+
+pub use self::rules::{MergeStrategy, RuleBasedResolver};
+
+pub trait MergeConflictResolver<E> {
+    fn resolve(&self, path: &Path, current: Value, other: Value) -> StdResult<Value, E>;
+}
+
+impl<F, E> MergeConflictResolver<E> for F
+where
+    F: Fn(&Path, Value, Value) -> StdResult<Value, E>,
+{
+    #[inline]
+    fn resolve(&self, path: &Path, current: Value, other: Value) -> StdResult<Value, E> {
+        (self)(path, current, other)
+    }
+}
+
+pub struct PreferCurrentOnConflict;
+
+impl MergeConflictResolver<Infallible> for PreferCurrentOnConflict {
+    #[inline]
+    fn resolve(&self, _path: &Path, current: Value, _other: Value) -> StdResult<Value, Infallible> {
+        Ok(current)
+    }
+}
+
+pub struct PreferOtherOnConflict;
+
+impl MergeConflictResolver<Infallible> for PreferOtherOnConflict {
+    #[inline]
+    fn resolve(&self, _path: &Path, _current: Value, other: Value) -> StdResult<Value, Infallible> {
+        Ok(other)
+    }
+}
+
+pub struct ErrorOnConflict;
+
+impl MergeConflictResolver<Error> for ErrorOnConflict {
+    #[inline]
+    fn resolve(&self, path: &Path, _current: Value, _other: Value) -> Result<Value> {
+        Err(Error::MergeConflict {
+            path: path.to_string(),
+        })
+    }
+}
+
+// NOTE: `Positional` merges elements with the same index, while the rest of the strategies
+// produce a sequence of different length. `MergeByKey` matches elements that are structures or
+// maps by the value of the given field and merges them, other elements of `other` are appended.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SequenceMergeStrategy {
+    #[default]
+    Positional,
+    Replace,
+    Append,
+    Prepend,
+    Union,
+    MergeByKey(String),
+}
+
+// NOTE: a value in `other` that deletes the corresponding map key, structure field or sequence
+// element from the merge result, following RFC 7396. Markers nested in values that don't have a
+// counterpart in `current` are dropped. Sequences merged by key delete elements as strategic merge
+// patches do, see `PATCH_DIRECTIVE_KEY`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemovalMarker {
+    Null,
+    UnitVariant(String),
+}
+
+// NOTE: an element with the key and this entry set to the removal marker deletes the element with
+// the same key from sequences merged by key, e.g. `{ "name": "b", "$patch": null }`.
+pub const PATCH_DIRECTIVE_KEY: &str = "$patch";
+
+impl RemovalMarker {
+    fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (RemovalMarker::Null, Value::Null) => true,
+            (RemovalMarker::UnitVariant(marker), Value::UnitVariant(name)) => marker == name,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MergeOptions {
+    sequences: SequenceMergeStrategy,
+    sequences_at: Vec<(Query, SequenceMergeStrategy)>,
+    removal_marker: Option<RemovalMarker>,
+}
+
+impl MergeOptions {
+    #[inline]
+    pub fn sequences(mut self, strategy: SequenceMergeStrategy) -> Self {
+        self.sequences = strategy;
+        self
+    }
+
+    // NOTE: overrides the strategy for sequences which paths match `pattern`. If multiple
+    // patterns match, the one added last wins.
+    #[inline]
+    pub fn sequences_at(mut self, pattern: Query, strategy: SequenceMergeStrategy) -> Self {
+        self.sequences_at.push((pattern, strategy));
+        self
+    }
+
+    #[inline]
+    pub fn remove_on(mut self, marker: RemovalMarker) -> Self {
+        self.removal_marker = Some(marker);
+        self
+    }
+
+    pub fn sequence_strategy(&self, path: &Path) -> &SequenceMergeStrategy {
+        self.sequences_at
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches_path(path.items()))
+            .map(|(_, strategy)| strategy)
+            .unwrap_or(&self.sequences)
+    }
+}
+
+impl Value {
+    #[inline]
+    pub fn merge<R, E>(self, conflict_resolver: R, other: impl Into<Value>) -> StdResult<Value, E>
+    where
+        R: MergeConflictResolver<E>,
+    {
+        self.merge_with_options(conflict_resolver, other, &Default::default())
+    }
+
+    pub fn merge_with_options<R, E>(
+        self,
+        conflict_resolver: R,
+        other: impl Into<Value>,
+        options: &MergeOptions,
+    ) -> StdResult<Value, E>
+    where
+        R: MergeConflictResolver<E>,
+    {
+        Merge {
+            path: Default::default(),
+            conflict_resolver,
+            options,
+            _conflict_err_ty: PhantomData,
+        }
+        .merge_values(self, other.into())
+    }
+}
+
+impl Value {
+    pub(crate) fn merge_at<E>(
+        self,
+        path: Path<'static>,
+        conflict_resolver: &dyn MergeConflictResolver<E>,
+        other: Value,
+    ) -> StdResult<Value, E> {
+        Merge {
+            path,
+            conflict_resolver,
+            options: &Default::default(),
+            _conflict_err_ty: PhantomData,
+        }
+        .merge_values(self, other)
+    }
+}
+
+impl<E> MergeConflictResolver<E> for &dyn MergeConflictResolver<E> {
+    #[inline]
+    fn resolve(&self, path: &Path, current: Value, other: Value) -> StdResult<Value, E> {
+        (**self).resolve(path, current, other)
+    }
+}
+
+impl ValueCell {
+    #[inline]
+    pub fn merge<R, E>(self, conflict_resolver: R, other: impl Into<Value>) -> StdResult<Value, E>
+    where
+        R: MergeConflictResolver<E>,
+    {
+        self.into_value().merge(conflict_resolver, other)
+    }
+}
+
+struct Merge<'o, R, E> {
+    path: Path<'static>,
+    conflict_resolver: R,
+    options: &'o MergeOptions,
+    _conflict_err_ty: PhantomData<E>,
+}
+
+impl<R, E> Merge<'_, R, E>
+where
+    R: MergeConflictResolver<E>,
+{
+    // NOTE: the merged cell keeps the lexical info of `current` if the values are merged
+    // recursively or the conflict is resolved in favour of `current`. Otherwise, it gets the
+    // lexical info of `other`, so the source and the span of the resulting value are preserved.
+    fn merge_cells(
+        &mut self,
+        mut current: ValueCell,
+        mut other: ValueCell,
+    ) -> StdResult<ValueCell, E> {
+        let current_info = mem::take(current.lexical_info_mut());
+        let other_info = mem::take(other.lexical_info_mut());
+
+        let is_recursive = match (current.as_value(), other.as_value()) {
+            (Value::Sequence(_), Value::Sequence(_))
+            | (Value::Map(_), Value::Map(_))
+            | (Value::Struct(_), Value::Struct(_)) => true,
+            (Value::Variant(current_name, _), Value::Variant(other_name, _)) => {
+                current_name == other_name
+            }
+            _ => false,
+        };
+
+        let current_copy = (!is_recursive).then(|| current.as_value().clone());
+        let merged = self.merge_values(current.into(), other.into())?;
+
+        let lexical_info = match current_copy {
+            Some(current_copy) if current_copy != merged => other_info,
+            _ => current_info,
+        };
+
+        let mut cell = ValueCell::from(merged);
+
+        *cell.lexical_info_mut() = lexical_info;
+
+        Ok(cell)
+    }
+
+    fn merge_values(&mut self, current: Value, other: Value) -> StdResult<Value, E> {
+        match (current, other) {
+            (Value::Sequence(current), Value::Sequence(other)) => {
+                self.merge_sequences(current, other)
+            }
+            (
+                Value::Variant(current_name, current_value),
+                Value::Variant(other_name, other_value),
+            ) if current_name == other_name => {
+                self.path.push_variant_name(current_name.to_string());
+
+                let resolved = self.merge_cells(current_value, other_value)?;
+
+                self.path.pop();
+
+                Ok(Value::Variant(current_name, resolved))
+            }
+            (Value::Map(current), Value::Map(other)) => self
+                .merge_maps(current, other, |p, k| p.push_map_key(k))
+                .map(Value::Map),
+            (Value::Struct(current), Value::Struct(other)) => self
+                .merge_maps(current, other, |p, n| p.push_struct_field_name(n))
+                .map(Value::Struct),
+            (current, other) => {
+                let other = self.strip_removals(other);
+
+                self.conflict_resolver.resolve(&self.path, current, other)
+            }
+        }
+    }
+
+    fn is_removal(&self, value: &Value) -> bool {
+        self.options
+            .removal_marker
+            .as_ref()
+            .is_some_and(|marker| marker.matches(value))
+    }
+
+    fn is_keyed_removal(&self, value: &Value, key: &str) -> bool {
+        match value {
+            Value::Struct(fields) | Value::Map(fields) => {
+                fields.len() == 2
+                    && fields.contains_key(key)
+                    && fields
+                        .get(PATCH_DIRECTIVE_KEY)
+                        .is_some_and(|directive| self.is_removal(directive))
+            }
+            _ => false,
+        }
+    }
+
+    fn strip_removals(&self, value: Value) -> Value {
+        if self.options.removal_marker.is_none() {
+            return value;
+        }
+
+        let strip = |cell| self.strip_removals_in_cell(cell);
+
+        match value {
+            Value::Sequence(seq) => Value::Sequence(
+                seq.into_iter()
+                    .filter(|cell| !self.is_removal(cell))
+                    .map(strip)
+                    .collect(),
+            ),
+            Value::Map(map) => Value::Map(
+                map.into_iter()
+                    .filter(|(_, cell)| !self.is_removal(cell))
+                    .map(|(key, cell)| (key, strip(cell)))
+                    .collect(),
+            ),
+            Value::Struct(fields) => Value::Struct(
+                fields
+                    .into_iter()
+                    .filter(|(_, cell)| !self.is_removal(cell))
+                    .map(|(name, cell)| (name, strip(cell)))
+                    .collect(),
+            ),
+            Value::Variant(name, cell) => Value::Variant(name, strip(cell)),
+            value => value,
+        }
+    }
+
+    fn strip_removals_in_cell(&self, mut cell: ValueCell) -> ValueCell {
+        let value = mem::replace(cell.as_value_mut(), Value::Null);
+
+        *cell.as_value_mut() = self.strip_removals(value);
+
+        cell
+    }
+
+    fn merge_sequences(
+        &mut self,
+        mut current: Vec<ValueCell>,
+        mut other: Vec<ValueCell>,
+    ) -> StdResult<Value, E> {
+        let strategy = self.options.sequence_strategy(&self.path);
+
+        if !matches!(
+            strategy,
+            SequenceMergeStrategy::Positional | SequenceMergeStrategy::MergeByKey(_)
+        ) {
+            other = other
+                .into_iter()
+                .filter(|cell| !self.is_removal(cell))
+                .map(|cell| self.strip_removals_in_cell(cell))
+                .collect();
+        }
+
+        match strategy {
+            SequenceMergeStrategy::Positional => self.merge_sequences_positionally(current, other),
+            SequenceMergeStrategy::Replace => Ok(Value::Sequence(other)),
+            SequenceMergeStrategy::Append => {
+                current.append(&mut other);
+
+                Ok(Value::Sequence(current))
+            }
+            SequenceMergeStrategy::Prepend => {
+                other.append(&mut current);
+
+                Ok(Value::Sequence(other))
+            }
+            SequenceMergeStrategy::Union => {
+                for other_value in other {
+                    if !current.contains(&other_value) {
+                        current.push(other_value);
+                    }
+                }
+
+                Ok(Value::Sequence(current))
+            }
+            SequenceMergeStrategy::MergeByKey(key) => {
+                let key = key.clone();
+
+                self.merge_sequences_by_key(current, other, &key)
+            }
+        }
+    }
+
+    fn merge_sequences_by_key(
+        &mut self,
+        mut current: Vec<ValueCell>,
+        other: Vec<ValueCell>,
+        key: &str,
+    ) -> StdResult<Value, E> {
+        for other_value in other {
+            let idx = key_of(&other_value, key).and_then(|other_key| {
+                current
+                    .iter()
+                    .position(|current_value| key_of(current_value, key) == Some(other_key))
+            });
+
+            if self.is_keyed_removal(&other_value, key) {
+                if let Some(idx) = idx {
+                    current.remove(idx);
+                }
+
+                continue;
+            }
+
+            if self.is_removal(&other_value) {
+                continue;
+            }
+
+            let Some(idx) = idx else {
+                current.push(self.strip_removals_in_cell(other_value));
+                continue;
+            };
+
+            if current[idx] != other_value {
+                self.path.push_sequence_index(idx);
+
+                let current_value = mem::replace(&mut current[idx], Value::Null.into());
+
+                let resolved = self.merge_cells(current_value, other_value)?;
+
+                self.path.pop();
+
+                let _ = mem::replace(&mut current[idx], resolved);
+            }
+        }
+
+        Ok(Value::Sequence(current))
+    }
+
+    fn merge_sequences_positionally(
+        &mut self,
+        current: Vec<ValueCell>,
+        mut other: Vec<ValueCell>,
+    ) -> StdResult<Value, E> {
+        // NOTE: removed elements are merged as unchanged and deleted afterwards, so the indices
+        // of the rest of the elements in conflict paths are the same as in the source values.
+        let mut removed = vec![];
+
+        for (idx, other_value) in other.iter_mut().enumerate() {
+            if self.is_removal(other_value) {
+                removed.push(idx);
+
+                if let Some(current_value) = current.get(idx) {
+                    *other_value = current_value.clone_with_lexical_info();
+                }
+            } else if idx >= current.len() {
+                let value = mem::replace(other_value, Value::Null.into());
+
+                *other_value = self.strip_removals_in_cell(value);
+            }
+        }
+
+        let mut merged = self.merge_sequences_without_removals(current, other)?;
+
+        for idx in removed.into_iter().rev() {
+            merged.remove(idx);
+        }
+
+        Ok(Value::Sequence(merged))
+    }
+
+    fn merge_sequences_without_removals(
+        &mut self,
+        current: Vec<ValueCell>,
+        other: Vec<ValueCell>,
+    ) -> StdResult<Vec<ValueCell>, E> {
+        let (src, mut dst, is_current_dst) = if current.len() >= other.len() {
+            (other, current, true)
+        } else {
+            (current, other, false)
+        };
+
+        for (i, src_value) in src.into_iter().enumerate() {
+            if dst[i] != src_value {
+                self.path.push_sequence_index(i);
+
+                let dst_value = mem::replace(&mut dst[i], Value::Null.into());
+
+                let (current_value, other_value) = if is_current_dst {
+                    (dst_value, src_value)
+                } else {
+                    (src_value, dst_value)
+                };
+
+                let resolved = self.merge_cells(current_value, other_value)?;
+
+                self.path.pop();
+
+                let _ = mem::replace(&mut dst[i], resolved);
+            }
+        }
+
+        Ok(dst)
+    }
+
+    fn merge_maps(
+        &mut self,
+        mut current: IndexMap<String, ValueCell>,
+        other: IndexMap<String, ValueCell>,
+        push_path: impl Fn(&mut Path, String),
+    ) -> StdResult<IndexMap<String, ValueCell>, E> {
+        for (other_key, other_value) in other {
+            if self.is_removal(&other_value) {
+                current.shift_remove(&other_key);
+                continue;
+            }
+
+            match current.get_mut(&other_key) {
+                Some(current_value) if *current_value == other_value => (),
+                Some(current_value_ref) => {
+                    push_path(&mut self.path, other_key.to_string());
+
+                    let current_value = mem::replace(current_value_ref, Value::Null.into());
+
+                    let resolved = self.merge_cells(current_value, other_value)?;
+
+                    self.path.pop();
+                    let _ = mem::replace(current_value_ref, resolved);
+                }
+                None => {
+                    current.insert(other_key, self.strip_removals_in_cell(other_value));
+                }
+            }
+        }
+
+        Ok(current)
+    }
+}
+
+fn key_of<'v>(value: &'v Value, key: &str) -> Option<&'v Value> {
+    match value {
+        Value::Struct(fields) | Value::Map(fields) => fields.get(key).map(ValueCell::as_value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    macro_rules! assert_merge {
+        ($current:expr, $other:expr, {
+            prefer_current: $expected_prefer_current:expr,
+            prefer_other: $expected_prefer_other:expr,
+            error: $expected_error:expr
+        }) => {
+            let current = crate::parser::parse(indoc!($current)).unwrap();
+            let other = crate::parser::parse(indoc!($other)).unwrap();
+
+            let actual_prefer_current = crate::serializer::serialize(
+                &current
+                    .clone()
+                    .merge(PreferCurrentOnConflict, other.clone())
+                    .unwrap()
+                    .into_cell(),
+                Default::default(),
+            )
+            .unwrap();
+
+            let actual_prefer_other = crate::serializer::serialize(
+                &current
+                    .clone()
+                    .merge(PreferOtherOnConflict, other.clone())
+                    .unwrap()
+                    .into_cell(),
+                Default::default(),
+            )
+            .unwrap();
+
+            let actual_error = current.clone().merge(ErrorOnConflict, other.clone()).err();
+
+            assert_eq!(
+                actual_prefer_current,
+                indoc!($expected_prefer_current),
+                "prefer current"
+            );
+            assert_eq!(
+                actual_prefer_other,
+                indoc!($expected_prefer_other),
+                "prefer other"
+            );
+            assert_eq!(actual_error, $expected_error, "error path");
+        };
+    }
+
+    #[test]
+    fn top_level_conflict() {
+        assert_merge! {
+            "> = 42",
+            "> = 43",
+            {
+                prefer_current: "> = 42",
+                prefer_other: "> = 43",
+                error: Some(Error::MergeConflict { path: ">".into() })
+            }
+        }
+
+        assert_merge! {
+            "> = [1, 2, 3]",
+            "> = 42",
+            {
+                prefer_current: "> = [1, 2, 3]",
+                prefer_other: "> = 42",
+                error: Some(Error::MergeConflict { path: ">".into() })
+            }
+        }
+
+        assert_merge! {
+            "> foo > bar > baz = 42",
+            "> `foo` = 42",
+            {
+                prefer_current: "> foo > bar > baz = 42",
+                prefer_other: "> `foo` = 42",
+                error: Some(Error::MergeConflict { path: ">".into() })
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_identical() {
+        assert_merge! {
+            "
+                > [0] > foo > bar = 123
+
+                > [1] = 42
+
+                > [2] > `baz` = 43\
+            ",
+            "
+                > [0] > foo > bar = 123
+
+                > [1] = 42
+
+                > [2] > `baz` = 43\
+            ",
+            {
+                prefer_current:
+                "
+                    > [0] > foo > bar = 123
+
+                    > [1] = 42
+
+                    > [2] > `baz` = 43\
+                ",
+
+                prefer_other:
+                "
+                    > [0] > foo > bar = 123
+
+                    > [1] = 42
+
+                    > [2] > `baz` = 43\
+                ",
+
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_empty() {
+        assert_merge! {
+            "> = []",
+            "> = []",
+            {
+                prefer_current: "> = []",
+                prefer_other: "> = []",
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_current_longer() {
+        assert_merge! {
+            "> = [1, 2, 3]",
+            "> = []",
+            {
+                prefer_current: "> = [1, 2, 3]",
+                prefer_other: "> = [1, 2, 3]",
+                error: None
+            }
+        }
+
+        assert_merge! {
+            "> = [1, 2, 3]",
+            "> = [1, 2]",
+            {
+                prefer_current: "> = [1, 2, 3]",
+                prefer_other: "> = [1, 2, 3]",
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_other_longer() {
+        assert_merge! {
+            "> = []",
+            "> = [1, 2, 3]",
+            {
+                prefer_current: "> = [1, 2, 3]",
+                prefer_other: "> = [1, 2, 3]",
+                error: None
+            }
+        }
+
+        assert_merge! {
+            "> = [1, 2]",
+            "> = [1, 2, 3]",
+            {
+                prefer_current: "> = [1, 2, 3]",
+                prefer_other: "> = [1, 2, 3]",
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_conflict() {
+        assert_merge! {
+            "> = [1, 4, 3]",
+            "> = [1, 2, 3]",
+            {
+                prefer_current: "> = [1, 4, 3]",
+                prefer_other: "> = [1, 2, 3]",
+                error: Some(Error::MergeConflict { path: "> [1]".into() })
+            }
+        }
+
+        assert_merge! {
+            "
+                > [0] = [123]
+
+                > [1] = 42
+
+                > [2] > `baz` = 43\
+            ",
+            "
+                > [0] = [`qux`]
+
+                > [1] = 43
+
+                > [2] > `baz` > foo = 43\
+            ",
+            {
+                prefer_current:
+                "
+                    > [0] = [123]
+
+                    > [1] = 42
+
+                    > [2] > `baz` = 43\
+                ",
+
+                prefer_other:
+                "
+                    > [0] = [`qux`]
+
+                    > [1] = 43
+
+                    > [2] > `baz` > foo = 43\
+                ",
+
+                error: Some(Error::MergeConflict { path: "> [0] > [0]".into() })
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_strategies() {
+        let merge = |current: &str, other: &str, options: MergeOptions| {
+            let current = crate::parser::parse(current).unwrap().into_value();
+            let other = crate::parser::parse(other).unwrap().into_value();
+
+            let merged = current
+                .merge_with_options(ErrorOnConflict, other, &options)
+                .map(|merged| {
+                    crate::serializer::serialize(&merged.into_cell(), Default::default())
+                });
+
+            merged.map(Result::unwrap)
+        };
+
+        let with = |strategy| MergeOptions::default().sequences(strategy);
+
+        assert_eq!(
+            merge(
+                "> = [1, 2]",
+                "> = [3]",
+                with(SequenceMergeStrategy::Replace)
+            ),
+            Ok("> = [3]".into())
+        );
+
+        assert_eq!(
+            merge("> = [1, 2]", "> = [3]", with(SequenceMergeStrategy::Append)),
+            Ok("> = [1, 2, 3]".into())
+        );
+
+        assert_eq!(
+            merge(
+                "> = [1, 2]",
+                "> = [3]",
+                with(SequenceMergeStrategy::Prepend)
+            ),
+            Ok("> = [3, 1, 2]".into())
+        );
+
+        assert_eq!(
+            merge(
+                "> = [1, 2]",
+                "> = [2, 3, 3]",
+                with(SequenceMergeStrategy::Union)
+            ),
+            Ok("> = [1, 2, 3]".into())
+        );
+
+        assert_eq!(
+            merge("> = [1, 2]", "> = [3]", Default::default()),
+            Err(Error::MergeConflict {
+                path: "> [0]".into()
+            })
+        );
+
+        let current = indoc! {"
+            > servers > [0] > name = \"web\"
+
+            > servers > [0] > port = 80
+
+            > servers > [1] > name = \"db\"
+
+            > servers > [1] > port = 5432
+
+            > servers > [1] > tags = [\"primary\"]
+        "};
+
+        let other = indoc! {"
+            > servers > [0] > name = \"db\"
+
+            > servers > [0] > tags = [\"backup\"]
+
+            > servers > [1] > name = \"cache\"
+
+            > servers > [1] > port = 6379
+        "};
+
+        let options = MergeOptions::default()
+            .sequences(SequenceMergeStrategy::Replace)
+            .sequences_at(
+                "> servers".parse().unwrap(),
+                SequenceMergeStrategy::MergeByKey("name".into()),
+            )
+            .sequences_at(
+                "> servers > [*] > tags".parse().unwrap(),
+                SequenceMergeStrategy::Append,
+            );
+
+        assert_eq!(
+            merge(current, other, options).unwrap(),
+            indoc! {"
+                > servers > [0] > name = \"web\"
+
+                > servers > [0] > port = 80
+
+                > servers > [1] > name = \"db\"
+
+                > servers > [1] > port = 5432
+
+                > servers > [1] > tags = [\"primary\", \"backup\"]
+
+                > servers > [2] > name = \"cache\"
+
+                > servers > [2] > port = 6379"
+            }
+        );
+
+        let options =
+            MergeOptions::default().sequences(SequenceMergeStrategy::MergeByKey("name".into()));
+
+        assert_eq!(
+            merge(
+                current,
+                "> servers > [0] > name = \"db\"\n\n> servers > [0] > port = 5433",
+                options
+            ),
+            Err(Error::MergeConflict {
+                path: "> servers > [1] > port".into()
+            })
+        );
+    }
+
+    #[test]
+    fn variant_different_name() {
+        assert_merge! {
+            "> `foo` = 42",
+            "> `bar` = 42",
+            {
+                prefer_current: "> `foo` = 42",
+                prefer_other: "> `bar` = 42",
+                error: Some(Error::MergeConflict { path: ">".into() })
+            }
+        }
+    }
+
+    #[test]
+    fn variant_different_value() {
+        assert_merge! {
+            "> `foo` = 42",
+            "> `foo` = 43",
+            {
+                prefer_current: "> `foo` = 42",
+                prefer_other: "> `foo` = 43",
+                error: Some(Error::MergeConflict { path: "> `foo`".into() })
+            }
+        }
+    }
+
+    #[test]
+    fn map_current_has_more_keys() {
+        assert_merge! {
+            "
+                > [\"foo\"] = 42
+
+                > [\"bar\"] = 43
+
+                > [\"baz\"] = 44\
+            ",
+            "
+                > [\"foo\"] = 42
+
+                > [\"baz\"] = 44\
+            ",
+            {
+                prefer_current:
+                "
+                    > [\"foo\"] = 42
+
+                    > [\"bar\"] = 43
+
+                    > [\"baz\"] = 44\
+                ",
+                prefer_other:
+                "
+                    > [\"foo\"] = 42
+
+                    > [\"bar\"] = 43
+
+                    > [\"baz\"] = 44\
+                ",
+
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn map_other_has_more_keys() {
+        assert_merge! {
+            "
+                > [\"foo\"] = 42
+
+                > [\"baz\"] = 44\
+            ",
+            "
+                > [\"foo\"] = 42
+
+                > [\"bar\"] = 43
+
+                > [\"baz\"] = 44\
+            ",
+            {
+                prefer_current:
+                "
+                    > [\"foo\"] = 42
+
+                    > [\"baz\"] = 44
+
+                    > [\"bar\"] = 43\
+                ",
+                prefer_other:
+                "
+                    > [\"foo\"] = 42
+
+                    > [\"baz\"] = 44
+
+                    > [\"bar\"] = 43\
+                ",
+
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn map_identical() {
+        assert_merge! {
+            "
+                > [\"foo\"] = 42
+
+                > [\"baz\"] = 44\
+            ",
+            "
+                > [\"foo\"] = 42
+
+                > [\"baz\"] = 44\
+            ",
+            {
+                prefer_current:
+                "
+                    > [\"foo\"] = 42
+
+                    > [\"baz\"] = 44\
+                ",
+                prefer_other:
+                "
+                    > [\"foo\"] = 42
+
+                    > [\"baz\"] = 44\
+                ",
+
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn map_conflict() {
+        assert_merge! {
+            "
+                > [\"foo\"] > [\"bar\"] = 42
+
+                > [\"baz\"] = `foo`\
+            ",
+            "
+                > [\"foo\"] > [\"qux\"] = 43
+
+                > [\"baz\"] = \"hello\"
+
+                > [\"quz\"] = 0\
+            ",
+            {
+                prefer_current:
+                "
+                    > [\"foo\"] > [\"bar\"] = 42
+
+                    > [\"foo\"] > [\"qux\"] = 43
+
+                    > [\"baz\"] = `foo`
+
+                    > [\"quz\"] = 0\
+                ",
+                prefer_other:
+                "
+                    > [\"foo\"] > [\"bar\"] = 42
+
+                    > [\"foo\"] > [\"qux\"] = 43
+
+                    > [\"baz\"] = \"hello\"
+
+                    > [\"quz\"] = 0\
+                ",
+
+                error: Some(Error::MergeConflict { path: "> [\"baz\"]".into() })
+            }
+        }
+    }
+
+    #[test]
+    fn struct_current_has_more_fields() {
+        assert_merge! {
+            "
+                > foo = 42
+
+                > bar = 43
+
+                > baz = 44\
+            ",
+            "
+                > foo = 42
+
+                > baz = 44\
+            ",
+            {
+                prefer_current:
+                "
+                    > foo = 42
+
+                    > bar = 43
+
+                    > baz = 44\
+                ",
+                prefer_other:
+                "
+                    > foo = 42
+
+                    > bar = 43
+
+                    > baz = 44\
+                ",
+
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn struct_other_has_more_fields() {
+        assert_merge! {
+            "
+                > foo = 42
+
+                > baz = 44\
+            ",
+            "
+                > foo = 42
+
+                > bar = 43
+
+                > baz = 44\
+            ",
+            {
+                prefer_current:
+                "
+                    > foo = 42
+
+                    > baz = 44
+
+                    > bar = 43\
+                ",
+                prefer_other:
+                "
+                    > foo = 42
+
+                    > baz = 44
+
+                    > bar = 43\
+                ",
+
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn struct_identical() {
+        assert_merge! {
+            "
+                > foo = 42
+
+                > baz = 44\
+            ",
+            "
+                > foo = 42
+
+                > baz = 44\
+            ",
+            {
+                prefer_current:
+                "
+                    > foo = 42
+
+                    > baz = 44\
+                ",
+                prefer_other:
+                "
+                    > foo = 42
+
+                    > baz = 44\
+                ",
+
+                error: None
+            }
+        }
+    }
+
+    #[test]
+    fn struct_conflict() {
+        assert_merge! {
+            "
+                > foo > bar = 42
+
+                > baz = `foo`\
+            ",
+            "
+                > foo > qux = 43
+
+                > baz = \"hello\"
+
+                > quz = 0\
+            ",
+            {
+                prefer_current:
+                "
+                    > foo > bar = 42
+
+                    > foo > qux = 43
+
+                    > baz = `foo`
+
+                    > quz = 0\
+                ",
+                prefer_other:
+                "
+                    > foo > bar = 42
+
+                    > foo > qux = 43
+
+                    > baz = \"hello\"
+
+                    > quz = 0\
+                ",
+
+                error: Some(Error::MergeConflict { path: "> baz".into() })
+            }
+        }
+    }
+
+    #[test]
+    fn removals() {
+        let merge = |current: &str, other: &str, options: MergeOptions| {
+            let current = crate::parser::parse(current).unwrap().into_value();
+            let other = crate::parser::parse(other).unwrap().into_value();
+
+            let merged = current
+                .merge_with_options(ErrorOnConflict, other, &options)
+                .unwrap();
+
+            crate::serializer::serialize(&merged.into_cell(), Default::default()).unwrap()
+        };
+
+        let current = indoc! {"
+            > features > metrics = true
+
+            > features > tracing > endpoint = \"localhost:4317\"
+
+            > features > tracing > sampling = 0.5
+
+            > hosts = [\"a\", \"b\", \"c\"]
+        "};
+
+        let other = indoc! {"
+            > features > metrics = null
+
+            > features > tracing > sampling = null
+
+            > features > tracing > batch = null
+
+            > features > audit > enabled = true
+
+            > features > audit > sink = null
+
+            > hosts = [\"a\", null]
+        "};
+
+        assert_eq!(
+            merge(
+                current,
+                other,
+                MergeOptions::default().remove_on(RemovalMarker::Null)
+            ),
+            indoc! {"
+                > features > tracing > endpoint = \"localhost:4317\"
+
+                > features > audit > enabled = true
+
+                > hosts = [\"a\", \"c\"]\
+            "}
+        );
+
+        let options = MergeOptions::default()
+            .remove_on(RemovalMarker::UnitVariant("Remove".into()))
+            .sequences(SequenceMergeStrategy::Append);
+
+        assert_eq!(
+            merge(
+                current,
+                indoc! {"
+                    > features > tracing = `Remove`
+
+                    > features > debug = null
+
+                    > hosts = [\"d\", `Remove`]
+                "},
+                options.clone()
+            ),
+            indoc! {"
+                > features > metrics = true
+
+                > features > debug = null
+
+                > hosts = [\"a\", \"b\", \"c\", \"d\"]\
+            "}
+        );
+
+        let options = MergeOptions::default()
+            .remove_on(RemovalMarker::Null)
+            .sequences(SequenceMergeStrategy::MergeByKey("name".into()));
+
+        assert_eq!(
+            merge(
+                indoc! {"
+                    > servers > [0] > name = \"a\"
+
+                    > servers > [1] > name = \"b\"
+
+                    > servers > [1] > port = 80
+
+                    > servers > [2] > name = \"c\"
+                "},
+                indoc! {"
+                    > servers > [0] > ['name'] = \"b\"
+
+                    > servers > [0] > ['$patch'] = null
+
+                    > servers > [1] > ['name'] = \"d\"
+
+                    > servers > [1] > ['$patch'] = null
+
+                    > servers > [2] > name = \"c\"
+
+                    > servers > [2] > port = null
+                "},
+                options
+            ),
+            indoc! {"
+                > servers > [0] > name = \"a\"
+
+                > servers > [1] > name = \"c\"\
+            "}
+        );
+
+        let options = MergeOptions::default()
+            .remove_on(RemovalMarker::UnitVariant("Remove".into()))
+            .sequences(SequenceMergeStrategy::Append);
+
+        // NOTE: markers are dropped from values which don't have a counterpart to remove.
+        assert_eq!(
+            merge(
+                "> qux = 1",
+                "> foo > bar = `Remove`\n\n> foo > baz = 2",
+                options.clone()
+            ),
+            "> qux = 1\n\n> foo > baz = 2"
+        );
+
+        let merged = Value::UInt(1)
+            .merge_with_options(
+                PreferOtherOnConflict,
+                crate::parser::parse("> bar = `Remove`\n\n> baz = 2").unwrap(),
+                &options,
+            )
+            .unwrap();
+
+        assert_eq!(
+            crate::serializer::serialize(&merged.into_cell(), Default::default()).unwrap(),
+            "> baz = 2"
+        );
+    }
+
+    #[test]
+    fn provenance() {
+        let layer = |source: &str, konfig: &str| {
+            let mut value = crate::parser::parse(konfig).unwrap();
+
+            value.set_source(source);
+
+            value
+        };
+
+        const SITE: &str = "> db > pool_size = 50\n\n> db > host = \"db.local\"";
+
+        let merged = layer("defaults", "> db > pool_size = 10\n\n> db > port = 5432")
+            .merge(PreferOtherOnConflict, layer("site", SITE))
+            .unwrap()
+            .merge(PreferCurrentOnConflict, layer("cli", "> db > port = 5433"))
+            .unwrap();
+
+        let provenance = |cell: &ValueCell| {
+            let info = cell.lexical_info();
+
+            (info.source.as_deref().map(String::from), info.span.clone())
+        };
+
+        let (source, span) = provenance(&merged["db"]["pool_size"]);
+
+        assert_eq!(source.as_deref(), Some("site"));
+        assert_eq!(&SITE[span.unwrap()], "50");
+
+        assert_eq!(
+            provenance(&merged["db"]["port"]).0.as_deref(),
+            Some("defaults")
+        );
+        assert_eq!(provenance(&merged["db"]["host"]).0.as_deref(), Some("site"));
+        assert_eq!(provenance(&merged["db"]).0.as_deref(), Some("defaults"));
+    }
+}
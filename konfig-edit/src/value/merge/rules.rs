@@ -0,0 +1,331 @@
+use super::{ErrorOnConflict, MergeConflictResolver};
+use crate::error::{Error, Result};
+use crate::parser::parse;
+use crate::value::{Path, Query, Value};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    PreferCurrent,
+    PreferOther,
+    Error,
+    // NOTE: concatenates strings, other values are a conflict. Sequences are merged according to
+    // `MergeOptions::sequences`, so conflicts are never between sequences.
+    Concat,
+}
+
+impl MergeStrategy {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "PreferCurrent" => MergeStrategy::PreferCurrent,
+            "PreferOther" => MergeStrategy::PreferOther,
+            "Error" => MergeStrategy::Error,
+            "Concat" => MergeStrategy::Concat,
+            _ => return None,
+        })
+    }
+
+    fn resolve(self, path: &Path, current: Value, other: Value) -> Result<Value> {
+        match (self, current, other) {
+            (MergeStrategy::PreferCurrent, current, _) => Ok(current),
+            (MergeStrategy::PreferOther, _, other) => Ok(other),
+            (MergeStrategy::Concat, Value::String(current), Value::String(other)) => {
+                Ok(Value::String(current + &other))
+            }
+            (_, current, other) => ErrorOnConflict.resolve(path, current, other),
+        }
+    }
+}
+
+enum Rule {
+    Builtin(MergeStrategy),
+    Custom(String),
+}
+
+// NOTE: conflicts are resolved by the last rule which pattern matches the conflict path, or by
+// the default strategy if there is no such rule.
+pub struct RuleBasedResolver {
+    rules: Vec<(Query, Rule)>,
+    custom: HashMap<String, Box<dyn MergeConflictResolver<Error>>>,
+    default: MergeStrategy,
+}
+
+impl Default for RuleBasedResolver {
+    fn default() -> Self {
+        Self {
+            rules: vec![],
+            custom: HashMap::new(),
+            default: MergeStrategy::Error,
+        }
+    }
+}
+
+impl RuleBasedResolver {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn default_strategy(mut self, strategy: MergeStrategy) -> Self {
+        self.default = strategy;
+        self
+    }
+
+    #[inline]
+    pub fn rule(mut self, pattern: Query, strategy: MergeStrategy) -> Self {
+        self.rules.push((pattern, Rule::Builtin(strategy)));
+        self
+    }
+
+    // NOTE: registers a custom strategy under the given name, so it can be used by rules,
+    // including the ones loaded from konfig documents.
+    pub fn strategy(
+        mut self,
+        name: impl Into<String>,
+        resolver: impl MergeConflictResolver<Error> + 'static,
+    ) -> Self {
+        self.custom.insert(name.into(), Box::new(resolver));
+        self
+    }
+
+    pub fn custom_rule(self, pattern: Query, name: &str) -> Result<Self> {
+        self.add_custom_rule(pattern, name)
+    }
+
+    // NOTE: rules are a map of patterns to built-in strategies as unit variants or names of
+    // custom strategies as strings, e.g.:
+    //
+    // > ['> secrets > **'] = `PreferOther`
+    //
+    // > ['> banner'] = `Concat`
+    //
+    // > ['> ** > version'] = "semver"
+    pub fn load(self, konfig: &str) -> Result<Self> {
+        self.load_value(&parse(konfig)?.into_value())
+    }
+
+    pub fn load_value(mut self, rules: &Value) -> Result<Self> {
+        let rules = rules.as_map().ok_or_else(|| {
+            Error::custom("merge rules should be a map of patterns to strategies")
+        })?;
+
+        for (pattern, strategy) in rules {
+            let query = Query::parse(pattern)?;
+
+            self = match strategy.as_value() {
+                Value::UnitVariant(name) => match MergeStrategy::from_name(name) {
+                    Some(strategy) => self.rule(query, strategy),
+                    None => {
+                        return Err(Error::custom(format!(
+                            "unknown merge strategy `{name}` for pattern `{pattern}`"
+                        )))
+                    }
+                },
+                Value::String(name) => self.add_custom_rule(query, name)?,
+                _ => {
+                    return Err(Error::custom(format!(
+                        "merge strategy for pattern `{pattern}` should be a unit variant or a \
+                        string"
+                    )))
+                }
+            };
+        }
+
+        Ok(self)
+    }
+
+    fn add_custom_rule(mut self, pattern: Query, name: &str) -> Result<Self> {
+        if !self.custom.contains_key(name) {
+            return Err(Error::custom(format!(
+                "unknown custom merge strategy `{name}`"
+            )));
+        }
+
+        self.rules.push((pattern, Rule::Custom(name.into())));
+
+        Ok(self)
+    }
+}
+
+impl MergeConflictResolver<Error> for RuleBasedResolver {
+    fn resolve(&self, path: &Path, current: Value, other: Value) -> Result<Value> {
+        let rule = self
+            .rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches_path(path.items()))
+            .map(|(_, rule)| rule);
+
+        match rule {
+            Some(Rule::Builtin(strategy)) => strategy.resolve(path, current, other),
+            Some(Rule::Custom(name)) => self.custom[name].resolve(path, current, other),
+            None => self.default.resolve(path, current, other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const CURRENT: &str = indoc! {"
+        > secrets > db > password = \"old\"
+
+        > schema_version = 1
+
+        > banner = \"Hello\"
+
+        > tags = [\"a\"]
+
+        > name = \"current\"
+    "};
+
+    const OTHER: &str = indoc! {"
+        > secrets > db > password = \"new\"
+
+        > schema_version = 2
+
+        > banner = \", world!\"
+
+        > tags = [\"b\"]
+
+        > name = \"other\"
+    "};
+
+    fn merge(resolver: &RuleBasedResolver, other: &str) -> Result<Value> {
+        let current = parse(CURRENT).unwrap().into_value();
+        let other = parse(other).unwrap().into_value();
+
+        current.merge(resolver as &dyn MergeConflictResolver<Error>, other)
+    }
+
+    #[test]
+    fn rules() {
+        let resolver = RuleBasedResolver::new()
+            .default_strategy(MergeStrategy::PreferCurrent)
+            .rule(
+                "> secrets > **".parse().unwrap(),
+                MergeStrategy::PreferOther,
+            )
+            .rule("> schema_version".parse().unwrap(), MergeStrategy::Error)
+            .rule("> banner".parse().unwrap(), MergeStrategy::Concat)
+            .rule("> tags > [*]".parse().unwrap(), MergeStrategy::Concat);
+
+        assert_eq!(
+            merge(&resolver, OTHER),
+            Err(Error::MergeConflict {
+                path: "> schema_version".into()
+            })
+        );
+
+        let merged = merge(&resolver, &OTHER.replace("= 2", "= 1")).unwrap();
+
+        assert_eq!(
+            merged["secrets"]["db"]["password"],
+            Value::String("new".into())
+        );
+        assert_eq!(merged["banner"], Value::String("Hello, world!".into()));
+        assert_eq!(merged["name"], Value::String("current".into()));
+
+        // NOTE: sequences are merged element-wise, so conflicts are between strings.
+        assert_eq!(merged["tags"][0], Value::String("ab".into()));
+
+        assert_eq!(
+            merge(
+                &resolver.rule("> tags > [0]".parse().unwrap(), MergeStrategy::Error),
+                &OTHER.replace("= 2", "= 1")
+            )
+            .unwrap_err(),
+            Error::MergeConflict {
+                path: "> tags > [0]".into()
+            }
+        );
+    }
+
+    #[test]
+    fn custom_strategies() {
+        let resolver = RuleBasedResolver::new()
+            .strategy("max", |path: &Path, current: Value, other: Value| {
+                match (current.as_u64(), other.as_u64()) {
+                    (Some(current), Some(other)) => Ok(Value::UInt(current.max(other))),
+                    _ => ErrorOnConflict.resolve(path, current, other),
+                }
+            })
+            .custom_rule("> ** > schema_version".parse().unwrap(), "max")
+            .unwrap()
+            .default_strategy(MergeStrategy::PreferOther);
+
+        let merged = merge(&resolver, OTHER).unwrap();
+
+        assert_eq!(merged["schema_version"], Value::UInt(2));
+        assert_eq!(merged["name"], Value::String("other".into()));
+
+        assert_eq!(
+            RuleBasedResolver::new()
+                .custom_rule("> foo".parse().unwrap(), "max")
+                .err()
+                .unwrap()
+                .to_string(),
+            "unknown custom merge strategy `max`"
+        );
+    }
+
+    #[test]
+    fn load_rules() {
+        let resolver = RuleBasedResolver::new()
+            .strategy("keep", |_: &Path, current: Value, _: Value| Ok(current))
+            .load(indoc! {"
+                > ['> **'] = \"keep\"
+
+                > ['> secrets > **'] = `PreferOther`
+
+                > ['> banner'] = `Concat`
+
+                > ['> schema_version'] = `Error`
+            "})
+            .unwrap();
+
+        assert_eq!(
+            merge(&resolver, OTHER),
+            Err(Error::MergeConflict {
+                path: "> schema_version".into()
+            })
+        );
+
+        let merged = merge(&resolver, &OTHER.replace("= 2", "= 1")).unwrap();
+
+        assert_eq!(
+            merged["secrets"]["db"]["password"],
+            Value::String("new".into())
+        );
+        assert_eq!(merged["banner"], Value::String("Hello, world!".into()));
+        assert_eq!(merged["name"], Value::String("current".into()));
+
+        let err = |rules: &str| {
+            RuleBasedResolver::new()
+                .load(rules)
+                .err()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            err("> ['> foo'] = `Nope`"),
+            "unknown merge strategy `Nope` for pattern `> foo`"
+        );
+
+        assert_eq!(
+            err("> ['> foo'] = 42"),
+            "merge strategy for pattern `> foo` should be a unit variant or a string"
+        );
+
+        assert_eq!(
+            err("> foo = `Error`"),
+            "merge rules should be a map of patterns to strategies"
+        );
+
+        assert!(err("> ['foo'] = `Error`").contains("expected `>`"));
+    }
+}
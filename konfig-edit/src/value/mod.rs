@@ -1,6 +1,11 @@
+mod assign;
 mod conv;
+mod entry;
+mod flatten;
 mod index;
 mod path;
+mod query;
+mod visit;
 
 #[cfg(feature = "serde")]
 mod to_value;
@@ -8,16 +13,25 @@ mod to_value;
 #[cfg(feature = "serde")]
 mod from_value;
 
+#[cfg(feature = "serde")]
+mod from_value_ref;
+
 #[cfg(feature = "serde")]
 mod serde;
 
+pub mod diff;
 pub mod merge;
+pub mod patch;
 pub(super) mod value_cell;
 
 use indexmap::IndexMap;
 
+pub use self::entry::{Entry, OccupiedEntry, VacantEntry};
+pub use self::flatten::{FlattenOptions, PrimitiveArrays};
 pub use self::path::{Path, PathItem};
+pub use self::query::Query;
 pub use self::value_cell::ValueCell;
+pub use self::visit::{Leaves, Visitor, VisitorMut};
 
 #[cfg(feature = "serde")]
 pub use self::to_value::{to_value, Serializer};
@@ -25,6 +39,9 @@ pub use self::to_value::{to_value, Serializer};
 #[cfg(feature = "serde")]
 pub use self::from_value::from_value;
 
+#[cfg(feature = "serde")]
+pub(crate) use self::from_value_ref::from_value_ref;
+
 #[derive(Clone, Debug, PartialEq, Default)]
 pub enum Value {
     #[default]
@@ -0,0 +1,834 @@
+use super::{Path, PathItem, Value, ValueCell};
+use crate::error::{Error, Result};
+use indexmap::IndexMap;
+use std::mem;
+
+// NOTE: RFC 6902 operation. Paths are JSON pointers, as they are resolved against the target at
+// the time the operation is applied.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "op", rename_all = "lowercase")
+)]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct JsonPatch(pub Vec<PatchOperation>);
+
+impl JsonPatch {
+    // NOTE: same layout as in JSON, e.g. `> [0] > op = "add"`, `> [0] > path = "/foo"`, etc.
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let seq = value
+            .as_sequence()
+            .ok_or_else(|| Error::custom("JSON patch should be a sequence of operations"))?;
+
+        seq.iter()
+            .enumerate()
+            .map(|(index, op)| {
+                PatchOperation::from_value(op)
+                    .map_err(|reason| Error::PatchFailed { index, reason })
+            })
+            .collect::<Result<_>>()
+            .map(JsonPatch)
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Sequence(self.0.iter().map(|op| op.to_value().into()).collect())
+    }
+}
+
+impl PatchOperation {
+    fn from_value(value: &Value) -> std::result::Result<Self, String> {
+        let fields = match value {
+            Value::Struct(fields) | Value::Map(fields) => fields,
+            _ => return Err("operation should be a structure or a map".into()),
+        };
+
+        let string = |name: &str| match fields.get(name).map(|v| v.as_value()) {
+            Some(Value::String(s)) => Ok(s.clone()),
+            Some(_) => Err(format!("`{name}` should be a string")),
+            None => Err(format!("`{name}` is missing")),
+        };
+
+        let value = || {
+            fields
+                .get("value")
+                .map(|v| v.as_value().clone())
+                .ok_or_else(|| "`value` is missing".to_string())
+        };
+
+        Ok(match string("op")?.as_str() {
+            "add" => PatchOperation::Add {
+                path: string("path")?,
+                value: value()?,
+            },
+            "remove" => PatchOperation::Remove {
+                path: string("path")?,
+            },
+            "replace" => PatchOperation::Replace {
+                path: string("path")?,
+                value: value()?,
+            },
+            "move" => PatchOperation::Move {
+                from: string("from")?,
+                path: string("path")?,
+            },
+            "copy" => PatchOperation::Copy {
+                from: string("from")?,
+                path: string("path")?,
+            },
+            "test" => PatchOperation::Test {
+                path: string("path")?,
+                value: value()?,
+            },
+            op => return Err(format!("unknown operation `{op}`")),
+        })
+    }
+
+    fn to_value(&self) -> Value {
+        let (op, path, from, value) = match self {
+            PatchOperation::Add { path, value } => ("add", path, None, Some(value)),
+            PatchOperation::Remove { path } => ("remove", path, None, None),
+            PatchOperation::Replace { path, value } => ("replace", path, None, Some(value)),
+            PatchOperation::Move { from, path } => ("move", path, Some(from), None),
+            PatchOperation::Copy { from, path } => ("copy", path, Some(from), None),
+            PatchOperation::Test { path, value } => ("test", path, None, Some(value)),
+        };
+
+        let mut fields = IndexMap::new();
+
+        fields.insert("op".into(), Value::String(op.into()).into());
+        fields.insert("path".into(), Value::String(path.clone()).into());
+
+        if let Some(from) = from {
+            fields.insert("from".into(), Value::String(from.clone()).into());
+        }
+
+        if let Some(value) = value {
+            fields.insert("value".into(), value.clone().into());
+        }
+
+        Value::Struct(fields)
+    }
+}
+
+impl Path<'_> {
+    pub fn to_json_pointer(&self) -> String {
+        let mut pointer = String::new();
+
+        for item in self.items() {
+            pointer.push('/');
+
+            match item {
+                PathItem::SequenceIndex(idx) => pointer.push_str(&idx.to_string()),
+                PathItem::MapKey(name)
+                | PathItem::StructFieldName(name)
+                | PathItem::VariantName(name) => {
+                    pointer.push_str(&name.replace('~', "~0").replace('/', "~1"))
+                }
+            }
+        }
+
+        pointer
+    }
+
+    // NOTE: JSON pointer tokens are untyped, so they are resolved against the target to tell
+    // structure fields, map keys, variants and sequence indices apart. The last token doesn't need
+    // to exist, in which case its type is inferred from the parent. `-` points past the end of
+    // a sequence.
+    pub fn from_json_pointer(pointer: &str, target: &Value) -> Result<Path<'static>> {
+        resolve_pointer(pointer, target).map_err(Error::custom)
+    }
+}
+
+fn resolve_pointer(pointer: &str, target: &Value) -> std::result::Result<Path<'static>, String> {
+    let mut path = Path::default();
+
+    if pointer.is_empty() {
+        return Ok(path);
+    }
+
+    let tokens = pointer
+        .strip_prefix('/')
+        .ok_or_else(|| format!("JSON pointer `{pointer}` should start with `/`"))?
+        .split('/');
+
+    let mut current = Some(target);
+
+    for token in tokens {
+        let token = unescape_token(token)
+            .ok_or_else(|| format!("JSON pointer `{pointer}` has invalid escape sequence"))?;
+
+        let Some(value) = current else {
+            return Err(format!("`{path}` doesn't exist"));
+        };
+
+        let (item, next) = match value {
+            Value::Sequence(seq) if token == "-" => (PathItem::SequenceIndex(seq.len()), None),
+            Value::Sequence(seq) => {
+                let idx = parse_index(&token)
+                    .ok_or_else(|| format!("`{token}` is not a valid index of `{path}`"))?;
+
+                (PathItem::SequenceIndex(idx), seq.get(idx))
+            }
+            Value::Map(map) => {
+                let next = map.get(&token);
+
+                (PathItem::MapKey(token.into()), next)
+            }
+            Value::Struct(fields) => {
+                let next = fields.get(&token);
+
+                (PathItem::StructFieldName(token.into()), next)
+            }
+            Value::Variant(name, value) => {
+                let next = (*name == token).then_some(value);
+
+                (PathItem::VariantName(token.into()), next)
+            }
+            _ => return Err(format!("`{path}` is not a container")),
+        };
+
+        path.push(item);
+        current = next.map(ValueCell::as_value);
+    }
+
+    Ok(path)
+}
+
+fn unescape_token(token: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next()? {
+                '0' => unescaped.push('~'),
+                '1' => unescaped.push('/'),
+                _ => return None,
+            },
+            c => unescaped.push(c),
+        }
+    }
+
+    Some(unescaped)
+}
+
+fn parse_index(token: &str) -> Option<usize> {
+    let leading_zero = token.len() > 1 && token.starts_with('0');
+
+    if leading_zero || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    token.parse().ok()
+}
+
+impl Value {
+    // NOTE: the patch is applied atomically: if any of the operations fails, the value is left
+    // intact.
+    pub fn apply_json_patch(&mut self, patch: &JsonPatch) -> Result<()> {
+        let backup = self.clone_with_lexical_info();
+
+        for (index, op) in patch.0.iter().enumerate() {
+            if let Err(reason) = apply_operation(self, op) {
+                *self = backup;
+
+                return Err(Error::PatchFailed { index, reason });
+            }
+        }
+
+        Ok(())
+    }
+
+    // NOTE: RFC 7396 treats structures, maps and variants as objects. Objects created by the patch
+    // have the same type as the patch object. Assigning a different variant replaces the variant
+    // instead of adding an entry to it.
+    pub fn apply_merge_patch(&mut self, patch: &Value) {
+        match patch {
+            Value::Variant(name, patch) => match self {
+                Value::Variant(current_name, value) if current_name == name => {
+                    value.apply_merge_patch(patch);
+                }
+                _ => {
+                    let mut value = Value::Null;
+
+                    value.apply_merge_patch(patch);
+
+                    *self = Value::Variant(name.clone(), value.into());
+                }
+            },
+            Value::Map(patch_entries) | Value::Struct(patch_entries) => {
+                if let Value::Map(entries) | Value::Struct(entries) = self {
+                    merge_patch_entries(entries, patch_entries);
+
+                    return;
+                }
+
+                let mut entries = match mem::take(self) {
+                    Value::Variant(name, value) if is_variant_patch(&name, patch_entries) => {
+                        [(name, value)].into()
+                    }
+                    _ => IndexMap::new(),
+                };
+
+                let is_variant = !entries.is_empty();
+
+                merge_patch_entries(&mut entries, patch_entries);
+
+                *self = match entries.pop() {
+                    Some((name, value)) if is_variant => Value::Variant(name, value),
+                    Some(entry) => {
+                        entries.insert(entry.0, entry.1);
+
+                        if patch.is_struct() {
+                            Value::Struct(entries)
+                        } else {
+                            Value::Map(entries)
+                        }
+                    }
+                    None if patch.is_struct() => Value::Struct(entries),
+                    None => Value::Map(entries),
+                };
+            }
+            _ => *self = patch.clone(),
+        }
+    }
+}
+
+impl ValueCell {
+    #[inline]
+    pub fn apply_json_patch(&mut self, patch: &JsonPatch) -> Result<()> {
+        self.as_value_mut().apply_json_patch(patch)
+    }
+
+    #[inline]
+    pub fn apply_merge_patch(&mut self, patch: &Value) {
+        self.as_value_mut().apply_merge_patch(patch)
+    }
+}
+
+// NOTE: the variant survives an object patch only if the patch modifies the variant value.
+fn is_variant_patch(name: &str, patch_entries: &IndexMap<String, ValueCell>) -> bool {
+    patch_entries
+        .iter()
+        .all(|(key, patch)| key == name && !patch.is_null())
+}
+
+fn merge_patch_entries(
+    entries: &mut IndexMap<String, ValueCell>,
+    patch: &IndexMap<String, ValueCell>,
+) {
+    for (key, patch) in patch {
+        if patch.is_null() {
+            entries.shift_remove(key);
+            continue;
+        }
+
+        match entries.get_mut(key) {
+            Some(value) => value.apply_merge_patch(patch),
+            None => {
+                let mut value = Value::Null;
+
+                value.apply_merge_patch(patch);
+                entries.insert(key.clone(), value.into());
+            }
+        }
+    }
+}
+
+fn apply_operation(target: &mut Value, op: &PatchOperation) -> std::result::Result<(), String> {
+    match op {
+        PatchOperation::Add { path, value } => {
+            let path = resolve_pointer(path, target)?;
+
+            insert(target, &path, value.clone().into())
+        }
+        PatchOperation::Remove { path } => {
+            let path = resolve_pointer(path, target)?;
+
+            remove(target, &path).map(drop)
+        }
+        PatchOperation::Replace { path, value } => {
+            let path = resolve_pointer(path, target)?;
+
+            *get_mut(target, &path)? = value.clone();
+
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if from == path {
+                return Ok(());
+            }
+
+            if path.starts_with(&format!("{from}/")) {
+                return Err(format!("can't move `{from}` into its own child `{path}`"));
+            }
+
+            let from = resolve_pointer(from, target)?;
+            let value = remove(target, &from)?;
+            let path = resolve_pointer(path, target)?;
+
+            insert(target, &path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let from = resolve_pointer(from, target)?;
+            let value = get_mut(target, &from)?.clone_with_lexical_info();
+            let path = resolve_pointer(path, target)?;
+
+            insert(target, &path, value.into())
+        }
+        PatchOperation::Test { path, value } => {
+            let path = resolve_pointer(path, target)?;
+            let actual = get_mut(target, &path)?;
+
+            if actual.json_eq(value) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "value at `{path}` is not equal to the expected value"
+                ))
+            }
+        }
+    }
+}
+
+fn get_mut<'v>(target: &'v mut Value, path: &Path) -> std::result::Result<&'v mut Value, String> {
+    if path.items().is_empty() {
+        return Ok(target);
+    }
+
+    target
+        .get_path_mut(path.items())
+        .map(ValueCell::as_value_mut)
+        .ok_or_else(|| format!("`{path}` doesn't exist"))
+}
+
+fn split_last<'p>(
+    path: &'p Path,
+) -> std::result::Result<(&'p [PathItem<'p>], &'p PathItem<'p>), String> {
+    path.items()
+        .split_last()
+        .map(|(last, parent)| (parent, last))
+        .ok_or_else(|| "the root can't be removed".to_string())
+}
+
+fn insert(target: &mut Value, path: &Path, value: ValueCell) -> std::result::Result<(), String> {
+    if path.items().is_empty() {
+        *target = value.into_value();
+
+        return Ok(());
+    }
+
+    let (parent_items, last) = split_last(path)?;
+    let parent_path = parent_items.iter().cloned().collect::<Path>();
+
+    match (get_mut(target, &parent_path)?, last) {
+        (Value::Sequence(seq), PathItem::SequenceIndex(idx)) if *idx <= seq.len() => {
+            seq.insert(*idx, value)
+        }
+        (Value::Map(map), PathItem::MapKey(key)) => {
+            map.insert(key.to_string(), value);
+        }
+        (Value::Struct(fields), PathItem::StructFieldName(name)) => {
+            fields.insert(name.to_string(), value);
+        }
+        (Value::Variant(name, current), PathItem::VariantName(new_name)) => {
+            *name = new_name.to_string();
+            *current = value;
+        }
+        _ => return Err(format!("can't add a value at `{path}`")),
+    }
+
+    Ok(())
+}
+
+fn remove(target: &mut Value, path: &Path) -> std::result::Result<ValueCell, String> {
+    let (parent_items, last) = split_last(path)?;
+    let parent_path = parent_items.iter().cloned().collect::<Path>();
+
+    let removed = match (get_mut(target, &parent_path)?, last) {
+        (Value::Sequence(seq), PathItem::SequenceIndex(idx)) if *idx < seq.len() => {
+            Some(seq.remove(*idx))
+        }
+        (Value::Map(map), PathItem::MapKey(key)) => map.shift_remove(key.as_ref()),
+        (Value::Struct(fields), PathItem::StructFieldName(name)) => {
+            fields.shift_remove(name.as_ref())
+        }
+        (Value::Variant(..), _) => return Err(format!("can't remove variant value at `{path}`")),
+        _ => None,
+    };
+
+    removed.ok_or_else(|| format!("`{path}` doesn't exist"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        > server > host = \"localhost\"
+
+        Server port.
+
+        > server > port = 80
+
+        > env > ['a/b~c'] = \"x\"
+
+        > mode > `Fast` > level = 1
+
+        > list = [1, 2, 3]
+    "};
+
+    fn op(konfig: &str) -> JsonPatch {
+        JsonPatch::from_value(&parse(konfig).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn json_pointers() {
+        let value = parse(INPUT).unwrap();
+
+        let path = |pointer| {
+            Path::from_json_pointer(pointer, &value)
+                .map(|path| path.to_string())
+                .map_err(|err| err.to_string())
+        };
+
+        assert_eq!(path(""), Ok(">".into()));
+        assert_eq!(path("/server/port"), Ok("> server > port".into()));
+        assert_eq!(path("/env/a~1b~0c"), Ok("> env > [\"a/b~c\"]".into()));
+        assert_eq!(path("/env/new"), Ok("> env > [\"new\"]".into()));
+        assert_eq!(
+            path("/mode/Fast/level"),
+            Ok("> mode > `Fast` > level".into())
+        );
+        assert_eq!(path("/mode/Slow"), Ok("> mode > `Slow`".into()));
+        assert_eq!(path("/list/-"), Ok("> list > [3]".into()));
+        assert_eq!(
+            path("/list/01"),
+            Err("`01` is not a valid index of `> list`".into())
+        );
+        assert_eq!(
+            path("/mode/Slow/level"),
+            Err("`> mode > `Slow`` doesn't exist".into())
+        );
+        assert_eq!(
+            path("/server/port/x"),
+            Err("`> server > port` is not a container".into())
+        );
+        assert_eq!(
+            path("/env/~2"),
+            Err("JSON pointer `/env/~2` has invalid escape sequence".into())
+        );
+        assert_eq!(
+            path("server"),
+            Err("JSON pointer `server` should start with `/`".into())
+        );
+
+        for pointer in [
+            "",
+            "/server/port",
+            "/env/a~1b~0c",
+            "/mode/Fast/level",
+            "/list/2",
+        ] {
+            assert_eq!(
+                Path::from_json_pointer(pointer, &value)
+                    .unwrap()
+                    .to_json_pointer(),
+                pointer
+            );
+        }
+    }
+
+    #[test]
+    fn json_patch() {
+        let mut value = parse(INPUT).unwrap();
+
+        let patch = op(indoc! {"
+            > [0] > op = \"test\"
+
+            > [0] > path = \"/server/port\"
+
+            > [0] > value = 80.0
+
+            > [1] > op = \"replace\"
+
+            > [1] > path = \"/server/port\"
+
+            > [1] > value = 8080
+
+            > [2] > op = \"add\"
+
+            > [2] > path = \"/list/1\"
+
+            > [2] > value = 42
+
+            > [3] > op = \"remove\"
+
+            > [3] > path = \"/server/host\"
+
+            > [4] > op = \"move\"
+
+            > [4] > from = \"/env/a~1b~0c\"
+
+            > [4] > path = \"/env/moved\"
+
+            > [5] > op = \"copy\"
+
+            > [5] > from = \"/mode/Fast\"
+
+            > [5] > path = \"/server/mode\"
+
+            > [6] > op = \"add\"
+
+            > [6] > path = \"/mode/Slow\"
+
+            > [6] > value = true
+        "});
+
+        value.apply_json_patch(&patch).unwrap();
+
+        let expected = parse(indoc! {"
+            > server > port = 8080
+
+            > server > mode > level = 1
+
+            > env > ['moved'] = \"x\"
+
+            > mode > `Slow` = true
+
+            > list = [1, 42, 2, 3]
+        "})
+        .unwrap();
+
+        assert_eq!(value, expected);
+        assert_eq!(
+            value["server"]["port"].lexical_info().docs_before,
+            "Server port.\n\n"
+        );
+    }
+
+    #[test]
+    fn json_patch_is_atomic() {
+        let mut value = parse(INPUT).unwrap();
+
+        let patch = JsonPatch(vec![
+            PatchOperation::Remove {
+                path: "/server/port".into(),
+            },
+            PatchOperation::Test {
+                path: "/server/host".into(),
+                value: Value::String("example.com".into()),
+            },
+        ]);
+
+        assert_eq!(
+            value.apply_json_patch(&patch).unwrap_err().to_string(),
+            "patch operation #1 failed: value at `> server > host` is not equal to the expected value"
+        );
+
+        assert_eq!(value, parse(INPUT).unwrap());
+        assert_eq!(
+            value["server"]["port"].lexical_info().docs_before,
+            "Server port.\n\n"
+        );
+
+        let err = |op| {
+            let mut value = parse(INPUT).unwrap();
+
+            value
+                .apply_json_patch(&JsonPatch(vec![op]))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            err(PatchOperation::Remove {
+                path: "/server/tls".into()
+            }),
+            "patch operation #0 failed: `> server > tls` doesn't exist"
+        );
+
+        assert_eq!(
+            err(PatchOperation::Add {
+                path: "/list/4".into(),
+                value: Value::Null
+            }),
+            "patch operation #0 failed: can't add a value at `> list > [4]`"
+        );
+
+        assert_eq!(
+            err(PatchOperation::Move {
+                from: "/server".into(),
+                path: "/server/inner".into()
+            }),
+            "patch operation #0 failed: can't move `/server` into its own child `/server/inner`"
+        );
+
+        assert_eq!(
+            err(PatchOperation::Remove {
+                path: "/mode/Fast".into()
+            }),
+            "patch operation #0 failed: can't remove variant value at `> mode > `Fast``"
+        );
+
+        assert_eq!(
+            JsonPatch::from_value(&parse("> [0] > op = \"nope\"").unwrap())
+                .unwrap_err()
+                .to_string(),
+            "patch operation #0 failed: unknown operation `nope`"
+        );
+    }
+
+    #[test]
+    fn json_patch_to_value() {
+        let patch = JsonPatch(vec![
+            PatchOperation::Add {
+                path: "/a".into(),
+                value: Value::UInt(1),
+            },
+            PatchOperation::Move {
+                from: "/a".into(),
+                path: "/b".into(),
+            },
+        ]);
+
+        assert_eq!(JsonPatch::from_value(&patch.to_value()).unwrap(), patch);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_patch_serde() {
+        let json = serde_json::json!([
+            { "op": "add", "path": "/a", "value": { "b": [1, 2] } },
+            { "op": "remove", "path": "/c" },
+            { "op": "copy", "from": "/a", "path": "/d" },
+        ]);
+
+        let patch: JsonPatch = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(
+            patch.0[0],
+            PatchOperation::Add {
+                path: "/a".into(),
+                value: Value::Map(
+                    [(
+                        "b".into(),
+                        Value::Sequence(vec![Value::UInt(1).into(), Value::UInt(2).into()]).into()
+                    )]
+                    .into()
+                ),
+            }
+        );
+
+        assert_eq!(serde_json::to_value(&patch).unwrap(), json);
+    }
+
+    #[test]
+    fn merge_patch() {
+        let mut value = parse(INPUT).unwrap();
+
+        let patch = parse(indoc! {"
+            > server > host = null
+
+            > server > port = 8080
+
+            > server > tls > enabled = true
+
+            > env > ['b'] = \"y\"
+
+            > mode > `Fast` > level = 2
+
+            > list = [3]
+        "})
+        .unwrap();
+
+        value.apply_merge_patch(&patch);
+
+        let expected = parse(indoc! {"
+            > server > port = 8080
+
+            > server > tls > enabled = true
+
+            > env > ['a/b~c'] = \"x\"
+
+            > env > ['b'] = \"y\"
+
+            > mode > `Fast` > level = 2
+
+            > list = [3]
+        "})
+        .unwrap();
+
+        assert_eq!(value, expected);
+        assert_eq!(
+            value["server"]["port"].lexical_info().docs_before,
+            "Server port.\n\n"
+        );
+
+        value.apply_merge_patch(&parse("> mode > `Slow` > level = null").unwrap());
+
+        assert_eq!(
+            value["mode"],
+            Value::Variant("Slow".into(), Value::Struct(IndexMap::new()).into())
+        );
+
+        value.apply_merge_patch(&parse("> mode > ['Slow'] > level = 1").unwrap());
+
+        assert_eq!(value["mode"]["`Slow`"]["level"], Value::UInt(1));
+
+        value.apply_merge_patch(&parse("> mode > ['Fast'] = 1").unwrap());
+
+        assert!(value["mode"].is_map());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn merge_patch_rfc_examples() {
+        macro_rules! ok {
+            ($target:tt + $patch:tt => $expected:tt) => {{
+                let mut target: Value = serde_json::from_value(serde_json::json!($target)).unwrap();
+                let patch: Value = serde_json::from_value(serde_json::json!($patch)).unwrap();
+
+                target.apply_merge_patch(&patch);
+
+                assert_eq!(
+                    serde_json::to_value(&target).unwrap(),
+                    serde_json::json!($expected)
+                );
+            }};
+        }
+
+        ok! { {"a": "b"} + {"a": "c"} => {"a": "c"} }
+        ok! { {"a": "b"} + {"b": "c"} => {"a": "b", "b": "c"} }
+        ok! { {"a": "b"} + {"a": null} => {} }
+        ok! { {"a": "b", "b": "c"} + {"a": null} => {"b": "c"} }
+        ok! { {"a": ["b"]} + {"a": "c"} => {"a": "c"} }
+        ok! { {"a": "c"} + {"a": ["b"]} => {"a": ["b"]} }
+        ok! { {"a": {"b": "c"}} + {"a": {"b": "d", "c": null}} => {"a": {"b": "d"}} }
+        ok! { {"a": [{"b": "c"}]} + {"a": [1]} => {"a": [1]} }
+        ok! { ["a", "b"] + ["c", "d"] => ["c", "d"] }
+        ok! { {"a": "b"} + ["c"] => ["c"] }
+        ok! { {"a": "foo"} + null => null }
+        ok! { {"a": "foo"} + "bar" => "bar" }
+        ok! { {"e": null} + {"a": 1} => {"e": null, "a": 1} }
+        ok! { [1, 2] + {"a": "b", "c": null} => {"a": "b"} }
+        ok! { {} + {"a": {"bb": {"ccc": null}}} => {"a": {"bb": {}}} }
+    }
+}
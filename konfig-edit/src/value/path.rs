@@ -1,3 +1,4 @@
+use super::{Value, ValueCell};
 use crate::serializer::components::{write_escaped_str, write_int};
 use std::borrow::Borrow;
 use std::borrow::Cow;
@@ -13,6 +14,29 @@ pub enum PathItem<'i> {
 }
 
 impl PathItem<'_> {
+    pub fn into_owned(self) -> PathItem<'static> {
+        match self {
+            PathItem::SequenceIndex(idx) => PathItem::SequenceIndex(idx),
+            PathItem::MapKey(key) => PathItem::MapKey(key.into_owned().into()),
+            PathItem::StructFieldName(name) => PathItem::StructFieldName(name.into_owned().into()),
+            PathItem::VariantName(name) => PathItem::VariantName(name.into_owned().into()),
+        }
+    }
+
+    // NOTE: creates a container which has `value` at this path item. It's how the type of a
+    // container is inferred from paths, so sequences can only be started with the first item.
+    pub(crate) fn wrap(self, value: ValueCell) -> Option<Value> {
+        match self {
+            PathItem::SequenceIndex(0) => Some(Value::Sequence(vec![value])),
+            PathItem::SequenceIndex(_) => None,
+            PathItem::MapKey(key) => Some(Value::Map([(key.into_owned(), value)].into())),
+            PathItem::StructFieldName(name) => {
+                Some(Value::Struct([(name.into_owned(), value)].into()))
+            }
+            PathItem::VariantName(name) => Some(Value::Variant(name.into_owned(), value)),
+        }
+    }
+
     pub fn write(&self, out: &mut impl Write) -> fmt::Result {
         match self {
             PathItem::MapKey(key) => {
@@ -146,6 +170,16 @@ impl Clone for Path<'_> {
     }
 }
 
+impl<'i> FromIterator<PathItem<'i>> for Path<'i> {
+    fn from_iter<T: IntoIterator<Item = PathItem<'i>>>(iter: T) -> Self {
+        Self {
+            items: iter.into_iter().collect(),
+            on_item_push: None,
+            on_item_pop: None,
+        }
+    }
+}
+
 impl<'i> Borrow<[PathItem<'i>]> for Path<'i> {
     fn borrow(&self) -> &[PathItem<'i>] {
         &self.items
@@ -0,0 +1,798 @@
+use super::{Path, PathItem, Value, ValueCell};
+use crate::error::{Error, Result};
+use crate::parser::{error_at, parse_path_item_at, parse_primitive_at};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+// NOTE: a query is a konfig path which items can also be:
+// * `*` - any child of a structure, map, sequence or variant;
+// * `[*]` - any sequence element;
+// * `["*"]` or `['*']` - any map entry;
+// * `**` - the value itself and all its descendants;
+// * `[? > foo > bar]` - the value itself if it has the given relative path;
+// * `[? > foo > bar == 42]` - the value itself if the value at the relative path compares to the
+//   given primitive with one of `==`, `!=`, `<`, `<=`, `>`, `>=`.
+//
+// E.g. `> servers > [*] > [? > enabled == true] > port` matches ports of all enabled servers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Item(PathItem<'static>),
+    Any,
+    AnyIndex,
+    AnyKey,
+    Descendants,
+    Filter(Predicate),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Predicate {
+    path: Vec<PathItem<'static>>,
+    comparison: Option<(Op, Value)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self> {
+        QueryParser {
+            input: query,
+            pos: 0,
+        }
+        .query()
+    }
+}
+
+// NOTE: paths are parsed as queries that don't have wildcards and predicates.
+impl FromStr for Path<'static> {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        Query::parse(path)?
+            .segments
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Item(item) => Ok(item),
+                _ => Err(Error::custom(format!(
+                    "path can't contain wildcards or predicates: {path}"
+                ))),
+            })
+            .collect()
+    }
+}
+
+impl Query {
+    // NOTE: matches the path alone, without a value, e.g. to select merge strategies. Predicates
+    // can't be checked without a value, so queries with them never match.
+    pub fn matches_path(&self, path: &[PathItem]) -> bool {
+        matches_path(&self.segments, path)
+    }
+}
+
+fn matches_path(segments: &[Segment], path: &[PathItem]) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        return path.is_empty();
+    };
+
+    if *segment == Segment::Descendants {
+        return matches_path(rest, path)
+            || (!path.is_empty() && matches_path(segments, &path[1..]));
+    }
+
+    let Some((item, path)) = path.split_first() else {
+        return false;
+    };
+
+    let item_matches = match segment {
+        Segment::Item(expected) => expected == item,
+        Segment::Any => true,
+        Segment::AnyIndex => matches!(item, PathItem::SequenceIndex(_)),
+        Segment::AnyKey => matches!(item, PathItem::MapKey(_)),
+        Segment::Descendants | Segment::Filter(_) => false,
+    };
+
+    item_matches && matches_path(rest, path)
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(query: &str) -> Result<Self> {
+        Self::parse(query)
+    }
+}
+
+impl Value {
+    // NOTE: the value itself is never matched, as it's not a `ValueCell`. Use `ValueCell::query`
+    // to match the root as well.
+    pub fn query(&self, query: &Query) -> Vec<(Path<'_>, &ValueCell)> {
+        let mut matcher = Matcher::new(query);
+
+        matcher.match_value(&mut Path::default(), self, None, 0);
+        matcher.matches
+    }
+
+    // NOTE: calls `f` for every match in the order of `query`. Matches that no longer exist after
+    // the previous calls of `f` are skipped. Returns the number of visited matches.
+    pub fn query_mut(&mut self, query: &Query, mut f: impl FnMut(&Path, &mut ValueCell)) -> usize {
+        let paths = query_paths(self.query(query));
+        let mut count = 0;
+
+        for path in paths {
+            if let Some(cell) = self.get_path_mut(path.items()) {
+                f(&path, cell);
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    pub fn get_path(&self, path: &[PathItem]) -> Option<&ValueCell> {
+        let (first, rest) = path.split_first()?;
+
+        rest.iter()
+            .try_fold(self.get_item(first)?, |cell, item| cell.get_item(item))
+    }
+
+    pub fn get_path_mut(&mut self, path: &[PathItem]) -> Option<&mut ValueCell> {
+        let (first, rest) = path.split_first()?;
+
+        rest.iter()
+            .try_fold(self.get_item_mut(first)?, |cell, item| {
+                cell.get_item_mut(item)
+            })
+    }
+
+    fn get_item(&self, item: &PathItem) -> Option<&ValueCell> {
+        match (self, item) {
+            (Value::Sequence(seq), PathItem::SequenceIndex(idx)) => seq.get(*idx),
+            (Value::Map(map), PathItem::MapKey(key)) => map.get(key.as_ref()),
+            (Value::Struct(fields), PathItem::StructFieldName(name)) => fields.get(name.as_ref()),
+            (Value::Variant(name, cell), PathItem::VariantName(item)) if name == item => Some(cell),
+            _ => None,
+        }
+    }
+
+    fn get_item_mut(&mut self, item: &PathItem) -> Option<&mut ValueCell> {
+        match (self, item) {
+            (Value::Sequence(seq), PathItem::SequenceIndex(idx)) => seq.get_mut(*idx),
+            (Value::Map(map), PathItem::MapKey(key)) => map.get_mut(key.as_ref()),
+            (Value::Struct(fields), PathItem::StructFieldName(name)) => {
+                fields.get_mut(name.as_ref())
+            }
+            (Value::Variant(name, cell), PathItem::VariantName(item)) if name == item => Some(cell),
+            _ => None,
+        }
+    }
+}
+
+impl ValueCell {
+    pub fn query(&self, query: &Query) -> Vec<(Path<'_>, &ValueCell)> {
+        let mut matcher = Matcher::new(query);
+
+        matcher.match_value(&mut Path::default(), self, Some(self), 0);
+        matcher.matches
+    }
+
+    pub fn query_mut(&mut self, query: &Query, mut f: impl FnMut(&Path, &mut ValueCell)) -> usize {
+        let paths = query_paths(self.query(query));
+        let mut count = 0;
+
+        for path in paths {
+            let cell = if path.items().is_empty() {
+                Some(&mut *self)
+            } else {
+                self.get_path_mut(path.items())
+            };
+
+            if let Some(cell) = cell {
+                f(&path, cell);
+                count += 1;
+            }
+        }
+
+        count
+    }
+}
+
+fn query_paths(matches: Vec<(Path, &ValueCell)>) -> Vec<Path<'static>> {
+    matches
+        .into_iter()
+        .map(|(path, _)| {
+            path.items()
+                .iter()
+                .map(|item| item.clone().into_owned())
+                .collect()
+        })
+        .collect()
+}
+
+struct Matcher<'q, 'v> {
+    segments: &'q [Segment],
+    matches: Vec<(Path<'v>, &'v ValueCell)>,
+    // NOTE: `**` can reach the same value in multiple ways, e.g. `> ** > **`.
+    seen: HashSet<Path<'v>>,
+}
+
+impl<'q, 'v> Matcher<'q, 'v> {
+    fn new(query: &'q Query) -> Self {
+        Self {
+            segments: &query.segments,
+            matches: vec![],
+            seen: HashSet::new(),
+        }
+    }
+
+    fn match_value(
+        &mut self,
+        path: &mut Path<'v>,
+        value: &'v Value,
+        cell: Option<&'v ValueCell>,
+        segment: usize,
+    ) {
+        let Some(current) = self.segments.get(segment) else {
+            if let Some(cell) = cell {
+                if self.seen.insert(path.clone()) {
+                    self.matches.push((path.clone(), cell));
+                }
+            }
+
+            return;
+        };
+
+        match current {
+            Segment::Item(item) => {
+                if let Some(child) = value.get_item(item) {
+                    path.push(item.clone());
+                    self.match_value(path, child, Some(child), segment + 1);
+                    path.pop();
+                }
+            }
+            Segment::Any => self.match_children(path, value, segment + 1, |_| true),
+            Segment::AnyIndex => self.match_children(path, value, segment + 1, |item| {
+                matches!(item, PathItem::SequenceIndex(_))
+            }),
+            Segment::AnyKey => self.match_children(path, value, segment + 1, |item| {
+                matches!(item, PathItem::MapKey(_))
+            }),
+            Segment::Descendants => {
+                self.match_value(path, value, cell, segment + 1);
+                self.match_children(path, value, segment, |_| true);
+            }
+            Segment::Filter(predicate) => {
+                if predicate.is_satisfied_by(value) {
+                    self.match_value(path, value, cell, segment + 1);
+                }
+            }
+        }
+    }
+
+    fn match_children(
+        &mut self,
+        path: &mut Path<'v>,
+        value: &'v Value,
+        segment: usize,
+        filter: impl Fn(&PathItem) -> bool,
+    ) {
+        let children: Vec<(PathItem<'v>, &'v ValueCell)> = match value {
+            Value::Sequence(seq) => seq
+                .iter()
+                .enumerate()
+                .map(|(idx, cell)| (PathItem::SequenceIndex(idx), cell))
+                .collect(),
+            Value::Map(map) => map
+                .iter()
+                .map(|(key, cell)| (PathItem::MapKey(key.into()), cell))
+                .collect(),
+            Value::Struct(fields) => fields
+                .iter()
+                .map(|(name, cell)| (PathItem::StructFieldName(name.into()), cell))
+                .collect(),
+            Value::Variant(name, cell) => vec![(PathItem::VariantName(name.into()), cell)],
+            _ => return,
+        };
+
+        for (item, child) in children {
+            if filter(&item) {
+                path.push(item);
+                self.match_value(path, child, Some(child), segment);
+                path.pop();
+            }
+        }
+    }
+}
+
+impl Predicate {
+    fn is_satisfied_by(&self, value: &Value) -> bool {
+        let target = if self.path.is_empty() {
+            value
+        } else {
+            match value.get_path(&self.path) {
+                Some(cell) => cell.as_value(),
+                None => return false,
+            }
+        };
+
+        let Some((op, operand)) = &self.comparison else {
+            return true;
+        };
+
+        match op {
+            Op::Eq => values_eq(target, operand),
+            Op::Ne => !values_eq(target, operand),
+            Op::Lt => compare(target, operand) == Some(Ordering::Less),
+            Op::Le => matches!(
+                compare(target, operand),
+                Some(Ordering::Less | Ordering::Equal)
+            ),
+            Op::Gt => compare(target, operand) == Some(Ordering::Greater),
+            Op::Ge => matches!(
+                compare(target, operand),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+        }
+    }
+}
+
+// NOTE: numbers are compared regardless of their representation, so `42` matches both unsigned
+// and signed integers, as well as floats.
+fn values_eq(a: &Value, b: &Value) -> bool {
+    match (number(a), number(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    if let (Some(a), Some(b)) = (number(a), number(b)) {
+        return a.partial_cmp(&b);
+    }
+
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+#[inline]
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::UInt(_) | Value::Int(_) | Value::Float(_) => value.as_f64(),
+        _ => None,
+    }
+}
+
+struct QueryParser<'i> {
+    input: &'i str,
+    pos: usize,
+}
+
+impl QueryParser<'_> {
+    fn query(mut self) -> Result<Query> {
+        let mut segments = vec![];
+
+        self.skip_spaces();
+        self.expect(">")?;
+        self.skip_spaces();
+
+        if !self.at_end() {
+            segments.push(self.segment()?);
+            self.skip_spaces();
+
+            while !self.at_end() {
+                self.expect(">")?;
+                self.skip_spaces();
+                segments.push(self.segment()?);
+                self.skip_spaces();
+            }
+        }
+
+        Ok(Query { segments })
+    }
+
+    fn segment(&mut self) -> Result<Segment> {
+        if self.eat("**") {
+            return Ok(Segment::Descendants);
+        }
+
+        if self.eat("*") {
+            return Ok(Segment::Any);
+        }
+
+        let start = self.pos;
+
+        if self.eat("[") {
+            self.skip_spaces();
+
+            if self.eat("?") {
+                let predicate = self.predicate()?;
+
+                self.skip_spaces();
+                self.expect("]")?;
+
+                return Ok(Segment::Filter(predicate));
+            }
+
+            let segment = if self.eat("*") {
+                Some(Segment::AnyIndex)
+            } else if self.eat("\"*\"") || self.eat("'*'") {
+                Some(Segment::AnyKey)
+            } else {
+                None
+            };
+
+            if let Some(segment) = segment {
+                self.skip_spaces();
+                self.expect("]")?;
+
+                return Ok(segment);
+            }
+
+            self.pos = start;
+        }
+
+        Ok(Segment::Item(self.path_item()?))
+    }
+
+    fn predicate(&mut self) -> Result<Predicate> {
+        let mut path = vec![];
+
+        self.skip_spaces();
+        self.expect(">")?;
+        self.skip_spaces();
+
+        if !self.rest().starts_with(OPS) {
+            path.push(self.path_item()?);
+            self.skip_spaces();
+
+            // NOTE: `>` is both a path separator and an operator, it's a separator only if it's
+            // followed by a path item.
+            while self.rest().starts_with('>') && !self.rest().starts_with(">=") {
+                let start = self.pos;
+
+                self.pos += 1;
+                self.skip_spaces();
+
+                match parse_path_item_at(self.input, self.pos) {
+                    Ok((item, end)) => {
+                        path.push(item);
+                        self.pos = end;
+                        self.skip_spaces();
+                    }
+                    Err(_) => {
+                        self.pos = start;
+                        break;
+                    }
+                }
+            }
+        }
+
+        let op = self.op();
+
+        let comparison = match op {
+            Some(op) => {
+                self.skip_spaces();
+
+                let (value, end) = parse_primitive_at(self.input, self.pos)?;
+
+                self.pos = end;
+
+                Some((op, value))
+            }
+            None => None,
+        };
+
+        Ok(Predicate { path, comparison })
+    }
+
+    fn op(&mut self) -> Option<Op> {
+        // NOTE: longer operators go first, so `<=` isn't parsed as `<`.
+        let op = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ]
+        .into_iter()
+        .find(|(token, _)| self.rest().starts_with(token))?;
+
+        self.pos += op.0.len();
+
+        Some(op.1)
+    }
+
+    fn path_item(&mut self) -> Result<PathItem<'static>> {
+        let (item, end) = parse_path_item_at(self.input, self.pos)?;
+
+        self.pos = end;
+
+        Ok(item)
+    }
+
+    #[inline]
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    #[inline]
+    fn at_end(&self) -> bool {
+        self.pos == self.input.len()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let matches = self.rest().starts_with(token);
+
+        if matches {
+            self.pos += token.len();
+        }
+
+        matches
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(error_at(
+                self.input,
+                self.pos,
+                format_args!("expected `{token}`"),
+            ))
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = self.rest();
+
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+}
+
+const OPS: [char; 4] = ['=', '!', '<', '>'];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        > servers > [0] > name = \"web\"
+
+        > servers > [0] > port = 8080
+
+        > servers > [0] > enabled = true
+
+        > servers > [1] > name = \"db\"
+
+        > servers > [1] > port = 5432
+
+        > servers > [1] > enabled = false
+
+        > env > ['HOME'] > path = \"/root\"
+
+        > env > ['TMP'] > path = \"/tmp\"
+
+        > mode > `Fast` > port = 1
+    "};
+
+    fn paths(value: &ValueCell, query: &str) -> Vec<String> {
+        value
+            .query(&query.parse().unwrap())
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn wildcards() {
+        let value = parse(INPUT).unwrap();
+
+        assert_eq!(
+            paths(&value, "> servers > [*] > name"),
+            ["> servers > [0] > name", "> servers > [1] > name"]
+        );
+
+        assert_eq!(
+            paths(&value, "> env > [\"*\"] > path"),
+            ["> env > [\"HOME\"] > path", "> env > [\"TMP\"] > path"]
+        );
+
+        assert_eq!(paths(&value, "> env > ['*']").len(), 2);
+        assert_eq!(paths(&value, "> servers > ['*']"), Vec::<String>::new());
+        assert_eq!(paths(&value, "> * > `Fast`"), ["> mode > `Fast`"]);
+        assert_eq!(paths(&value, ">"), [">"]);
+        assert!(value
+            .as_value()
+            .query(&Query::parse(">").unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn descendants() {
+        let value = parse(INPUT).unwrap();
+
+        assert_eq!(
+            paths(&value, "> ** > port"),
+            [
+                "> servers > [0] > port",
+                "> servers > [1] > port",
+                "> mode > `Fast` > port"
+            ]
+        );
+
+        assert_eq!(paths(&value, "> ** > ** > path").len(), 2);
+        assert_eq!(paths(&value, "> mode > **").len(), 3);
+    }
+
+    #[test]
+    fn predicates() {
+        let value = parse(INPUT).unwrap();
+
+        assert_eq!(
+            paths(&value, "> servers > [*] > [? > enabled == true] > port"),
+            ["> servers > [0] > port"]
+        );
+
+        assert_eq!(
+            paths(&value, "> servers > [*] > [? > port > 6000] > name"),
+            ["> servers > [0] > name"]
+        );
+
+        assert_eq!(
+            paths(&value, "> servers > [*] > [? > name != 'web']"),
+            ["> servers > [1]"]
+        );
+
+        assert_eq!(paths(&value, "> ** > [? > `Fast` > port]"), ["> mode"]);
+
+        assert_eq!(
+            paths(&value, "> servers > [*] > port > [? > >= 8080]"),
+            ["> servers > [0] > port"]
+        );
+
+        assert_eq!(
+            paths(&value, "> servers > [*] > [? > name < \"e\"] > name"),
+            ["> servers > [1] > name"]
+        );
+
+        assert!(paths(&value, "> servers > [*] > [? > missing == 1]").is_empty());
+    }
+
+    #[test]
+    fn query_mut() {
+        let mut value = parse(INPUT).unwrap();
+        let query = Query::parse("> ** > port").unwrap();
+
+        let count = value.query_mut(&query, |_, port| {
+            let new_port = port.as_u64().unwrap() + 1;
+
+            *port.as_value_mut() = Value::UInt(new_port);
+        });
+
+        assert_eq!(count, 3);
+        assert_eq!(value["servers"][1]["port"], Value::UInt(5433));
+        assert_eq!(value["mode"]["`Fast`"]["port"], Value::UInt(2));
+        assert_eq!(
+            value["servers"][0]["port"].lexical_info().span,
+            Some(57..61)
+        );
+
+        let query = Query::parse("> servers > [*]").unwrap();
+
+        value.as_value_mut().query_mut(&query, |path, server| {
+            if path.items()[1] == PathItem::SequenceIndex(0) {
+                *server.as_value_mut() = Value::Null;
+            }
+        });
+
+        assert_eq!(value["servers"][0], Value::Null);
+    }
+
+    #[test]
+    fn get_path() {
+        let value = parse(INPUT).unwrap();
+
+        let path = [
+            PathItem::StructFieldName("env".into()),
+            PathItem::MapKey("TMP".into()),
+            PathItem::StructFieldName("path".into()),
+        ];
+
+        assert_eq!(
+            *value.get_path(&path).unwrap(),
+            Value::String("/tmp".into())
+        );
+
+        assert!(value.get_path(&path[..1]).unwrap().is_map());
+        assert!(value.get_path(&[]).is_none());
+        assert!(value
+            .get_path(&[PathItem::MapKey("servers".into())])
+            .is_none());
+    }
+
+    #[test]
+    fn matches_path() {
+        let path = [
+            PathItem::StructFieldName("servers".into()),
+            PathItem::SequenceIndex(1),
+            PathItem::StructFieldName("ports".into()),
+        ];
+
+        let matches = |query: &str| Query::parse(query).unwrap().matches_path(&path);
+
+        assert!(matches("> servers > [1] > ports"));
+        assert!(matches("> servers > [*] > ports"));
+        assert!(matches("> * > * > *"));
+        assert!(matches("> ** > ports"));
+        assert!(matches("> servers > **"));
+        assert!(matches("> ** > [*] > **"));
+        assert!(!matches("> servers > [\"*\"] > ports"));
+        assert!(!matches("> servers > [*]"));
+        assert!(!matches("> ** > servers"));
+        assert!(!matches("> servers > [*] > [? > ports] > ports"));
+        assert!(Query::parse(">").unwrap().matches_path(&[]));
+    }
+
+    #[test]
+    fn parse_path() {
+        let path: Path = "> servers > [1] > [\"ports\"] > `Tcp`".parse().unwrap();
+
+        assert_eq!(
+            path.items(),
+            [
+                PathItem::StructFieldName("servers".into()),
+                PathItem::SequenceIndex(1),
+                PathItem::MapKey("ports".into()),
+                PathItem::VariantName("Tcp".into()),
+            ]
+        );
+
+        assert_eq!(path.to_string().parse::<Path>().unwrap(), path);
+        assert!(">".parse::<Path>().unwrap().items().is_empty());
+
+        assert_eq!(
+            "> servers > [*]".parse::<Path>().unwrap_err().to_string(),
+            "path can't contain wildcards or predicates: > servers > [*]"
+        );
+    }
+
+    #[test]
+    fn invalid_queries() {
+        let err = |query: &str| Query::parse(query).unwrap_err().to_string();
+
+        assert_eq!(
+            err("servers"),
+            indoc! {"
+                 --> 1:1
+                  |
+                1 | servers
+                  | ^
+                  |
+                  = expected `>`"
+            }
+        );
+
+        assert!(err("> servers > [x]").contains("1:14"));
+        assert!(err("> servers > [x]").contains("expected map key or sequence index"));
+        assert!(err("> servers > [? > port >]").contains("1:24"));
+        assert!(err("> servers port").contains("expected `>`"));
+    }
+}
@@ -4,6 +4,7 @@ use std::cell::{Ref, RefCell, RefMut};
 use std::fmt;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub(crate) struct ValueCellInternal {
@@ -20,12 +21,12 @@ impl ValueCell {
     }
 
     #[inline]
-    pub(crate) fn borrow(&self) -> Ref<ValueCellInternal> {
+    pub(crate) fn borrow(&self) -> Ref<'_, ValueCellInternal> {
         self.0.borrow()
     }
 
     #[inline]
-    pub(crate) fn borrow_mut(&self) -> RefMut<ValueCellInternal> {
+    pub(crate) fn borrow_mut(&self) -> RefMut<'_, ValueCellInternal> {
         self.0.borrow_mut()
     }
 
@@ -75,6 +76,66 @@ impl ValueCell {
     }
 }
 
+impl ValueCell {
+    // NOTE: unlike `clone`, keeps the lexical info of the value and all its descendants.
+    pub(crate) fn clone_with_lexical_info(&self) -> Self {
+        let mut cell = ValueCell::from(self.as_value().clone_with_lexical_info());
+
+        *cell.lexical_info_mut() = self.lexical_info().clone();
+
+        cell
+    }
+}
+
+impl ValueCell {
+    // NOTE: sets the source of the value and all its descendants.
+    pub fn set_source(&mut self, source: impl Into<Arc<str>>) {
+        let source = source.into();
+
+        self.lexical_info_mut().source = Some(Arc::clone(&source));
+        self.as_value_mut().set_source(source);
+    }
+}
+
+impl Value {
+    // NOTE: sets the source of all the descendants of the value.
+    pub fn set_source(&mut self, source: impl Into<Arc<str>>) {
+        let source = source.into();
+
+        let set = |cell: &mut ValueCell| cell.set_source(Arc::clone(&source));
+
+        match self {
+            Value::Sequence(seq) => seq.iter_mut().for_each(set),
+            Value::Map(map) | Value::Struct(map) => map.values_mut().for_each(set),
+            Value::Variant(_, value) => set(value),
+            _ => (),
+        }
+    }
+
+    pub(crate) fn clone_with_lexical_info(&self) -> Self {
+        match self {
+            Value::Sequence(seq) => {
+                Value::Sequence(seq.iter().map(ValueCell::clone_with_lexical_info).collect())
+            }
+            Value::Map(map) => Value::Map(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), v.clone_with_lexical_info()))
+                    .collect(),
+            ),
+            Value::Struct(fields) => Value::Struct(
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone_with_lexical_info()))
+                    .collect(),
+            ),
+            Value::Variant(name, value) => {
+                Value::Variant(name.clone(), value.clone_with_lexical_info())
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
 impl Deref for ValueCell {
     type Target = Value;
 
@@ -130,7 +191,7 @@ impl From<Value> for ValueCell {
 // that parsing is done in a single thread and AST is not exposed to the external code until parsing
 // is complete. After releasing the AST to the external code all the `Rc` and `RefCell` API is hidden,
 // so exclusive ownership is guraranteed allowing us to ignore those containers. Compile time test
-// ensures that `Value` is `Send` and `Sync` itself.
+// ensures that `Value` and `LexicalInfo` are `Send` and `Sync` themselves.
 unsafe impl Send for ValueCell {}
 unsafe impl Sync for ValueCell {}
 
@@ -160,6 +221,24 @@ pub(crate) mod safety_checks {
         }
     }
 
+    // NOTE: allows to use the public API while parsing is in progress, e.g. to merge values.
+    // The caller must ensure that the values are exclusively owned.
+    pub(crate) struct SuspendParsingGuard;
+
+    impl SuspendParsingGuard {
+        pub(crate) fn new() -> Self {
+            IS_PARSING.with(|is_parsing| is_parsing.set(false));
+
+            Self
+        }
+    }
+
+    impl Drop for SuspendParsingGuard {
+        fn drop(&mut self) {
+            IS_PARSING.with(|is_parsing| is_parsing.set(true));
+        }
+    }
+
     pub(crate) fn assert_not_parsing() {
         IS_PARSING.with(|is_parsing| {
             assert!(!is_parsing.get(), "parser should not use this API");
@@ -170,6 +249,7 @@ pub(crate) mod safety_checks {
         fn assert<S: Send + Sync>() {}
 
         assert::<Value>();
+        assert::<LexicalInfo>();
     }
 }
 
@@ -190,4 +270,32 @@ mod tests {
 
         assert_eq!(actual, value);
     }
+
+    #[test]
+    fn sourced_value_across_threads() {
+        let mut value = crate::parser::parse("> a > b = 1\n\n> c = [1, 2]").unwrap();
+
+        value.set_source("site");
+
+        let value = value.into_value();
+
+        // NOTE: the source is shared by all the cells, so lexical info is cloned concurrently.
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let info = value["a"]["b"].lexical_info().clone();
+
+                        assert_eq!(info.source.as_deref(), Some("site"));
+                    }
+                });
+            }
+        });
+
+        let source = std::thread::spawn(move || value["c"].lexical_info().source.clone())
+            .join()
+            .unwrap();
+
+        assert_eq!(source.as_deref(), Some("site"));
+    }
 }
@@ -0,0 +1,372 @@
+use super::{Path, PathItem, Value, ValueCell};
+use indexmap::map;
+use std::iter::Enumerate;
+use std::slice;
+
+// NOTE: sequences, maps, structs and variants are containers, everything else is a leaf. Sequences
+// of primitives are containers as well, even though they are serialized as a single expression.
+// `leave` is not called for containers which `enter` returned `false` for.
+pub trait Visitor<'v> {
+    #[inline]
+    fn enter(&mut self, _path: &Path<'v>, _value: &'v ValueCell) -> bool {
+        true
+    }
+
+    #[inline]
+    fn leave(&mut self, _path: &Path<'v>, _value: &'v ValueCell) {}
+
+    #[inline]
+    fn visit_leaf(&mut self, _path: &Path<'v>, _value: &'v ValueCell) {}
+}
+
+pub trait VisitorMut {
+    #[inline]
+    fn enter(&mut self, _path: &Path, _value: &mut ValueCell) -> bool {
+        true
+    }
+
+    #[inline]
+    fn leave(&mut self, _path: &Path, _value: &mut ValueCell) {}
+
+    #[inline]
+    fn visit_leaf(&mut self, _path: &Path, _value: &mut ValueCell) {}
+}
+
+impl Value {
+    #[inline]
+    pub fn is_container(&self) -> bool {
+        matches!(
+            self,
+            Value::Sequence(_) | Value::Map(_) | Value::Struct(_) | Value::Variant(_, _)
+        )
+    }
+
+    // NOTE: the value itself is not visited, as it's not a `ValueCell`. Use `ValueCell::walk` to
+    // visit the root as well.
+    pub fn walk<'v>(&'v self, visitor: &mut impl Visitor<'v>) {
+        walk_children(&mut Path::default(), self, visitor);
+    }
+
+    pub fn walk_mut(&mut self, visitor: &mut impl VisitorMut) {
+        walk_children_mut(&mut Path::default(), self, visitor);
+    }
+
+    pub fn leaves(&self) -> Leaves<'_> {
+        Leaves {
+            path: Path::default(),
+            root: None,
+            stack: Children::new(self).into_iter().collect(),
+        }
+    }
+}
+
+impl ValueCell {
+    #[inline]
+    pub fn walk<'v>(&'v self, visitor: &mut impl Visitor<'v>) {
+        walk_cell(&mut Path::default(), self, visitor);
+    }
+
+    #[inline]
+    pub fn walk_mut(&mut self, visitor: &mut impl VisitorMut) {
+        walk_cell_mut(&mut Path::default(), self, visitor);
+    }
+
+    pub fn leaves(&self) -> Leaves<'_> {
+        match Children::new(self) {
+            Some(children) => Leaves {
+                path: Path::default(),
+                root: None,
+                stack: vec![children],
+            },
+            None => Leaves {
+                path: Path::default(),
+                root: Some(self),
+                stack: vec![],
+            },
+        }
+    }
+}
+
+fn walk_cell<'v>(path: &mut Path<'v>, cell: &'v ValueCell, visitor: &mut impl Visitor<'v>) {
+    if !cell.is_container() {
+        visitor.visit_leaf(path, cell);
+    } else if visitor.enter(path, cell) {
+        walk_children(path, cell, visitor);
+        visitor.leave(path, cell);
+    }
+}
+
+fn walk_children<'v>(path: &mut Path<'v>, value: &'v Value, visitor: &mut impl Visitor<'v>) {
+    let mut walk_child = |path: &mut Path<'v>, item, cell| {
+        path.push(item);
+        walk_cell(path, cell, visitor);
+        path.pop();
+    };
+
+    match value {
+        Value::Sequence(seq) => {
+            for (idx, cell) in seq.iter().enumerate() {
+                walk_child(path, PathItem::SequenceIndex(idx), cell);
+            }
+        }
+        Value::Map(map) => {
+            for (key, cell) in map {
+                walk_child(path, PathItem::MapKey(key.into()), cell);
+            }
+        }
+        Value::Struct(fields) => {
+            for (name, cell) in fields {
+                walk_child(path, PathItem::StructFieldName(name.into()), cell);
+            }
+        }
+        Value::Variant(name, cell) => walk_child(path, PathItem::VariantName(name.into()), cell),
+        _ => (),
+    }
+}
+
+fn walk_cell_mut(path: &mut Path, cell: &mut ValueCell, visitor: &mut impl VisitorMut) {
+    if !cell.is_container() {
+        visitor.visit_leaf(path, cell);
+    } else if visitor.enter(path, cell) {
+        walk_children_mut(path, cell, visitor);
+        visitor.leave(path, cell);
+    }
+}
+
+// NOTE: the path owns its items, as they can't be borrowed from the value that is being mutated.
+fn walk_children_mut(path: &mut Path, value: &mut Value, visitor: &mut impl VisitorMut) {
+    let mut walk_child = |path: &mut Path, item, cell: &mut ValueCell| {
+        path.push(item);
+        walk_cell_mut(path, cell, visitor);
+        path.pop();
+    };
+
+    match value {
+        Value::Sequence(seq) => {
+            for (idx, cell) in seq.iter_mut().enumerate() {
+                walk_child(path, PathItem::SequenceIndex(idx), cell);
+            }
+        }
+        Value::Map(map) => {
+            for (key, cell) in map {
+                walk_child(path, PathItem::MapKey(key.clone().into()), cell);
+            }
+        }
+        Value::Struct(fields) => {
+            for (name, cell) in fields {
+                walk_child(path, PathItem::StructFieldName(name.clone().into()), cell);
+            }
+        }
+        Value::Variant(name, cell) => {
+            walk_child(path, PathItem::VariantName(name.clone().into()), cell)
+        }
+        _ => (),
+    }
+}
+
+pub struct Leaves<'v> {
+    path: Path<'v>,
+    root: Option<&'v ValueCell>,
+    stack: Vec<Children<'v>>,
+}
+
+enum Children<'v> {
+    Sequence(Enumerate<slice::Iter<'v, ValueCell>>),
+    Map(map::Iter<'v, String, ValueCell>),
+    Struct(map::Iter<'v, String, ValueCell>),
+    Variant(Option<(&'v str, &'v ValueCell)>),
+}
+
+impl<'v> Children<'v> {
+    fn new(value: &'v Value) -> Option<Self> {
+        match value {
+            Value::Sequence(seq) => Some(Children::Sequence(seq.iter().enumerate())),
+            Value::Map(map) => Some(Children::Map(map.iter())),
+            Value::Struct(fields) => Some(Children::Struct(fields.iter())),
+            Value::Variant(name, cell) => Some(Children::Variant(Some((name, cell)))),
+            _ => None,
+        }
+    }
+}
+
+impl<'v> Iterator for Children<'v> {
+    type Item = (PathItem<'v>, &'v ValueCell);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Children::Sequence(iter) => iter
+                .next()
+                .map(|(idx, cell)| (PathItem::SequenceIndex(idx), cell)),
+            Children::Map(iter) => iter
+                .next()
+                .map(|(key, cell)| (PathItem::MapKey(key.into()), cell)),
+            Children::Struct(iter) => iter
+                .next()
+                .map(|(name, cell)| (PathItem::StructFieldName(name.into()), cell)),
+            Children::Variant(child) => child
+                .take()
+                .map(|(name, cell)| (PathItem::VariantName(name.into()), cell)),
+        }
+    }
+}
+
+impl<'v> Iterator for Leaves<'v> {
+    type Item = (Path<'v>, &'v ValueCell);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            return Some((Path::default(), root));
+        }
+
+        loop {
+            let Some((item, cell)) = self.stack.last_mut()?.next() else {
+                self.stack.pop();
+
+                // NOTE: children of the root don't have a path item of their own.
+                if !self.stack.is_empty() {
+                    self.path.pop();
+                }
+
+                continue;
+            };
+
+            self.path.push(item);
+
+            match Children::new(cell) {
+                Some(children) => self.stack.push(children),
+                None => {
+                    let path = self.path.clone();
+
+                    self.path.pop();
+
+                    return Some((path, cell));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        > foo > [0] > bar = 42
+
+        > foo > [1] = [1, 2]
+
+        > foo > [2] > ['baz'] > `Qux` = \"secret\"
+
+        > empty = []
+
+        > quz = true
+    "};
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl<'v> Visitor<'v> for Log {
+        fn enter(&mut self, path: &Path<'v>, _value: &'v ValueCell) -> bool {
+            self.0.push(format!("enter {path}"));
+
+            path.items().len() < 3
+        }
+
+        fn leave(&mut self, path: &Path<'v>, _value: &'v ValueCell) {
+            self.0.push(format!("leave {path}"));
+        }
+
+        fn visit_leaf(&mut self, path: &Path<'v>, value: &'v ValueCell) {
+            self.0.push(format!("leaf {path} = {value:?}"));
+        }
+    }
+
+    #[test]
+    fn walk() {
+        let value = parse(INPUT).unwrap();
+        let mut log = Log::default();
+
+        value.walk(&mut log);
+
+        assert_eq!(
+            log.0,
+            [
+                "enter >",
+                "enter > foo",
+                "enter > foo > [0]",
+                "leaf > foo > [0] > bar = UInt(42)",
+                "leave > foo > [0]",
+                "enter > foo > [1]",
+                "leaf > foo > [1] > [0] = UInt(1)",
+                "leaf > foo > [1] > [1] = UInt(2)",
+                "leave > foo > [1]",
+                "enter > foo > [2]",
+                "enter > foo > [2] > [\"baz\"]",
+                "leave > foo > [2]",
+                "leave > foo",
+                "enter > empty",
+                "leave > empty",
+                "leaf > quz = Bool(true)",
+                "leave >",
+            ]
+        );
+
+        let mut log = Log::default();
+
+        value.as_value().walk(&mut log);
+
+        assert_eq!(log.0.first().unwrap(), "enter > foo");
+        assert_eq!(log.0.last().unwrap(), "leaf > quz = Bool(true)");
+    }
+
+    #[test]
+    fn walk_mut() {
+        struct Redact;
+
+        impl VisitorMut for Redact {
+            fn visit_leaf(&mut self, path: &Path, value: &mut ValueCell) {
+                if path.items().last() == Some(&PathItem::VariantName("Qux".into())) {
+                    *value.as_value_mut() = Value::String("***".into());
+                }
+            }
+        }
+
+        let mut value = parse(INPUT).unwrap();
+
+        value.walk_mut(&mut Redact);
+
+        assert_eq!(value["foo"][2]["baz"]["`Qux`"], Value::String("***".into()));
+    }
+
+    #[test]
+    fn leaves() {
+        let value = parse(INPUT).unwrap();
+
+        let leaves = value
+            .leaves()
+            .map(|(path, value)| format!("{path} = {value:?}"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            leaves,
+            [
+                "> foo > [0] > bar = UInt(42)",
+                "> foo > [1] > [0] = UInt(1)",
+                "> foo > [1] > [1] = UInt(2)",
+                "> foo > [2] > [\"baz\"] > `Qux` = String(\"secret\")",
+                "> quz = Bool(true)",
+            ]
+        );
+
+        let value = parse("> = 42").unwrap();
+
+        assert_eq!(
+            value.leaves().map(|(path, _)| path).collect::<Vec<_>>(),
+            [Path::default()]
+        );
+
+        assert_eq!(value.as_value().leaves().count(), 0);
+    }
+}
@@ -0,0 +1,460 @@
+use crate::derive_with_docs::extract_doc_lines_and_cfg_attrs;
+use crate::serde_attrs::{SerdeAttributesInfo, SerdeContainerAttributesInfo};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::{Attribute, Data, DataEnum, DeriveInput, Fields};
+
+pub(crate) fn expand(input: DeriveInput) -> TokenStream2 {
+    let name = &input.ident;
+    let span = name.span();
+    let container_attrs = SerdeContainerAttributesInfo::from(&input.attrs);
+    let description = description(&input.attrs);
+
+    let body = match &input.data {
+        Data::Struct(data) if container_attrs.transparent() => expand_transparent(&data.fields),
+        Data::Struct(data) => {
+            let fields = expand_fields(&data.fields, &container_attrs, &container_attrs);
+
+            // NOTE: the tag of a structure is the name of the structure, as with the internally
+            // tagged variants.
+            match container_attrs.tag() {
+                Some(tag) => {
+                    let name_str = container_attrs.maybe_rename(name.to_string());
+
+                    quote! {
+                        konfig::json_schema::internally_tagged_variant_schema(
+                            #tag,
+                            #name_str,
+                            Some(#fields)
+                        )
+                    }
+                }
+                None => fields,
+            }
+        }
+        Data::Enum(data) => expand_variants(data, &container_attrs),
+        Data::Union(_) => {
+            return syn::Error::new(span, "JSON schemas can't be derived for unions")
+                .to_compile_error()
+        }
+    };
+
+    // NOTE: the type name includes the module path and the type arguments, so instances of
+    // generic types and types with the same name in different modules are told apart.
+    let schema_name = quote! {
+        std::borrow::Cow::Borrowed(std::any::type_name::<Self>())
+    };
+
+    let mut generics = input.generics.clone();
+    let type_params = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect::<Vec<_>>();
+
+    let where_clause = generics.make_where_clause();
+
+    for param in type_params {
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #param: konfig::JsonSchema });
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote_spanned! { span =>
+        #[automatically_derived]
+        impl #impl_generics konfig::JsonSchema for #name #ty_generics #where_clause {
+            fn schema_name() -> Option<std::borrow::Cow<'static, str>> {
+                Some(#schema_name)
+            }
+
+            fn json_schema(
+                generator: &mut konfig::json_schema::SchemaGenerator
+            ) -> konfig::Value {
+                konfig::json_schema::describe(#body, #description)
+            }
+        }
+    }
+}
+
+// NOTE: variant fields are renamed by the `rename_all` attribute of the variant, but the rest of
+// the attributes come from the enum.
+fn expand_fields(
+    fields: &Fields,
+    rename_attrs: &SerdeContainerAttributesInfo,
+    container_attrs: &SerdeContainerAttributesInfo,
+) -> TokenStream2 {
+    match fields {
+        Fields::Named(fields) => {
+            let mut properties = TokenStream2::default();
+
+            for field in &fields.named {
+                let field_attrs = SerdeAttributesInfo::from(&field.attrs);
+
+                if field_attrs.is_skipped() {
+                    continue;
+                }
+
+                // NOTE: schemas of flattened fields can't be merged into the object without
+                // resolving them, e.g. maps would constrain the rest of the properties as well.
+                if field_attrs.is_flattened() {
+                    return syn::Error::new_spanned(
+                        field,
+                        "flattened fields are not supported in JSON schemas",
+                    )
+                    .to_compile_error();
+                }
+
+                let name = field.ident.as_ref().unwrap();
+                let ty = &field.ty;
+                let (_, cfg_attrs) = extract_doc_lines_and_cfg_attrs(&field.attrs);
+                let description = description(&field.attrs);
+
+                let name_str =
+                    field_attrs.maybe_rename(rename_attrs.maybe_rename_field(name.to_string()));
+
+                let required = if field_attrs.has_default() || container_attrs.has_default() {
+                    quote! { false }
+                } else {
+                    quote! { !<#ty as konfig::JsonSchema>::is_optional() }
+                };
+
+                properties.extend(quote! {
+                    #(#cfg_attrs)*
+                    object.property(
+                        #name_str,
+                        generator.subschema_for::<#ty>(),
+                        #required,
+                        #description
+                    );
+                });
+            }
+
+            let deny_unknown_fields = if container_attrs.deny_unknown_fields() {
+                quote! { object.deny_unknown_fields(); }
+            } else {
+                TokenStream2::default()
+            };
+
+            quote! {
+                {
+                    let mut object = konfig::json_schema::ObjectSchema::default();
+
+                    #properties
+                    #deny_unknown_fields
+
+                    object.into_schema()
+                }
+            }
+        }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            let field = fields.unnamed.first().unwrap();
+            let ty = &field.ty;
+            let description = description(&field.attrs);
+
+            quote! {
+                konfig::json_schema::describe(generator.subschema_for::<#ty>(), #description)
+            }
+        }
+        Fields::Unnamed(fields) => {
+            let mut items = TokenStream2::default();
+
+            for field in &fields.unnamed {
+                let ty = &field.ty;
+                let (_, cfg_attrs) = extract_doc_lines_and_cfg_attrs(&field.attrs);
+                let description = description(&field.attrs);
+
+                items.extend(quote! {
+                    #(#cfg_attrs)*
+                    items.push(konfig::json_schema::describe(
+                        generator.subschema_for::<#ty>(),
+                        #description
+                    ));
+                });
+            }
+
+            quote! {
+                {
+                    let mut items = vec![];
+
+                    #items
+
+                    konfig::json_schema::tuple_schema(items)
+                }
+            }
+        }
+        Fields::Unit => quote! {
+            konfig::json_schema::schema([("type", konfig::Value::from("null"))])
+        },
+    }
+}
+
+// NOTE: transparent structures have the schema of their only field that is not skipped.
+fn expand_transparent(fields: &Fields) -> TokenStream2 {
+    let mut fields = fields
+        .iter()
+        .filter(|field| !SerdeAttributesInfo::from(&field.attrs).is_skipped());
+
+    match (fields.next(), fields.next()) {
+        (Some(field), None) => {
+            let ty = &field.ty;
+
+            quote! { generator.subschema_for::<#ty>() }
+        }
+        _ => syn::Error::new(
+            Span::call_site(),
+            "transparent structs must have exactly one field that is not skipped",
+        )
+        .to_compile_error(),
+    }
+}
+
+// NOTE: variants are externally tagged, as in konfig documents, unless the enum is untagged or
+// has the tag specified. Adjacently tagged variants have the content specified as well.
+fn expand_variants(
+    data_enum: &DataEnum,
+    container_attrs: &SerdeContainerAttributesInfo,
+) -> TokenStream2 {
+    let untagged = container_attrs.untagged();
+    let mut variants = TokenStream2::default();
+
+    for variant in &data_enum.variants {
+        let variant_attrs = SerdeAttributesInfo::from(&variant.attrs);
+
+        if variant_attrs.is_skipped() {
+            continue;
+        }
+
+        let (_, cfg_attrs) = extract_doc_lines_and_cfg_attrs(&variant.attrs);
+        let description = description(&variant.attrs);
+        let rename_attrs = SerdeContainerAttributesInfo::from(&variant.attrs);
+        let fields = expand_fields(&variant.fields, &rename_attrs, container_attrs);
+
+        let name_str = variant_attrs
+            .maybe_rename(container_attrs.maybe_rename_variant(variant.ident.to_string()));
+
+        let content = match variant.fields {
+            Fields::Unit => quote! { None },
+            _ => quote! { Some(#fields) },
+        };
+
+        let schema = match (
+            &variant.fields,
+            container_attrs.tag(),
+            container_attrs.content(),
+        ) {
+            _ if untagged => fields,
+            (_, Some(tag), Some(content_key)) => quote! {
+                konfig::json_schema::adjacently_tagged_variant_schema(
+                    #tag,
+                    #content_key,
+                    #name_str,
+                    #content
+                )
+            },
+            (_, Some(tag), None) => quote! {
+                konfig::json_schema::internally_tagged_variant_schema(#tag, #name_str, #content)
+            },
+            (Fields::Unit, None, _) => {
+                quote! { konfig::json_schema::unit_variant_schema(#name_str) }
+            }
+            (_, None, _) => {
+                quote! { konfig::json_schema::tagged_variant_schema(#name_str, #fields) }
+            }
+        };
+
+        variants.extend(quote! {
+            #(#cfg_attrs)*
+            variants.push(konfig::json_schema::describe(#schema, #description));
+        });
+    }
+
+    let combinator = if untagged {
+        quote! { konfig::json_schema::any_of }
+    } else {
+        quote! { konfig::json_schema::one_of }
+    };
+
+    quote! {
+        {
+            let mut variants = vec![];
+
+            #variants
+
+            #combinator(variants)
+        }
+    }
+}
+
+// NOTE: doc comments have a leading space on each line, which is not a part of the description.
+fn description(attrs: &[Attribute]) -> TokenStream2 {
+    let (lines, _) = extract_doc_lines_and_cfg_attrs(attrs);
+
+    let description = lines
+        .iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let description = description.trim();
+
+    if description.is_empty() {
+        quote! { None }
+    } else {
+        quote! { Some(#description) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_struct() {
+        let input: DeriveInput = syn::parse_quote! {
+            /// Server config.
+            #[serde(rename_all = "camelCase", deny_unknown_fields)]
+            struct Server<T> {
+                /// Host name.
+                ///
+                /// Or IP address.
+                host_name: String,
+                #[serde(default)]
+                port: u16,
+                #[serde(skip)]
+                cache: T,
+                tls: Option<T>,
+            }
+        };
+
+        let actual = expand(input);
+
+        let expected: TokenStream2 = syn::parse_quote! {
+            #[automatically_derived]
+            impl<T> konfig::JsonSchema for Server<T> where T: konfig::JsonSchema {
+                fn schema_name() -> Option<std::borrow::Cow<'static, str>> {
+                    Some(std::borrow::Cow::Borrowed(std::any::type_name::<Self>()))
+                }
+
+                fn json_schema(
+                    generator: &mut konfig::json_schema::SchemaGenerator
+                ) -> konfig::Value {
+                    konfig::json_schema::describe(
+                        {
+                            let mut object = konfig::json_schema::ObjectSchema::default();
+
+                            object.property(
+                                "hostName",
+                                generator.subschema_for::<String>(),
+                                !<String as konfig::JsonSchema>::is_optional(),
+                                Some("Host name.\n\nOr IP address.")
+                            );
+                            object.property(
+                                "port",
+                                generator.subschema_for::<u16>(),
+                                false,
+                                None
+                            );
+                            object.property(
+                                "tls",
+                                generator.subschema_for::<Option<T> >(),
+                                !<Option<T> as konfig::JsonSchema>::is_optional(),
+                                None
+                            );
+                            object.deny_unknown_fields();
+
+                            object.into_schema()
+                        },
+                        Some("Server config.")
+                    )
+                }
+            }
+        };
+
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn expand_untagged_enum() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[serde(untagged)]
+            enum Port {
+                /// Port number.
+                Number(u16),
+                Range(u16, u16),
+                None
+            }
+        };
+
+        let actual = expand(input);
+
+        let expected: TokenStream2 = syn::parse_quote! {
+            #[automatically_derived]
+            impl konfig::JsonSchema for Port {
+                fn schema_name() -> Option<std::borrow::Cow<'static, str>> {
+                    Some(std::borrow::Cow::Borrowed(std::any::type_name::<Self>()))
+                }
+
+                fn json_schema(
+                    generator: &mut konfig::json_schema::SchemaGenerator
+                ) -> konfig::Value {
+                    konfig::json_schema::describe(
+                        {
+                            let mut variants = vec![];
+
+                            variants.push(konfig::json_schema::describe(
+                                konfig::json_schema::describe(
+                                    generator.subschema_for::<u16>(),
+                                    None
+                                ),
+                                Some("Port number.")
+                            ));
+                            variants.push(konfig::json_schema::describe(
+                                {
+                                    let mut items = vec![];
+
+                                    items.push(konfig::json_schema::describe(
+                                        generator.subschema_for::<u16>(),
+                                        None
+                                    ));
+                                    items.push(konfig::json_schema::describe(
+                                        generator.subschema_for::<u16>(),
+                                        None
+                                    ));
+
+                                    konfig::json_schema::tuple_schema(items)
+                                },
+                                None
+                            ));
+                            variants.push(konfig::json_schema::describe(
+                                konfig::json_schema::schema(
+                                    [("type", konfig::Value::from("null"))]
+                                ),
+                                None
+                            ));
+
+                            konfig::json_schema::any_of(variants)
+                        },
+                        None
+                    )
+                }
+            }
+        };
+
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn expand_flattened_field() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct Server {
+                #[serde(flatten)]
+                extra: HashMap<String, String>,
+            }
+        };
+
+        assert!(expand(input)
+            .to_string()
+            .contains("flattened fields are not supported in JSON schemas"));
+    }
+}
@@ -1,6 +1,4 @@
-mod serde_attrs;
-
-use self::serde_attrs::{SerdeAttributesInfo, SerdeContainerAttributesInfo};
+use crate::serde_attrs::{SerdeAttributesInfo, SerdeContainerAttributesInfo};
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{quote, quote_spanned};
 use syn::visit::Visit;
@@ -330,6 +328,22 @@ fn tuple_enum_variant_field_name(idx: usize) -> Ident {
 }
 
 fn extract_docs_and_cfg_attrs(attrs: &[Attribute]) -> (TokenStream2, Vec<&Attribute>) {
+    let (docs, cfg_attrs) = extract_doc_lines_and_cfg_attrs(attrs);
+
+    let docs = if docs.is_empty() {
+        TokenStream2::default()
+    } else {
+        let docs = docs.join("\n");
+
+        quote! { docs.insert(path.clone(), #docs.to_string()); }
+    };
+
+    (docs, cfg_attrs)
+}
+
+pub(crate) fn extract_doc_lines_and_cfg_attrs(
+    attrs: &[Attribute],
+) -> (Vec<String>, Vec<&Attribute>) {
     let mut docs = vec![];
     let mut cfg_attrs = vec![];
 
@@ -349,14 +363,6 @@ fn extract_docs_and_cfg_attrs(attrs: &[Attribute]) -> (TokenStream2, Vec<&Attrib
         }
     }
 
-    let docs = if docs.is_empty() {
-        TokenStream2::default()
-    } else {
-        let docs = docs.join("\n");
-
-        quote! { docs.insert(path.clone(), #docs.to_string()); }
-    };
-
     (docs, cfg_attrs)
 }
 
@@ -1,5 +1,7 @@
+mod derive_json_schema;
 mod derive_with_docs;
 mod konfig;
+mod serde_attrs;
 
 use proc_macro::TokenStream;
 use syn::{DeriveInput, LitStr};
@@ -17,3 +19,10 @@ pub fn derive_with_docs(input: TokenStream) -> TokenStream {
 
     self::derive_with_docs::ImplCodegen::expand(input).into()
 }
+
+#[proc_macro_derive(JsonSchema)]
+pub fn derive_json_schema(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    self::derive_json_schema::expand(input).into()
+}
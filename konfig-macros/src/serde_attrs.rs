@@ -6,8 +6,14 @@ use syn::Attribute;
 #[derive(FromAttributes, Debug, PartialEq, Default)]
 #[darling(attributes(serde), allow_unknown_fields)]
 pub(crate) struct SerdeContainerAttributesInfo {
+    rename: Option<Rename>,
     rename_all: Option<Rename>,
     untagged: Option<bool>,
+    tag: Option<String>,
+    content: Option<String>,
+    transparent: Option<bool>,
+    deny_unknown_fields: Option<bool>,
+    default: Option<DefaultValue>,
 }
 
 impl From<&Vec<Attribute>> for SerdeContainerAttributesInfo {
@@ -21,6 +27,33 @@ impl SerdeContainerAttributesInfo {
         self.untagged.unwrap_or(false)
     }
 
+    pub(crate) fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    pub(crate) fn content(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+
+    pub(crate) fn transparent(&self) -> bool {
+        self.transparent.unwrap_or(false)
+    }
+
+    pub(crate) fn deny_unknown_fields(&self) -> bool {
+        self.deny_unknown_fields.unwrap_or(false)
+    }
+
+    pub(crate) fn maybe_rename(&self, name: String) -> String {
+        match self.rename {
+            Some(ref rename) => rename.0.to_string(),
+            None => name,
+        }
+    }
+
+    pub(crate) fn has_default(&self) -> bool {
+        self.default.is_some()
+    }
+
     pub(crate) fn maybe_rename_field(&self, name: String) -> String {
         match self.rename_rule() {
             Some(rule) => rule.apply_to_field(&name),
@@ -47,6 +80,10 @@ impl SerdeContainerAttributesInfo {
 pub(crate) struct SerdeAttributesInfo {
     #[darling(rename = "rename")]
     rename: Option<Rename>,
+    default: Option<DefaultValue>,
+    skip: Option<bool>,
+    skip_deserializing: Option<bool>,
+    flatten: Option<bool>,
 }
 
 impl From<&Vec<Attribute>> for SerdeAttributesInfo {
@@ -62,6 +99,32 @@ impl SerdeAttributesInfo {
             None => name,
         }
     }
+
+    pub(crate) fn has_default(&self) -> bool {
+        self.default.is_some()
+    }
+
+    pub(crate) fn is_skipped(&self) -> bool {
+        self.skip.unwrap_or(false) || self.skip_deserializing.unwrap_or(false)
+    }
+
+    pub(crate) fn is_flattened(&self) -> bool {
+        self.flatten.unwrap_or(false)
+    }
+}
+
+// NOTE: `default` is either a flag or a path to the function producing the default value.
+#[derive(Debug, PartialEq)]
+struct DefaultValue;
+
+impl FromMeta for DefaultValue {
+    fn from_word() -> darling::Result<Self> {
+        Ok(Self)
+    }
+
+    fn from_string(_value: &str) -> darling::Result<Self> {
+        Ok(Self)
+    }
 }
 
 #[derive(FromMeta)]
@@ -101,7 +164,8 @@ mod tests {
             attr_info,
             SerdeContainerAttributesInfo {
                 rename_all: Some(Rename("foo".into())),
-                untagged: Some(true)
+                untagged: Some(true),
+                ..Default::default()
             }
         );
 
@@ -117,9 +181,21 @@ mod tests {
             attr_info,
             SerdeContainerAttributesInfo {
                 rename_all: Some(Rename("foo".into())),
-                untagged: Some(true)
+                untagged: Some(true),
+                ..Default::default()
             }
         );
+
+        let src: ItemEnum = syn::parse_quote! {
+            #[serde(tag = "type", content = "value", rename = "Bar")]
+            enum FooBar {}
+        };
+
+        let attr_info = SerdeContainerAttributesInfo::from(&src.attrs);
+
+        assert_eq!(attr_info.tag(), Some("type"));
+        assert_eq!(attr_info.content(), Some("value"));
+        assert_eq!(attr_info.maybe_rename("FooBar".into()), "Bar");
     }
 
     #[test]
@@ -135,6 +211,7 @@ mod tests {
             attr_info,
             SerdeAttributesInfo {
                 rename: Some(Rename("foo".into())),
+                ..Default::default()
             }
         );
 
@@ -149,6 +226,7 @@ mod tests {
             attr_info,
             SerdeAttributesInfo {
                 rename: Some(Rename("foo".into())),
+                ..Default::default()
             }
         );
     }
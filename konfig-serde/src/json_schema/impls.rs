@@ -0,0 +1,346 @@
+use super::{any_of, schema, tuple_schema, JsonSchema, SchemaGenerator};
+use indexmap::{IndexMap, IndexSet};
+use konfig_edit::value::Value;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::ffi::{CStr, CString, OsStr, OsString};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+};
+use std::path::{Path as StdPath, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+
+macro_rules! impl_for_type {
+    ( $ty:literal => $( $Ty:ty ),* ) => {
+        $(
+            impl JsonSchema for $Ty {
+                #[inline]
+                fn json_schema(_generator: &mut SchemaGenerator) -> Value {
+                    schema([("type", $ty.into())])
+                }
+            }
+        )*
+    };
+}
+
+impl_for_type!("boolean" => bool);
+impl_for_type!("integer" => i64, i128, isize, NonZeroI64, NonZeroI128, NonZeroIsize);
+impl_for_type!("number" => f32, f64);
+impl_for_type!("null" => ());
+
+impl_for_type!(
+    "string" => str, String, CStr, CString, OsStr, OsString, StdPath, PathBuf, IpAddr, Ipv4Addr,
+    Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6
+);
+
+macro_rules! impl_for_bounded_int {
+    ( $( $Ty:ty => $Bounds:ty ),* ) => {
+        $(
+            impl JsonSchema for $Ty {
+                #[inline]
+                fn json_schema(_generator: &mut SchemaGenerator) -> Value {
+                    schema([
+                        ("type", "integer".into()),
+                        ("minimum", <$Bounds>::MIN.into()),
+                        ("maximum", <$Bounds>::MAX.into()),
+                    ])
+                }
+            }
+        )*
+    };
+}
+
+impl_for_bounded_int! {
+    i8 => i8,
+    i16 => i16,
+    i32 => i32,
+    u8 => u8,
+    u16 => u16,
+    u32 => u32,
+    NonZeroI8 => i8,
+    NonZeroI16 => i16,
+    NonZeroI32 => i32,
+    NonZeroU8 => u8,
+    NonZeroU16 => u16,
+    NonZeroU32 => u32
+}
+
+macro_rules! impl_for_unsigned {
+    ( $( $Ty:ty => $min:literal ),* ) => {
+        $(
+            impl JsonSchema for $Ty {
+                #[inline]
+                fn json_schema(_generator: &mut SchemaGenerator) -> Value {
+                    schema([("type", "integer".into()), ("minimum", $min.into())])
+                }
+            }
+        )*
+    };
+}
+
+impl_for_unsigned! {
+    u64 => 0u64,
+    u128 => 0u64,
+    usize => 0u64,
+    NonZeroU64 => 1u64,
+    NonZeroU128 => 1u64,
+    NonZeroUsize => 1u64
+}
+
+impl JsonSchema for char {
+    #[inline]
+    fn json_schema(_generator: &mut SchemaGenerator) -> Value {
+        schema([
+            ("type", "string".into()),
+            ("minLength", 1u64.into()),
+            ("maxLength", 1u64.into()),
+        ])
+    }
+}
+
+macro_rules! impl_for_ref {
+    ( $( $impl_desc:tt )* ) => {
+        impl $( $impl_desc )* {
+            #[inline]
+            fn schema_name() -> Option<Cow<'static, str>> {
+                T::schema_name()
+            }
+
+            #[inline]
+            fn is_optional() -> bool {
+                T::is_optional()
+            }
+
+            #[inline]
+            fn json_schema(generator: &mut SchemaGenerator) -> Value {
+                T::json_schema(generator)
+            }
+        }
+    };
+}
+
+impl_for_ref!(<'i, T> JsonSchema for &'i T where T: JsonSchema + ?Sized);
+impl_for_ref!(<'i, T> JsonSchema for &'i mut T where T: JsonSchema + ?Sized);
+impl_for_ref!(<T> JsonSchema for Box<T> where T: JsonSchema + ?Sized);
+impl_for_ref!(<T> JsonSchema for Rc<T> where T: JsonSchema + ?Sized);
+impl_for_ref!(<T> JsonSchema for Arc<T> where T: JsonSchema + ?Sized);
+impl_for_ref!(<'i, T> JsonSchema for Cow<'i, T> where T: JsonSchema + ToOwned + ?Sized);
+impl_for_ref!(<T> JsonSchema for Cell<T> where T: JsonSchema + Copy);
+impl_for_ref!(<T> JsonSchema for RefCell<T> where T: JsonSchema + ?Sized);
+impl_for_ref!(<T> JsonSchema for Mutex<T> where T: JsonSchema + ?Sized);
+impl_for_ref!(<T> JsonSchema for RwLock<T> where T: JsonSchema + ?Sized);
+
+macro_rules! impl_for_seq {
+    ( $unique:literal => $( $impl_desc:tt )* ) => {
+        impl $( $impl_desc )* {
+            fn json_schema(generator: &mut SchemaGenerator) -> Value {
+                let items = generator.subschema_for::<T>();
+
+                match $unique {
+                    true => schema([
+                        ("type", "array".into()),
+                        ("items", items),
+                        ("uniqueItems", true.into()),
+                    ]),
+                    false => schema([("type", "array".into()), ("items", items)]),
+                }
+            }
+        }
+    };
+}
+
+impl_for_seq!(false => <T> JsonSchema for [T] where T: JsonSchema);
+impl_for_seq!(false => <T> JsonSchema for Vec<T> where T: JsonSchema);
+impl_for_seq!(false => <T> JsonSchema for VecDeque<T> where T: JsonSchema);
+impl_for_seq!(false => <T> JsonSchema for LinkedList<T> where T: JsonSchema);
+impl_for_seq!(false => <T> JsonSchema for BinaryHeap<T> where T: JsonSchema + Ord);
+impl_for_seq!(true => <T> JsonSchema for BTreeSet<T> where T: JsonSchema + Ord);
+impl_for_seq!(true => <T, S> JsonSchema for HashSet<T, S> where T: JsonSchema);
+impl_for_seq!(true => <T, S> JsonSchema for IndexSet<T, S> where T: JsonSchema);
+
+impl<T, const N: usize> JsonSchema for [T; N]
+where
+    T: JsonSchema,
+{
+    fn json_schema(generator: &mut SchemaGenerator) -> Value {
+        let len = N as u64;
+
+        schema([
+            ("type", "array".into()),
+            ("items", generator.subschema_for::<T>()),
+            ("minItems", len.into()),
+            ("maxItems", len.into()),
+        ])
+    }
+}
+
+macro_rules! impl_for_tuple {
+    ( $( ( $( $Ty:ident )* ) ),* ) => {
+        $(
+            impl<$($Ty),*> JsonSchema for ($($Ty,)*)
+            where
+                $( $Ty: JsonSchema ),*
+            {
+                fn json_schema(generator: &mut SchemaGenerator) -> Value {
+                    tuple_schema(vec![$( generator.subschema_for::<$Ty>() ),*])
+                }
+            }
+        )*
+    };
+}
+
+impl_for_tuple! {
+    (T0),
+    (T0 T1),
+    (T0 T1 T2),
+    (T0 T1 T2 T3),
+    (T0 T1 T2 T3 T4),
+    (T0 T1 T2 T3 T4 T5),
+    (T0 T1 T2 T3 T4 T5 T6),
+    (T0 T1 T2 T3 T4 T5 T6 T7),
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8),
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9),
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10),
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11),
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12),
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13),
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14),
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15)
+}
+
+macro_rules! impl_for_map {
+    ( $( $impl_desc:tt )* ) => {
+        impl $( $impl_desc )* {
+            fn json_schema(generator: &mut SchemaGenerator) -> Value {
+                schema([
+                    ("type", "object".into()),
+                    ("additionalProperties", generator.subschema_for::<V>()),
+                ])
+            }
+        }
+    };
+}
+
+impl_for_map!(<K, V, S> JsonSchema for HashMap<K, V, S> where V: JsonSchema);
+impl_for_map!(<K, V, S> JsonSchema for IndexMap<K, V, S> where V: JsonSchema);
+impl_for_map!(<K, V> JsonSchema for BTreeMap<K, V> where V: JsonSchema);
+
+impl<T> JsonSchema for Option<T>
+where
+    T: JsonSchema,
+{
+    #[inline]
+    fn is_optional() -> bool {
+        true
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Value {
+        any_of(vec![
+            generator.subschema_for::<T>(),
+            schema([("type", "null".into())]),
+        ])
+    }
+}
+
+impl JsonSchema for Value {
+    #[inline]
+    fn json_schema(_generator: &mut SchemaGenerator) -> Value {
+        Value::Bool(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema_of<T>() -> serde_json::Value
+    where
+        T: JsonSchema + ?Sized,
+    {
+        serde_json::to_value(SchemaGenerator::default().subschema_for::<T>()).unwrap()
+    }
+
+    #[test]
+    fn primitives() {
+        assert_eq!(schema_of::<bool>(), json!({ "type": "boolean" }));
+        assert_eq!(schema_of::<f32>(), json!({ "type": "number" }));
+        assert_eq!(schema_of::<()>(), json!({ "type": "null" }));
+        assert_eq!(schema_of::<PathBuf>(), json!({ "type": "string" }));
+
+        assert_eq!(
+            schema_of::<i8>(),
+            json!({ "type": "integer", "minimum": -128, "maximum": 127 })
+        );
+
+        assert_eq!(
+            schema_of::<NonZeroU16>(),
+            json!({ "type": "integer", "minimum": 0, "maximum": 65535 })
+        );
+
+        assert_eq!(
+            schema_of::<NonZeroU64>(),
+            json!({ "type": "integer", "minimum": 1 })
+        );
+
+        assert_eq!(
+            schema_of::<char>(),
+            json!({ "type": "string", "minLength": 1, "maxLength": 1 })
+        );
+
+        assert_eq!(schema_of::<Value>(), json!(true));
+    }
+
+    #[test]
+    fn containers() {
+        assert_eq!(
+            schema_of::<Vec<u64>>(),
+            json!({ "type": "array", "items": { "type": "integer", "minimum": 0 } })
+        );
+
+        assert_eq!(
+            schema_of::<BTreeSet<String>>(),
+            json!({ "type": "array", "items": { "type": "string" }, "uniqueItems": true })
+        );
+
+        assert_eq!(
+            schema_of::<[bool; 2]>(),
+            json!({
+                "type": "array",
+                "items": { "type": "boolean" },
+                "minItems": 2,
+                "maxItems": 2
+            })
+        );
+
+        assert_eq!(
+            schema_of::<(bool, String)>(),
+            json!({
+                "type": "array",
+                "prefixItems": [{ "type": "boolean" }, { "type": "string" }],
+                "items": false,
+                "minItems": 2,
+                "maxItems": 2
+            })
+        );
+
+        assert_eq!(
+            schema_of::<HashMap<String, bool>>(),
+            json!({ "type": "object", "additionalProperties": { "type": "boolean" } })
+        );
+
+        assert_eq!(
+            schema_of::<Option<bool>>(),
+            json!({ "anyOf": [{ "type": "boolean" }, { "type": "null" }] })
+        );
+
+        assert_eq!(schema_of::<Box<str>>(), schema_of::<str>());
+        assert!(<Option<bool>>::is_optional());
+        assert!(<Arc<Option<bool>>>::is_optional());
+        assert!(!<Vec<Option<bool>>>::is_optional());
+    }
+}
@@ -0,0 +1,467 @@
+mod impls;
+
+use indexmap::IndexMap;
+use konfig_edit::value::{Value, ValueCell};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+pub const DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+// NOTE: describes the serde representation of the type, so the schema can be used with JSON
+// tooling, e.g. for completion in editors. Named types are put into `$defs` and referenced,
+// which allows recursive types. The name identifies the type, so it should be unique, e.g. the
+// `type_name` of the type. Keys of `$defs` are derived from the names.
+pub trait JsonSchema {
+    #[inline]
+    fn schema_name() -> Option<Cow<'static, str>> {
+        None
+    }
+
+    // NOTE: optional fields are not required in the schemas of the structures containing them.
+    #[inline]
+    fn is_optional() -> bool {
+        false
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Value;
+}
+
+#[derive(Default)]
+pub struct SchemaGenerator {
+    root: Option<Cow<'static, str>>,
+    defs: IndexMap<String, ValueCell>,
+    def_names: HashMap<Cow<'static, str>, String>,
+}
+
+impl SchemaGenerator {
+    pub fn subschema_for<T>(&mut self) -> Value
+    where
+        T: JsonSchema + ?Sized,
+    {
+        let Some(name) = T::schema_name() else {
+            return T::json_schema(self);
+        };
+
+        if self.root.as_ref() == Some(&name) {
+            return schema([("$ref", "#".into())]);
+        }
+
+        let def_name = match self.def_names.get(&name) {
+            Some(def_name) => def_name.clone(),
+            None => {
+                let def_name = self.unique_def_name(&name);
+
+                self.def_names.insert(name, def_name.clone());
+
+                // NOTE: the placeholder stops the recursion for recursive types.
+                self.defs.insert(def_name.clone(), Value::Null.into());
+
+                let schema = T::json_schema(self);
+
+                self.defs.insert(def_name.clone(), schema.into());
+
+                def_name
+            }
+        };
+
+        schema([("$ref", format!("#/$defs/{def_name}").into())])
+    }
+
+    // NOTE: types are named without module paths, e.g. `Wrapper_Vec_u16` for
+    // `crate::Wrapper<alloc::vec::Vec<u16>>`, unless another type already has the name.
+    fn unique_def_name(&self, name: &str) -> String {
+        let short = def_name(name, true);
+
+        if !self.defs.contains_key(&short) {
+            return short;
+        }
+
+        let long = def_name(name, false);
+
+        (1..)
+            .map(|i| match i {
+                1 => long.clone(),
+                i => format!("{long}_{i}"),
+            })
+            .find(|def_name| !self.defs.contains_key(def_name))
+            .unwrap()
+    }
+}
+
+pub fn json_schema_for<T>() -> Value
+where
+    T: JsonSchema + ?Sized,
+{
+    let mut generator = SchemaGenerator {
+        root: T::schema_name(),
+        ..Default::default()
+    };
+
+    let mut root = IndexMap::new();
+
+    root.insert("$schema".into(), Value::from(DRAFT_2020_12).into());
+
+    match T::json_schema(&mut generator) {
+        Value::Map(map) => root.extend(map),
+        Value::Bool(true) => (),
+        schema => {
+            root.insert("allOf".into(), Value::Sequence(vec![schema.into()]).into());
+        }
+    }
+
+    if !generator.defs.is_empty() {
+        root.insert("$defs".into(), Value::Map(generator.defs).into());
+    }
+
+    Value::Map(root)
+}
+
+// NOTE: only ASCII alphanumerics and underscores are kept, so the name can be used in JSON
+// pointers and URI fragments without escaping.
+fn def_name(type_name: &str, strip_module_paths: bool) -> String {
+    type_name
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':'))
+        .filter_map(|segment| match strip_module_paths {
+            true => segment.rsplit("::").next().map(Cow::Borrowed),
+            false => Some(Cow::Owned(segment.replace("::", "_"))),
+        })
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+// NOTE: the helpers below are used by the derived implementations, but can be used by manual
+// implementations as well.
+pub fn schema<'k>(entries: impl IntoIterator<Item = (&'k str, Value)>) -> Value {
+    Value::Map(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.into()))
+            .collect(),
+    )
+}
+
+pub fn describe(schema: Value, description: Option<&str>) -> Value {
+    match (schema, description) {
+        (Value::Map(mut map), Some(description)) => {
+            map.insert("description".into(), Value::from(description).into());
+
+            Value::Map(map)
+        }
+        (schema, _) => schema,
+    }
+}
+
+pub fn tuple_schema(items: Vec<Value>) -> Value {
+    let len = items.len() as u64;
+
+    schema([
+        ("type", "array".into()),
+        (
+            "prefixItems",
+            Value::Sequence(items.into_iter().map(Into::into).collect()),
+        ),
+        ("items", false.into()),
+        ("minItems", len.into()),
+        ("maxItems", len.into()),
+    ])
+}
+
+pub fn unit_variant_schema(name: &str) -> Value {
+    schema([("const", name.into())])
+}
+
+// NOTE: externally tagged variants are objects with the variant name as the only property.
+pub fn tagged_variant_schema(name: &str, value: Value) -> Value {
+    let mut object = ObjectSchema::default();
+
+    object.property(name, value, true, None);
+    object.deny_unknown_fields();
+    object.into_schema()
+}
+
+// NOTE: the tag is a property of the variant object. Variants that are not objects, e.g. newtype
+// variants of structures, are combined with the tag.
+pub fn internally_tagged_variant_schema(tag: &str, name: &str, value: Option<Value>) -> Value {
+    match value {
+        Some(Value::Map(mut map)) if map.contains_key("properties") => {
+            if let Some(Value::Map(properties)) =
+                map.get_mut("properties").map(|p| p.as_value_mut())
+            {
+                properties.shift_insert(0, tag.into(), unit_variant_schema(name).into());
+            }
+
+            match map.get_mut("required").map(|r| r.as_value_mut()) {
+                Some(Value::Sequence(required)) => required.insert(0, Value::from(tag).into()),
+                _ => {
+                    map.insert(
+                        "required".into(),
+                        Value::Sequence(vec![Value::from(tag).into()]).into(),
+                    );
+                }
+            }
+
+            Value::Map(map)
+        }
+        value => {
+            let mut object = ObjectSchema::default();
+
+            object.property(tag, unit_variant_schema(name), true, None);
+
+            match value {
+                Some(value) => schema([(
+                    "allOf",
+                    Value::Sequence(vec![object.into_schema().into(), value.into()]),
+                )]),
+                None => object.into_schema(),
+            }
+        }
+    }
+}
+
+pub fn adjacently_tagged_variant_schema(
+    tag: &str,
+    content: &str,
+    name: &str,
+    value: Option<Value>,
+) -> Value {
+    let mut object = ObjectSchema::default();
+
+    object.property(tag, unit_variant_schema(name), true, None);
+
+    if let Some(value) = value {
+        object.property(content, value, true, None);
+    }
+
+    object.into_schema()
+}
+
+pub fn one_of(schemas: Vec<Value>) -> Value {
+    schema([(
+        "oneOf",
+        Value::Sequence(schemas.into_iter().map(Into::into).collect()),
+    )])
+}
+
+pub fn any_of(schemas: Vec<Value>) -> Value {
+    schema([(
+        "anyOf",
+        Value::Sequence(schemas.into_iter().map(Into::into).collect()),
+    )])
+}
+
+#[derive(Default)]
+pub struct ObjectSchema {
+    properties: IndexMap<String, ValueCell>,
+    required: Vec<ValueCell>,
+    deny_unknown_fields: bool,
+}
+
+impl ObjectSchema {
+    pub fn property(
+        &mut self,
+        name: &str,
+        schema: Value,
+        required: bool,
+        description: Option<&str>,
+    ) {
+        self.properties
+            .insert(name.into(), describe(schema, description).into());
+
+        if required {
+            self.required.push(Value::from(name).into());
+        }
+    }
+
+    #[inline]
+    pub fn deny_unknown_fields(&mut self) {
+        self.deny_unknown_fields = true;
+    }
+
+    pub fn into_schema(self) -> Value {
+        let mut schema = IndexMap::new();
+
+        schema.insert("type".into(), Value::from("object").into());
+        schema.insert("properties".into(), Value::Map(self.properties).into());
+
+        if !self.required.is_empty() {
+            schema.insert("required".into(), Value::Sequence(self.required).into());
+        }
+
+        if self.deny_unknown_fields {
+            schema.insert("additionalProperties".into(), Value::from(false).into());
+        }
+
+        Value::Map(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct Named<const N: usize>;
+
+    impl<const N: usize> JsonSchema for Named<N> {
+        fn schema_name() -> Option<Cow<'static, str>> {
+            Some(["a::Node", "b::Node", "a::Node<b::Node>", "a_Node"][N].into())
+        }
+
+        fn json_schema(generator: &mut SchemaGenerator) -> Value {
+            let mut object = ObjectSchema::default();
+
+            object.property("next", generator.subschema_for::<Self>(), false, None);
+            object.into_schema()
+        }
+    }
+
+    fn to_json(value: Value) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn def_names() {
+        let mut generator = SchemaGenerator::default();
+
+        let refs = [
+            generator.subschema_for::<Named<0>>(),
+            generator.subschema_for::<Named<1>>(),
+            generator.subschema_for::<Named<2>>(),
+            generator.subschema_for::<Named<3>>(),
+            generator.subschema_for::<Named<0>>(),
+        ];
+
+        assert_eq!(
+            refs.into_iter().map(to_json).collect::<Vec<_>>(),
+            [
+                json!({ "$ref": "#/$defs/Node" }),
+                json!({ "$ref": "#/$defs/b_Node" }),
+                json!({ "$ref": "#/$defs/Node_Node" }),
+                json!({ "$ref": "#/$defs/a_Node" }),
+                json!({ "$ref": "#/$defs/Node" }),
+            ]
+        );
+
+        assert_eq!(
+            generator.defs.keys().collect::<Vec<_>>(),
+            ["Node", "b_Node", "Node_Node", "a_Node"]
+        );
+
+        assert_eq!(
+            def_name("crate::Wrapper<alloc::vec::Vec<u16>>", true),
+            "Wrapper_Vec_u16"
+        );
+
+        assert_eq!(
+            def_name("crate::Wrapper<[u8; 4]>", false),
+            "crate_Wrapper_u8_4"
+        );
+    }
+
+    #[test]
+    fn root_schemas() {
+        assert_eq!(
+            to_json(json_schema_for::<Named<0>>()),
+            json!({
+                "$schema": DRAFT_2020_12,
+                "type": "object",
+                "properties": { "next": { "$ref": "#" } }
+            })
+        );
+
+        assert_eq!(
+            to_json(json_schema_for::<Value>()),
+            json!({ "$schema": DRAFT_2020_12 })
+        );
+
+        assert_eq!(
+            to_json(json_schema_for::<Vec<Named<1>>>()),
+            json!({
+                "$schema": DRAFT_2020_12,
+                "type": "array",
+                "items": { "$ref": "#/$defs/Node" },
+                "$defs": {
+                    "Node": {
+                        "type": "object",
+                        "properties": { "next": { "$ref": "#/$defs/Node" } }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn variant_schemas() {
+        let object = || {
+            let mut object = ObjectSchema::default();
+
+            object.property("size", schema([("type", "integer".into())]), true, None);
+            object.into_schema()
+        };
+
+        assert_eq!(
+            to_json(internally_tagged_variant_schema(
+                "kind",
+                "Disk",
+                Some(object())
+            )),
+            json!({
+                "type": "object",
+                "properties": {
+                    "kind": { "const": "Disk" },
+                    "size": { "type": "integer" }
+                },
+                "required": ["kind", "size"]
+            })
+        );
+
+        assert_eq!(
+            to_json(internally_tagged_variant_schema(
+                "kind",
+                "Disk",
+                Some(schema([("$ref", "#/$defs/Disk".into())]))
+            )),
+            json!({
+                "allOf": [
+                    {
+                        "type": "object",
+                        "properties": { "kind": { "const": "Disk" } },
+                        "required": ["kind"]
+                    },
+                    { "$ref": "#/$defs/Disk" }
+                ]
+            })
+        );
+
+        assert_eq!(
+            to_json(adjacently_tagged_variant_schema("t", "c", "Memory", None)),
+            json!({
+                "type": "object",
+                "properties": { "t": { "const": "Memory" } },
+                "required": ["t"]
+            })
+        );
+
+        assert_eq!(
+            to_json(tagged_variant_schema("Disk", object())),
+            json!({
+                "type": "object",
+                "properties": {
+                    "Disk": {
+                        "type": "object",
+                        "properties": { "size": { "type": "integer" } },
+                        "required": ["size"]
+                    }
+                },
+                "required": ["Disk"],
+                "additionalProperties": false
+            })
+        );
+
+        assert_eq!(
+            to_json(describe(true.into(), Some("Anything."))),
+            json!(true)
+        );
+    }
+}
@@ -3,5 +3,8 @@
 #[cfg(feature = "ser-docs")]
 pub mod ser_docs;
 
+#[cfg(feature = "json-schema")]
+pub mod json_schema;
+
 pub mod de;
 pub mod ser;
@@ -5,6 +5,7 @@ mod seq;
 mod serializer;
 
 use konfig_edit::error::Result;
+use konfig_edit::serializer::formatting::LineEnding;
 use serde::ser::Serialize;
 
 pub use self::serializer::Serializer;
@@ -20,3 +21,10 @@ where
 
     Ok(out)
 }
+
+pub fn to_string_with_line_ending<T>(value: &T, line_ending: LineEnding) -> Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    to_string(value).map(|out| line_ending.apply_owned(out))
+}
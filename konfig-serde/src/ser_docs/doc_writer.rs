@@ -1,4 +1,4 @@
-use konfig_edit::serializer::components::doc_line_leading_gt_sign_pos;
+use konfig_edit::serializer::components::{code_block_lines, doc_line_leading_gt_sign_pos};
 use konfig_edit::serializer::formatting::{DocLineEscape, MarkdowDocLineEscape};
 use konfig_edit::value::Path;
 use std::cell::RefCell;
@@ -66,8 +66,16 @@ impl DocWriter {
 
         self.write_header(out, header, nesting_level);
 
-        for line in body.lines() {
-            if let Some(gt_sign_pos) = doc_line_leading_gt_sign_pos(line) {
+        let code_block_lines = code_block_lines(body);
+
+        for (line_idx, line) in body.lines().enumerate() {
+            let gt_sign_pos = if code_block_lines[line_idx] {
+                None
+            } else {
+                doc_line_leading_gt_sign_pos(line)
+            };
+
+            if let Some(gt_sign_pos) = gt_sign_pos {
                 let mut line = line.to_string();
 
                 MarkdowDocLineEscape.escape(&mut line, gt_sign_pos);
@@ -3,6 +3,7 @@ mod with_docs;
 
 use crate::ser::Serializer;
 use konfig_edit::error::Result;
+use konfig_edit::serializer::formatting::LineEnding;
 use serde::ser::Serialize;
 
 pub use self::with_docs::WithDocs;
@@ -23,3 +24,10 @@ where
 
     Ok(out)
 }
+
+pub fn to_string_with_docs_and_line_ending<T>(value: &T, line_ending: LineEnding) -> Result<String>
+where
+    T: Serialize + WithDocs + ?Sized,
+{
+    to_string_with_docs(value).map(|out| line_ending.apply_owned(out))
+}
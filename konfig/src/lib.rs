@@ -3,9 +3,16 @@
 #[doc(inline)]
 pub use konfig_edit::error;
 
+#[doc(inline)]
+pub use konfig_edit::loader;
+
 #[doc(inline)]
 pub use konfig_edit::parser;
 
+#[doc(inline)]
+#[cfg(feature = "schema")]
+pub use konfig_edit::schema;
+
 #[doc(inline)]
 pub use konfig_edit::serializer;
 
@@ -36,16 +43,23 @@ pub use konfig_serde::ser::{to_string, Serializer};
 pub use konfig_serde::ser_docs::{to_string_with_docs, WithDocs};
 
 #[doc(inline)]
-pub use konfig_edit::error::{Error, ParseError, Result};
+#[cfg(feature = "json-schema")]
+pub use konfig_serde::json_schema::{self, json_schema_for, JsonSchema};
 
 #[doc(inline)]
-pub use konfig_edit::parser::parse;
+pub use konfig_edit::error::{ConversionError, Error, ParseError, Result};
 
 #[doc(inline)]
-pub use konfig_edit::serializer::serialize;
+pub use konfig_edit::parser::{parse, parse_embedded, parse_expr};
+
+#[doc(inline)]
+pub use konfig_edit::serializer::{serialize, serialize_embedded};
 
 #[cfg(feature = "macros")]
 pub use konfig_macros::konfig;
 
 #[cfg(all(feature = "macros", feature = "ser-docs"))]
 pub use konfig_macros::WithDocs;
+
+#[cfg(all(feature = "macros", feature = "json-schema"))]
+pub use konfig_macros::JsonSchema;
@@ -1,3 +1,5 @@
+#![allow(clippy::approx_constant)]
+
 use konfig::WithDocs;
 use serde::Serialize;
 use std::collections::BTreeMap;
@@ -107,6 +109,7 @@ enum UnitVariant {
 }
 
 /// A map of integer values.
+#[allow(dead_code)]
 #[derive(Serialize, WithDocs)]
 struct IntMap {
     /// The map of integer values.
@@ -114,6 +117,7 @@ struct IntMap {
 }
 
 /// A map of string values.
+#[allow(dead_code)]
 #[derive(Serialize, WithDocs)]
 struct StringMap {
     /// The map of string values.
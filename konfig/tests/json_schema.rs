@@ -0,0 +1,295 @@
+use konfig::{json_schema_for, JsonSchema};
+use serde::Deserialize;
+use serde_json::json;
+
+/// Service configuration.
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[allow(dead_code)]
+struct Config {
+    /// Address to listen on.
+    listen_addr: String,
+    /// Upstream servers.
+    upstreams: Vec<Upstream>,
+    #[serde(default)]
+    retries: u8,
+    timeout: Option<f64>,
+    storage: Storage,
+    #[serde(skip)]
+    cache: (),
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[allow(dead_code)]
+struct Upstream {
+    #[serde(rename = "url")]
+    address: String,
+    port: Port,
+    /// Fallback for the upstream.
+    fallback: Option<Box<Upstream>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum Port {
+    Number(u16),
+    Range(u16, u16),
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[allow(dead_code)]
+enum Storage {
+    /// In-memory storage.
+    Memory,
+    #[serde(rename = "disk")]
+    Disk { path: String },
+}
+
+#[test]
+fn generate() {
+    let schema = serde_json::to_value(json_schema_for::<Config>()).unwrap();
+
+    assert_eq!(
+        schema,
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": {
+                "listenAddr": {
+                    "type": "string",
+                    "description": "Address to listen on."
+                },
+                "upstreams": {
+                    "type": "array",
+                    "items": { "$ref": "#/$defs/Upstream" },
+                    "description": "Upstream servers."
+                },
+                "retries": { "type": "integer", "minimum": 0, "maximum": 255 },
+                "timeout": { "anyOf": [{ "type": "number" }, { "type": "null" }] },
+                "storage": { "$ref": "#/$defs/Storage" }
+            },
+            "required": ["listenAddr", "upstreams", "storage"],
+            "additionalProperties": false,
+            "description": "Service configuration.",
+            "$defs": {
+                "Upstream": {
+                    "type": "object",
+                    "properties": {
+                        "url": { "type": "string" },
+                        "port": { "$ref": "#/$defs/Port" },
+                        "fallback": {
+                            "anyOf": [{ "$ref": "#/$defs/Upstream" }, { "type": "null" }],
+                            "description": "Fallback for the upstream."
+                        }
+                    },
+                    "required": ["url", "port"]
+                },
+                "Port": {
+                    "anyOf": [
+                        { "type": "integer", "minimum": 0, "maximum": 65535 },
+                        {
+                            "type": "array",
+                            "prefixItems": [
+                                { "type": "integer", "minimum": 0, "maximum": 65535 },
+                                { "type": "integer", "minimum": 0, "maximum": 65535 }
+                            ],
+                            "items": false,
+                            "minItems": 2,
+                            "maxItems": 2
+                        }
+                    ]
+                },
+                "Storage": {
+                    "oneOf": [
+                        { "const": "Memory", "description": "In-memory storage." },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "disk": {
+                                    "type": "object",
+                                    "properties": { "path": { "type": "string" } },
+                                    "required": ["path"]
+                                }
+                            },
+                            "required": ["disk"],
+                            "additionalProperties": false
+                        }
+                    ]
+                }
+            }
+        })
+    );
+}
+
+#[test]
+fn generic_and_recursive_types() {
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Tree<T> {
+        value: T,
+        children: Vec<Tree<T>>,
+    }
+
+    assert_eq!(
+        serde_json::to_value(json_schema_for::<Vec<Tree<u64>>>()).unwrap(),
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "array",
+            "items": { "$ref": "#/$defs/Tree_u64" },
+            "$defs": {
+                "Tree_u64": {
+                    "type": "object",
+                    "properties": {
+                        "value": { "type": "integer", "minimum": 0 },
+                        "children": {
+                            "type": "array",
+                            "items": { "$ref": "#/$defs/Tree_u64" }
+                        }
+                    },
+                    "required": ["value", "children"]
+                }
+            }
+        })
+    );
+
+    assert_eq!(
+        serde_json::to_value(json_schema_for::<Tree<String>>()).unwrap()["properties"]["children"]
+            ["items"],
+        json!({ "$ref": "#" })
+    );
+}
+
+#[test]
+fn types_with_same_names() {
+    mod a {
+        #[derive(konfig::JsonSchema)]
+        #[allow(dead_code)]
+        pub struct Config {
+            pub port: u16,
+        }
+    }
+
+    mod b {
+        #[derive(konfig::JsonSchema)]
+        #[allow(dead_code)]
+        pub struct Config {
+            pub host: String,
+        }
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Root {
+        a: a::Config,
+        b: b::Config,
+    }
+
+    assert_eq!(
+        serde_json::to_value(json_schema_for::<Root>()).unwrap(),
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": {
+                "a": { "$ref": "#/$defs/Config" },
+                "b": { "$ref": "#/$defs/json_schema_types_with_same_names_b_Config" }
+            },
+            "required": ["a", "b"],
+            "$defs": {
+                "Config": {
+                    "type": "object",
+                    "properties": { "port": { "type": "integer", "minimum": 0, "maximum": 65535 } },
+                    "required": ["port"]
+                },
+                "json_schema_types_with_same_names_b_Config": {
+                    "type": "object",
+                    "properties": { "host": { "type": "string" } },
+                    "required": ["host"]
+                }
+            }
+        })
+    );
+}
+
+#[test]
+fn tagged_and_transparent_types() {
+    #[derive(Deserialize, JsonSchema)]
+    #[serde(tag = "type", rename_all = "lowercase")]
+    #[allow(dead_code)]
+    enum Internal {
+        Memory,
+        Disk { path: String },
+        Remote(Url),
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    #[serde(tag = "type", content = "value")]
+    #[allow(dead_code)]
+    enum Adjacent {
+        Memory,
+        Disk(String),
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    #[serde(transparent)]
+    #[allow(dead_code)]
+    struct Url {
+        #[serde(skip)]
+        parsed: (),
+        raw: String,
+    }
+
+    assert_eq!(
+        serde_json::to_value(json_schema_for::<Internal>()).unwrap(),
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "oneOf": [
+                {
+                    "type": "object",
+                    "properties": { "type": { "const": "memory" } },
+                    "required": ["type"]
+                },
+                {
+                    "type": "object",
+                    "properties": { "type": { "const": "disk" }, "path": { "type": "string" } },
+                    "required": ["type", "path"]
+                },
+                {
+                    "allOf": [
+                        {
+                            "type": "object",
+                            "properties": { "type": { "const": "remote" } },
+                            "required": ["type"]
+                        },
+                        { "$ref": "#/$defs/Url" }
+                    ]
+                }
+            ],
+            "$defs": {
+                "Url": { "type": "string" }
+            }
+        })
+    );
+
+    assert_eq!(
+        serde_json::to_value(json_schema_for::<Adjacent>()).unwrap(),
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "oneOf": [
+                {
+                    "type": "object",
+                    "properties": { "type": { "const": "Memory" } },
+                    "required": ["type"]
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "type": { "const": "Disk" },
+                        "value": { "type": "string" }
+                    },
+                    "required": ["type", "value"]
+                }
+            ]
+        })
+    );
+}
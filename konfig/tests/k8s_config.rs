@@ -1,4 +1,6 @@
 // NOTE: generated from https://raw.githubusercontent.com/garethr/kubernetes-json-schema/master/v1.6.1-standalone/deployment.json
+#![allow(clippy::large_enum_variant, clippy::doc_lazy_continuation)]
+
 use konfig::WithDocs;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
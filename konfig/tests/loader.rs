@@ -0,0 +1,253 @@
+use indoc::indoc;
+use konfig::error::Error;
+use konfig::loader::{Env, Layer, Loader};
+use konfig::value::merge::{ErrorOnConflict, MergeOptions, RemovalMarker};
+use konfig::value::Path;
+use konfig::Value;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Config {
+    server: Server,
+    features: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+fn temp_file(name: &str, konfig: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("konfig-loader-{}-{name}", std::process::id()));
+
+    fs::write(&path, konfig).unwrap();
+
+    path
+}
+
+const DEFAULTS: &str = indoc! {"
+    > server > host = \"localhost\"
+
+    > server > port = 80
+
+    > features = [\"metrics\"]
+"};
+
+#[test]
+fn layers() {
+    let defaults = temp_file("layers-defaults.konfig", DEFAULTS);
+    let site = temp_file("layers-site.konfig", "> server > host = \"example.com\"");
+
+    let loader = Loader::new()
+        .file(&defaults)
+        .optional_file(std::env::temp_dir().join("konfig-loader-missing.konfig"))
+        .optional_file(&site)
+        .konfig("--set", "> server > port = 8080");
+
+    assert_eq!(
+        loader.load::<Config>().unwrap(),
+        Config {
+            server: Server {
+                host: "example.com".into(),
+                port: 8080
+            },
+            features: vec!["metrics".into()]
+        }
+    );
+
+    let value = loader.load_value().unwrap();
+    let source = |cell: &konfig::ValueCell| cell.lexical_info().source.as_deref().map(String::from);
+
+    assert_eq!(
+        source(&value["server"]["host"]),
+        Some(site.display().to_string())
+    );
+
+    assert_eq!(source(&value["server"]["port"]).as_deref(), Some("--set"));
+
+    assert_eq!(
+        source(&value["features"]),
+        Some(defaults.display().to_string())
+    );
+}
+
+#[test]
+fn per_layer_resolvers() {
+    let loader = Loader::new()
+        .konfig("defaults", DEFAULTS)
+        .layer(
+            Layer::konfig("site", "> features = [\"tracing\"]")
+                .resolver(ErrorOnConflict)
+                .merge_options(MergeOptions::default()),
+        )
+        .konfig("host", "> server > port = 81");
+
+    assert_eq!(
+        loader.load::<Config>().unwrap_err().to_string(),
+        "site:1:14: merge conflict at path: > features > [0]"
+    );
+
+    let loader = Loader::new()
+        .konfig("defaults", DEFAULTS)
+        .layer(Layer::konfig("site", "> server > port = 80").resolver(ErrorOnConflict))
+        .layer(
+            Layer::value("host", konfig::parse("> server > port = 81").unwrap())
+                .resolver(|_: &Path, current: Value, _: Value| Ok(current)),
+        );
+
+    assert_eq!(loader.load::<Config>().unwrap().server.port, 80);
+}
+
+#[test]
+fn per_layer_merge_options() {
+    let defaults = indoc! {"
+        > server > host = \"localhost\"
+
+        > server > port = 80
+
+        > features = [\"metrics\", \"tracing\"]
+    "};
+
+    let loader = Loader::new()
+        .konfig("defaults", defaults)
+        .konfig("site", "> features = [\"audit\"]");
+
+    assert_eq!(loader.load::<Config>().unwrap().features, ["audit"]);
+
+    // NOTE: removal markers switch off features of the previous layers.
+    let loader = Loader::new().konfig("defaults", defaults).layer(
+        Layer::konfig("site", "> features = [`Remove`]").merge_options(
+            MergeOptions::default().remove_on(RemovalMarker::UnitVariant("Remove".into())),
+        ),
+    );
+
+    assert_eq!(loader.load::<Config>().unwrap().features, ["tracing"]);
+}
+
+#[test]
+fn errors() {
+    let missing = std::env::temp_dir().join("konfig-loader-missing.konfig");
+
+    let err = Loader::new().file(&missing).load_value().unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::Load { ref origin, .. } if *origin == missing.display().to_string()
+    ));
+
+    assert_eq!(
+        Loader::new()
+            .optional_file(&missing)
+            .load_value()
+            .unwrap_err()
+            .to_string(),
+        "none of the configuration sources exist"
+    );
+
+    assert!(Loader::new()
+        .konfig("--set", "> server >")
+        .load_value()
+        .unwrap_err()
+        .to_string()
+        .starts_with("--set:  --> 1:"));
+
+    let site = temp_file(
+        "errors-site.konfig",
+        "> server > host = \"example.com\"\n\n> server > port = 100000",
+    );
+
+    assert_eq!(
+        Loader::new()
+            .konfig("defaults", DEFAULTS)
+            .file(&site)
+            .load::<Config>()
+            .unwrap_err()
+            .to_string(),
+        format!(
+            "{}:3:19: invalid value: integer `100000`, expected u16 at path: > server > port",
+            site.display()
+        )
+    );
+
+    assert_eq!(
+        Loader::new()
+            .konfig("defaults", "> server > host = \"localhost\"")
+            .load::<Config>()
+            .unwrap_err()
+            .to_string(),
+        "defaults: missing field `port` at path: > server"
+    );
+}
+
+#[test]
+fn env() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        server: Server,
+        labels: BTreeMap<String, String>,
+    }
+
+    let loader = Loader::new()
+        .konfig("defaults", DEFAULTS)
+        .env(Env::new("APP__").vars([
+            ("APP__SERVER__PORT", "8080"),
+            ("APP__SERVER__HOST", "10.0.0.1"),
+            ("APP__LABELS__TEAM", "core"),
+        ]));
+
+    assert_eq!(
+        loader.load::<Config>().unwrap(),
+        Config {
+            server: Server {
+                host: "10.0.0.1".into(),
+                port: 8080
+            },
+            labels: [("team".to_string(), "core".to_string())].into()
+        }
+    );
+
+    let value = loader.load_value().unwrap();
+
+    assert_eq!(
+        value["server"]["port"].lexical_info().source.as_deref(),
+        Some("APP__SERVER__PORT")
+    );
+
+    assert_eq!(
+        Loader::new()
+            .konfig("defaults", DEFAULTS)
+            .env(Env::new("APP__").vars([("APP__SERVER__PORT", "high")]))
+            .load::<Config>()
+            .unwrap_err()
+            .to_string(),
+        "APP__SERVER__PORT: invalid type: string \"high\", expected u16 at path: > server > port"
+    );
+}
+
+#[test]
+fn typed_accessors() {
+    let value = Loader::new()
+        .konfig("defaults", DEFAULTS)
+        .konfig("site", "> server > port = 70000")
+        .load_value()
+        .unwrap();
+
+    assert_eq!(
+        value.get_as::<String>("> server > host").unwrap(),
+        "localhost"
+    );
+
+    let Error::Conversion(err) = value.get_as::<u16>("> server > port").unwrap_err() else {
+        panic!("expected a conversion error");
+    };
+
+    assert_eq!(err.path(), "> server > port".parse::<Path>().unwrap());
+    assert_eq!(err.expected, "u16");
+    assert_eq!(err.actual, Some("integer"));
+    assert_eq!(err.source.as_deref(), Some("site"));
+    assert_eq!(err.line_col("> server > port = 70000"), Some((1, 19)));
+}
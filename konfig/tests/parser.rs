@@ -758,6 +758,148 @@ fn doc_and_expr_spacing() {
     }
 }
 
+#[test]
+fn docs_code_blocks() {
+    ok! {
+        indoc! {"
+            Example:
+
+            ```konfig
+            > foo = 1
+
+            > bar = 2
+            ```
+
+            > baz = 3
+
+            ~~~~
+            > qux = 4
+            ~~~
+            > quz = 5
+            ~~~~~
+
+            > quux = 6
+        "} =>
+        Struct({
+            "baz": UInt(3),
+            "quux": UInt(6)
+        })
+    }
+
+    ok! {
+        indoc! {"
+            > foo = 1
+
+            ``` not a `fence`
+            > bar = 2
+
+               ```
+            > baz = 3
+               ``` 
+        "} =>
+        Struct({
+            "foo": UInt(1),
+            "bar": UInt(2)
+        })
+    }
+
+    ok! {
+        indoc! {"
+            > foo = 1
+
+            ```
+            > bar = 2
+        "} =>
+        Struct({
+            "foo": UInt(1),
+            "bar": UInt(2)
+        })
+    }
+
+    ok! {
+        indoc! {"
+            ```
+            > foo = 1
+            ```~~~
+            > bar = 2
+            ``
+            > baz = 3
+            ````
+
+            > quux = 4
+        "} =>
+        Struct({
+            "quux": UInt(4)
+        })
+    }
+}
+
+#[test]
+fn embedded_blocks() {
+    let input = indoc! {r#"
+        # Configuration
+
+        Some prose with `> inline` code.
+
+        ```rust
+        > not = "konfig"
+        ```
+
+        ```konfig
+        > server > host = "localhost"
+
+        Port to listen on.
+        > server > port = 8080
+        ```
+
+        More prose.
+
+        ```konfig
+        > log_level = "info"
+        ```
+    "#};
+
+    let actual = AstValue::from(konfig::parse_embedded(input).unwrap());
+    let expected = ron::from_str::<AstValue>(
+        r#"Struct({
+            "server": Struct({ "host": String("localhost"), "port": UInt(8080) }),
+            "log_level": String("info"),
+        })"#,
+    )
+    .unwrap();
+
+    assert_eq!(actual, expected);
+
+    let actual = konfig::parse_embedded(indoc! {"
+        Prose.
+
+        ```konfig
+        > foo = 1
+
+        > foo = 2
+        ```
+    "})
+    .unwrap_err()
+    .to_string();
+
+    assert_eq!(
+        actual,
+        indoc! {"
+             --> 6:1
+              |
+            6 | > foo = 2␊
+              | ^--------^
+              |
+              = the path already has a value assigned"}
+    );
+
+    let actual = konfig::parse_embedded("No konfig here.\n")
+        .unwrap_err()
+        .to_string();
+
+    assert!(actual.contains("konfig should contain some expressions"));
+}
+
 #[test]
 fn seq_order() {
     err! {
@@ -876,6 +1018,293 @@ fn reassignment() {
     }
 }
 
+#[test]
+fn duplicate_assignment() {
+    use konfig::parser::{DuplicateAssignment, ParseOptions};
+    use konfig::value::merge::ErrorOnConflict;
+    use konfig::value::Path;
+
+    macro_rules! ok {
+        ($policy:expr, $input:expr => $expected:expr, warnings: [$($warning:expr),*]) => {{
+            let options = ParseOptions {
+                duplicate_assignment: $policy,
+                ..Default::default()
+            };
+
+            let parsed = konfig::parser::parse_with_options(indoc!($input), options).unwrap();
+            let actual = konfig::serialize(&parsed.value, Default::default()).unwrap();
+            let warnings = parsed.warnings.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+            assert_eq!(actual, indoc!($expected));
+            assert_eq!(warnings, vec![$($warning),*] as Vec<&str>);
+        }};
+    }
+
+    ok! {
+        DuplicateAssignment::LastWins,
+        "
+            > foo > bar = 1
+
+            > baz = 2
+
+            > foo > bar = 3
+        " => "
+            > foo > bar = 3
+
+            > baz = 2\
+        ",
+        warnings: ["1:1: value is overridden by the assignment at 5:1 [overridden-assignment]"]
+    }
+
+    ok! {
+        DuplicateAssignment::LastWins,
+        "
+            > foo > bar = 1
+
+            > foo > baz = 2
+
+            > foo = `Qux`
+        " => "
+            > foo = `Qux`\
+        ",
+        warnings: [
+            "1:1: value is overridden by the assignment at 5:1 [overridden-assignment]",
+            "3:1: value is overridden by the assignment at 5:1 [overridden-assignment]"
+        ]
+    }
+
+    ok! {
+        DuplicateAssignment::LastWins,
+        "
+            > foo = [1, 2]
+
+            > foo > [0] > bar = 3
+        " => "
+            > foo > [0] > bar = 3\
+        ",
+        warnings: ["1:1: value is overridden by the assignment at 3:1 [overridden-assignment]"]
+    }
+
+    ok! {
+        DuplicateAssignment::FirstWins,
+        "
+            > foo > bar = 1
+
+            > foo > bar = 3
+
+            > foo > bar > baz = 4
+        " => "
+            > foo > bar = 1\
+        ",
+        warnings: [
+            "3:1: assignment is ignored, the path already has a value assigned at 1:1 [overridden-assignment]",
+            "5:1: assignment is ignored, the path already has a value assigned at 1:1 [overridden-assignment]"
+        ]
+    }
+
+    ok! {
+        DuplicateAssignment::DeepMerge(Box::new(|_: &Path, _, other| Ok(other))),
+        "
+            > foo = [1, 2, 3]
+
+            > foo = [4]
+        " => "
+            > foo = [4, 2, 3]\
+        ",
+        warnings: ["1:1: value is merged with the assignment at 3:1 [overridden-assignment]"]
+    }
+
+    let options = ParseOptions {
+        duplicate_assignment: DuplicateAssignment::DeepMerge(Box::new(ErrorOnConflict)),
+        ..Default::default()
+    };
+
+    let actual = konfig::parser::parse_with_options(
+        indoc! {"
+            > foo = [1, 2, 3]
+
+            > foo = [4]
+        "},
+        options,
+    )
+    .unwrap_err()
+    .to_string();
+
+    assert_eq!(
+        actual,
+        indoc! {"
+             --> 3:1
+              |
+            3 | > foo = [4]␊
+              | ^----------^
+              |
+              = merge conflict at path: > foo > [0]"}
+    );
+
+    // NOTE: template definitions are checked for duplicates the same way.
+    let input = indoc! {"
+        > &w > t = 4
+
+        > &w > t = 5
+
+        > a = *w
+    "};
+
+    for (policy, expected, warning) in [
+        (
+            DuplicateAssignment::LastWins,
+            5,
+            "1:1: value is overridden by the assignment at 3:1 [overridden-assignment]",
+        ),
+        (
+            DuplicateAssignment::FirstWins,
+            4,
+            "3:1: assignment is ignored, the path already has a value assigned at 1:1 [overridden-assignment]",
+        ),
+    ] {
+        let options = ParseOptions {
+            duplicate_assignment: policy,
+            ..Default::default()
+        };
+
+        let parsed = konfig::parser::parse_with_options(input, options).unwrap();
+        let warnings = parsed.warnings.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        assert_eq!(parsed.value["a"]["t"], Value::UInt(expected));
+        assert_eq!(warnings, [warning]);
+    }
+
+    let actual = konfig::parser::parse_with_options(input, Default::default())
+        .unwrap_err()
+        .to_string();
+
+    assert!(actual.starts_with(" --> 3:1"));
+    assert!(actual.ends_with("= the path already has a value assigned"));
+}
+
+#[test]
+fn lints() {
+    use konfig::parser::{Lint, LintLevel, ParseOptions};
+
+    macro_rules! ok {
+        ($input:expr => [$($warning:expr),*]) => {{
+            let parsed = konfig::parser::parse_with_options(indoc!($input), Default::default())
+                .unwrap();
+
+            let warnings = parsed.warnings.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+            assert_eq!(warnings, vec![$($warning),*] as Vec<&str>);
+        }};
+    }
+
+    ok! {
+        "
+            > foo = 058
+
+            > bar = -007
+
+            > baz = [0, 10, 0x0A, 01.5]
+        " => [
+            "1:9: decimal number with leading zeros [leading-zeros]",
+            "3:10: decimal number with leading zeros [leading-zeros]"
+        ]
+    }
+
+    ok! {
+        "
+            Prose = fine, as it's not a path.
+
+            foo > bar = 42
+
+            ```
+            foo > bar = 42
+            ```
+
+            > foo > bar = 42
+        " => ["3:1: docs look like a path assignment without leading `>` [docs-look-like-path]"]
+    }
+
+    ok! {
+        "
+            > foo > [0] > ports = [80, 443]
+
+            > foo > [0] > hosts = []
+
+            > foo > [1] > ports = []
+
+            > foo > [1] > hosts = []
+        " => ["3:23: empty array next to populated sibling arrays [empty-array]"]
+    }
+
+    ok! {
+        "
+            > [\"foo \"] = 1
+
+            > [' bar'] = 2
+        " => ["1:3: map key has trailing whitespace [map-key-trailing-whitespace]"]
+    }
+
+    ok! {
+        "
+            > foo = 1
+
+            ```konfig
+            > bar = 2
+        " => ["3:1: code fence is not closed [unclosed-code-fence]"]
+    }
+
+    let mut options = ParseOptions::default();
+
+    options
+        .lint_levels
+        .insert(Lint::LeadingZeros, LintLevel::Deny);
+    options
+        .lint_levels
+        .insert(Lint::EmptyArray, LintLevel::Allow);
+
+    let input = indoc! {"
+        > foo = [1]
+
+        > bar = []
+
+        > baz = 058
+    "};
+
+    let actual = konfig::parser::parse_with_options(input, options)
+        .unwrap_err()
+        .to_string();
+
+    assert_eq!(
+        actual,
+        indoc! {"
+             --> 5:9
+              |
+            5 | > baz = 058
+              |         ^-^
+              |
+              = decimal number with leading zeros [leading-zeros]"}
+    );
+
+    assert_eq!(Lint::from_code("empty-array"), Some(Lint::EmptyArray));
+    assert_eq!(Lint::from_code("unknown"), None);
+}
+
+#[test]
+fn crlf_and_bom() {
+    let input =
+        "\u{feff}# Docs\r\n\r\n> foo = 1\r\n\r\n> bar = \r\n```\r\nline1\r\nline2\r\n```\r\n";
+    let value = parse(input);
+
+    ok! { input =>
+        Struct({
+            "foo": UInt(1),
+            "bar": String("line1\nline2")
+        })
+    }
+
+    assert_eq!(value["foo"].lexical_info().docs_before, "# Docs\n\n");
+}
+
 #[test]
 fn empty_input() {
     err! {
@@ -976,3 +1405,402 @@ fn lexical_info_docs() {
         include_str!("./data/expected/doc_chunks/7.md")
     );
 }
+
+#[test]
+fn detached_exprs_only() {
+    use konfig::parser::ParseOptions;
+
+    let parse = |input: &str, profiles: &[&str]| {
+        let options = ParseOptions {
+            active_profiles: profiles.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        };
+
+        konfig::parser::parse_with_options(input, options).map(|parsed| parsed.value)
+    };
+
+    let input = "# Production\n\n> @prod > a = 1\n\n> @prod > b = [1, 2]\n\nTrailing docs.\n";
+
+    let value = parse(input, &["prod"]).unwrap();
+
+    assert_eq!(
+        AstValue::from(value.clone()),
+        ron::from_str::<AstValue>(r#"Struct({ "a": UInt(1), "b": Sequence([UInt(1), UInt(2)]) })"#)
+            .unwrap()
+    );
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    let value = parse(input, &[]).unwrap();
+
+    assert_eq!(
+        AstValue::from(value.clone()),
+        AstValue::Struct(Default::default())
+    );
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    let input = "> &worker > threads = 4\n\n> &worker > name = \"w\"";
+    let value = parse(input, &[]).unwrap();
+
+    assert_eq!(
+        AstValue::from(value.clone()),
+        AstValue::Struct(Default::default())
+    );
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    assert!(parse("Docs only.\n", &["prod"])
+        .unwrap_err()
+        .to_string()
+        .ends_with("konfig should contain some expressions"));
+}
+
+#[test]
+fn profiles() {
+    use konfig::parser::{DetachedExprKind, ParseOptions};
+
+    let input = indoc! {"
+        # Database
+
+        > @prod > db > host = \"db.example.com\"
+
+        > db > host = \"localhost\"
+
+        > db > pool = [1, 2]
+
+        Staging uses a bigger pool.
+
+        > @staging > db > pool > [2] = 3
+
+        > @prod > db > tls > `Enabled` > cert = \"cert.pem\"
+
+        > db > tls = `Disabled`
+
+        > @prod = 42\
+    "};
+
+    let parse = |profiles: &[&str]| {
+        let options = ParseOptions {
+            active_profiles: profiles.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        };
+
+        konfig::parser::parse_with_options(input, options).map(|parsed| parsed.value)
+    };
+
+    let value = parse(&[]).unwrap();
+
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    assert_eq!(
+        AstValue::from(value),
+        ron::from_str::<AstValue>(
+            r#"Struct({
+                "db": Struct({
+                    "host": String("localhost"),
+                    "pool": Sequence([UInt(1), UInt(2)]),
+                    "tls": UnitVariant("Disabled"),
+                }),
+            })"#
+        )
+        .unwrap()
+    );
+
+    assert_eq!(
+        AstValue::from(parse(&["staging", "dev"]).unwrap()),
+        ron::from_str::<AstValue>(
+            r#"Struct({
+                "db": Struct({
+                    "host": String("localhost"),
+                    "pool": Sequence([UInt(1), UInt(2), UInt(3)]),
+                    "tls": UnitVariant("Disabled"),
+                }),
+            })"#
+        )
+        .unwrap()
+    );
+
+    // NOTE: expressions of the active profiles are kept along with the values they override, so
+    // the konfig is serialized back unchanged.
+    let value = parse(&["staging", "prod"]).unwrap();
+
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    assert_eq!(AstValue::from(value), AstValue::UInt(42));
+
+    let input = input.replace("\n\n> @prod = 42", "");
+    let options = ParseOptions {
+        active_profiles: ["prod".to_string()].into(),
+        ..Default::default()
+    };
+
+    let mut value = konfig::parser::parse_with_options(&input, options)
+        .unwrap()
+        .value;
+
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    let host = value["db"]["host"].lexical_info();
+
+    assert_eq!(
+        host.exprs_before[0].kind,
+        DetachedExprKind::Profile("prod".into())
+    );
+    assert_eq!(host.exprs_before[0].docs_before, "# Database\n\n");
+    assert_eq!(
+        host.profile_override.as_ref().unwrap().base,
+        Some(Value::String("localhost".into()))
+    );
+
+    assert_eq!(
+        AstValue::from(value.clone()),
+        ron::from_str::<AstValue>(
+            r#"Struct({
+                "db": Struct({
+                    "host": String("db.example.com"),
+                    "pool": Sequence([UInt(1), UInt(2)]),
+                    "tls": Variant("Enabled", Struct({ "cert": String("cert.pem") })),
+                }),
+            })"#
+        )
+        .unwrap()
+    );
+
+    // NOTE: values changed after parsing are written instead of the overridden ones.
+    *value["db"]["host"].as_value_mut() = Value::String("db.internal".into());
+
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input.replace("\"localhost\"", "\"db.internal\"")
+    );
+
+    // NOTE: values that are only defined by the active profiles are not written.
+    let input = "> db > pool = [1]\n\n> @prod > db > pool > [1] = 2\n\n> @prod > cache > size = 8";
+
+    let options = ParseOptions {
+        active_profiles: ["prod".to_string()].into(),
+        ..Default::default()
+    };
+
+    let value = konfig::parser::parse_with_options(input, options)
+        .unwrap()
+        .value;
+
+    assert_eq!(value["db"]["pool"][1], Value::UInt(2));
+    assert_eq!(value["cache"]["size"], Value::UInt(8));
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    let options = ParseOptions {
+        active_profiles: ["prod".to_string()].into(),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        konfig::parser::parse_with_options("> db = [1]\n\n> @prod > db > [3] = 2", options)
+            .unwrap_err()
+            .to_string(),
+        indoc! {"
+             --> 3:1
+              |
+            3 | > @prod > db > [3] = 2
+              | ^--------------------^
+              |
+              = sequence index is out of bounds at path: > db > [3]"}
+    );
+
+    err! {
+        "> @prod >= 1" =>
+        " --> 1:10
+          |
+        1 | > @prod >= 1
+          |          ^---
+          |
+          = expected path item"
+    }
+
+    assert!(konfig::parse_expr("> @prod > a = 1")
+        .unwrap_err()
+        .to_string()
+        .contains("profiles are not allowed in a single expression"));
+}
+
+#[test]
+fn templates() {
+    use konfig::parser::DetachedExprKind;
+    use konfig::value::PathItem;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Worker {
+        name: String,
+        threads: u32,
+        queues: Vec<String>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        workers: Vec<Worker>,
+        reporter: Worker,
+    }
+
+    let input = indoc! {"
+        # Workers
+
+        > &worker > name = \"worker\"
+
+        > &worker > threads = 4
+
+        > &worker > queues = [\"default\"]
+
+        > &reporter = *worker
+
+        > &reporter > queues = [\"reports\"]
+
+        > workers > [0] = *worker
+
+        The second worker has more threads.
+
+        > workers > [1] = *worker
+
+        > workers > [1] > threads = 8
+
+        > workers > [1] > name = \"second\"
+
+        > reporter = *reporter\
+    "};
+
+    let value = parse(input);
+
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    // NOTE: template definitions are kept along with the first value after them.
+    let lexical_info = value["workers"][0].lexical_info();
+    let definitions = &lexical_info.exprs_before;
+
+    assert_eq!(lexical_info.docs_before, "");
+    assert_eq!(definitions.len(), 5);
+    assert_eq!(definitions[0].docs_before, "# Workers\n\n");
+    assert_eq!(
+        definitions[0].kind,
+        DetachedExprKind::Template("worker".into())
+    );
+    assert_eq!(
+        definitions[0].path,
+        [PathItem::StructFieldName("name".into())]
+    );
+    assert_eq!(definitions[0].value, Value::String("worker".into()));
+    assert_eq!(
+        definitions[3].kind,
+        DetachedExprKind::Template("reporter".into())
+    );
+    assert!(definitions[3].path.is_empty());
+    assert_eq!(
+        definitions[3]
+            .value
+            .lexical_info()
+            .template
+            .as_ref()
+            .unwrap()
+            .name,
+        "worker"
+    );
+
+    let worker = |name: &str, threads, queue: &str| Worker {
+        name: name.into(),
+        threads,
+        queues: vec![queue.into()],
+    };
+
+    assert_eq!(
+        konfig::from_value::<Config>(value.clone().into_value()).unwrap(),
+        Config {
+            workers: vec![
+                worker("worker", 4, "default"),
+                worker("second", 8, "default")
+            ],
+            reporter: worker("worker", 4, "reports"),
+        }
+    );
+
+    let mut value = value;
+
+    *value["workers"][1]["threads"].as_value_mut() = Value::UInt(16);
+    *value["workers"][0]["queues"][0].as_value_mut() = Value::String("high".into());
+
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+            .replace(
+                "> workers > [0] = *worker\n",
+                "> workers > [0] = *worker\n\n> workers > [0] > queues > [0] = \"high\"\n"
+            )
+            .replace("threads = 8", "threads = 16")
+    );
+
+    // NOTE: values with removed template values are written in the expanded form.
+    value["reporter"]
+        .as_value_mut()
+        .as_struct_mut()
+        .unwrap()
+        .shift_remove("queues");
+
+    assert!(konfig::serialize(&value, Default::default())
+        .unwrap()
+        .ends_with("> reporter > name = \"worker\"\n\n> reporter > threads = 4"));
+
+    err! {
+        "> a = *worker" =>
+        " --> 1:7
+          |
+        1 | > a = *worker
+          |       ^-----^
+          |
+          = unknown template `worker`"
+    }
+
+    err! {
+        "> &a > b = 1\n\n> c = *a\n\n> c > [0] = 2" =>
+        " --> 5:7
+          |
+        5 | > c > [0] = 2
+          |       ^-^
+          |
+          = path item has incompatible type with the previously specified values"
+    }
+
+    let input = "> a = 1\n\nDocs.\n\n> &b = [1, 2]\n\nTrailing docs.\n";
+    let value = parse(input);
+
+    assert_eq!(value["a"].lexical_info().exprs_after.len(), 1);
+    assert_eq!(value["a"].lexical_info().docs_after, "Trailing docs.\n");
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    assert!(konfig::parse_expr("> &a > b = 1")
+        .unwrap_err()
+        .to_string()
+        .contains("template definitions are not allowed in a single expression"));
+}
@@ -0,0 +1,80 @@
+use indoc::indoc;
+use konfig::loader::Loader;
+use konfig::schema::{JsonSchemaValidator, Schema, Violation};
+use konfig::{json_schema_for, JsonSchema};
+
+const SCHEMA: &str = indoc! {"
+    > type = `Struct`
+
+    > fields > server > type = `Struct`
+
+    > fields > server > fields > host > type = `String`
+
+    > fields > server > fields > port > type = `Integer`
+
+    > fields > server > fields > port > min = 1
+
+    > fields > server > fields > port > max = 65535
+
+    > fields > features > type = `Sequence`
+
+    > fields > features > items > type = `String`
+
+    > fields > features > max_items = 1
+"};
+
+fn describe(input: &str, violations: Vec<Violation>) -> Vec<String> {
+    violations
+        .iter()
+        .map(|violation| {
+            let (line, col) = violation.line_col(input).unwrap();
+
+            match violation.source.as_deref() {
+                Some(source) => format!("{source}:{line}:{col}: {violation}"),
+                None => format!("{line}:{col}: {violation}"),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn validate_loaded_value() {
+    let schema = Schema::load(SCHEMA).unwrap();
+
+    let defaults = "> server > host = \"localhost\"\n\n> server > port = 80";
+    let site = "> server > port = 0\n\n> features = [\"metrics\", 42]";
+
+    let value = Loader::new()
+        .konfig("defaults", defaults)
+        .konfig("site", site)
+        .load_value()
+        .unwrap()
+        .into_cell();
+
+    assert_eq!(
+        describe(site, schema.validate(&value)),
+        [
+            "site:1:19: 0 is less than the minimum of 1 at path: > server > port",
+            "site:3:14: sequence should have at most 1 items, found 2 at path: > features",
+            "site:3:14: invalid type: integer, expected string at path: > features > [1]",
+        ]
+    );
+}
+
+#[test]
+fn validate_against_json_schema() {
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    let validator = JsonSchemaValidator::new(json_schema_for::<Server>());
+    let input = "> host = \"localhost\"\n\n> port = -1";
+
+    assert_eq!(
+        describe(input, validator.check(input).unwrap()),
+        ["3:10: -1 is less than the minimum of 0 at path: > port"]
+    );
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::approx_constant, clippy::enum_variant_names)]
+
 use indoc::indoc;
 use konfig::error::Error;
 use konfig::Value;
@@ -103,4 +103,250 @@ fn docs_escaping() {
         <span>&gt;</span>
         "
     }
+
+    ok! {
+        before: "
+            Example:
+
+            ```konfig
+            > foo = 42
+            ```
+
+            > hello
+        ",
+        after: "
+        ~~~
+        > bye
+        ~~~",
+        expected: "
+        Example:
+
+        ```konfig
+        > foo = 42
+        ```
+
+        <span>&gt;</span> hello
+        > = null
+
+        ~~~
+        > bye
+        ~~~"
+    }
+
+    ok! {
+        before: "
+            > @user said hi
+            > &copy; 2024
+
+            > hello
+        ",
+        after: "",
+        expected: "
+        <span>&gt;</span> @user said hi
+        <span>&gt;</span> &copy; 2024
+
+        <span>&gt;</span> hello
+        > = null"
+    }
+
+    // NOTE: docs that look like profile expressions or template definitions are still docs.
+    let mut value = konfig::parse("> a = 1").unwrap();
+
+    value["a"].lexical_info_mut().docs_before = "> @user said hi\n\n> &b = 2\n\n".into();
+
+    let serialized = konfig::serialize(&value, Default::default()).unwrap();
+    let reparsed = konfig::parse(&serialized).unwrap();
+
+    assert_eq!(reparsed, value);
+    assert!(reparsed["a"].lexical_info().exprs_before.is_empty());
+}
+
+#[test]
+fn embedded_blocks() {
+    let host = indoc! {r#"
+        # Configuration
+
+        ```konfig
+        > server > host = "localhost"
+
+        Port to listen on.
+
+        > server > port = 8080
+        ```
+
+        Logging settings.
+
+        ```konfig
+        > log_level = "info"
+        ```
+
+        Trailing prose.
+    "#};
+
+    let mut parsed = konfig::parse_embedded(host).unwrap();
+    let serialized = konfig::serialize_embedded(host, &parsed, Default::default()).unwrap();
+
+    assert_eq!(serialized, host);
+
+    let Value::Struct(root) = parsed.as_value_mut() else {
+        panic!("expected struct");
+    };
+
+    *root["log_level"].as_value_mut() = Value::String("debug".into());
+
+    let Value::Struct(server) = root["server"].as_value_mut() else {
+        panic!("expected struct");
+    };
+
+    server.insert("workers".into(), Value::UInt(4).into_cell());
+    root.insert("timeout".into(), Value::UInt(30).into_cell());
+
+    let serialized = konfig::serialize_embedded(host, &parsed, Default::default()).unwrap();
+
+    assert_eq!(
+        serialized,
+        indoc! {r#"
+            # Configuration
+
+            ```konfig
+            > server > host = "localhost"
+
+            Port to listen on.
+
+            > server > port = 8080
+
+            > server > workers = 4
+            ```
+
+            Logging settings.
+
+            ```konfig
+            > log_level = "debug"
+
+            > timeout = 30
+            ```
+
+            Trailing prose.
+        "#}
+    );
+
+    let value = Value::Struct(
+        [("foo".into(), Value::UInt(1).into_cell())]
+            .into_iter()
+            .collect(),
+    )
+    .into_cell();
+
+    assert_eq!(
+        konfig::serialize_embedded("# Title", &value, Default::default()).unwrap(),
+        indoc! {"
+            # Title
+
+            ```konfig
+            > foo = 1
+            ```
+        "}
+    );
+}
+
+#[test]
+fn line_endings() {
+    use konfig::serializer::formatting::{FormattingOptions, LineEnding};
+
+    let src = include_str!("./data/doc_parsing.konfig.md").replace('\n', "\r\n");
+    let parsed = konfig::parse(&format!("\u{feff}{src}")).unwrap();
+
+    let formatting = FormattingOptions {
+        line_ending: LineEnding::detect(&src),
+        ..Default::default()
+    };
+
+    assert_eq!(formatting.line_ending, LineEnding::CrLf);
+    assert_eq!(konfig::serialize(&parsed, formatting).unwrap(), src);
+
+    assert_eq!(
+        konfig::serialize(&parsed, Default::default()).unwrap(),
+        include_str!("./data/doc_parsing.konfig.md")
+    );
+
+    assert_eq!(LineEnding::detect("foo\nbar\r\n"), LineEnding::Lf);
+    assert_eq!(LineEnding::CrLf.apply("a\nb\r\nc"), "a\r\nb\r\nc");
+    assert_eq!(LineEnding::Lf.apply("a\nb\r\nc"), "a\nb\nc");
+}
+
+#[test]
+fn unclosed_code_fence_in_docs() {
+    let mut value = konfig::parse("> a = 1\n\n> b = 2").unwrap();
+
+    value["a"].lexical_info_mut().docs_before = "Example:\n```\n> x = 1\n\n".into();
+
+    let serialized = konfig::serialize(&value, Default::default()).unwrap();
+
+    // NOTE: docs are kept as is, fences that are not closed don't start code blocks, so the lines
+    // after them are escaped.
+    assert_eq!(
+        serialized,
+        "Example:\n```\n<span>&gt;</span> x = 1\n\n> a = 1\n\n> b = 2"
+    );
+
+    let reparsed = konfig::parse(&serialized).unwrap();
+
+    assert_eq!(reparsed["b"].as_u64(), Some(2));
+
+    let value = konfig::parse("> a = 1\n\n```\n> b = 2").unwrap();
+
+    assert_eq!(value["b"].as_u64(), Some(2));
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        "> a = 1\n\n```\n> b = 2"
+    );
+}
+
+#[test]
+fn modified_template_instances() {
+    let mut value = konfig::parse("> &port = 80\n\n> p = *port").unwrap();
+
+    *value["p"].as_value_mut() = Value::UInt(81);
+
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        "> &port = 80\n\n> p = 81"
+    );
+
+    let input = indoc! {"
+        > &tls > `Enabled` > cert = \"cert.pem\"
+
+        > &server > tls = *tls
+
+        > a = *tls
+
+        > b = *server"};
+
+    let mut value = konfig::parse(input).unwrap();
+
+    assert_eq!(
+        konfig::serialize(&value, Default::default()).unwrap(),
+        input
+    );
+
+    let disabled = || Value::Variant("Disabled".into(), Value::Null.into_cell());
+
+    // NOTE: instances with other variants are written in the expanded form, while nested values
+    // with other variants are overrides.
+    *value["a"].as_value_mut() = disabled();
+    *value["b"]["tls"].as_value_mut() = disabled();
+
+    let serialized = konfig::serialize(&value, Default::default()).unwrap();
+
+    assert_eq!(
+        serialized,
+        input
+            .replace("> a = *tls", "> a > `Disabled` = null")
+            .replace(
+                "> b = *server",
+                "> b = *server\n\n> b > tls > `Disabled` = null"
+            )
+    );
+
+    assert_eq!(konfig::parse(&serialized).unwrap(), value);
 }